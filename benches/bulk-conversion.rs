@@ -0,0 +1,75 @@
+//!
+//! Measure requests that convert many java objects at once.
+//!
+//! The benchmark starts the JMX test server with many additional MBeans registered
+//! and times `query_names`, `get_mbean_info` and `snapshot_mbean` requests against it.
+//!
+//! Run with `cargo bench --bench bulk-conversion`.
+//!
+extern crate jmx;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanClientTrait;
+
+
+static BEANS: usize = 5000;
+static ITERATIONS: u32 = 20;
+static JMX_PORT: u16 = 1632;
+
+
+fn main() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .arg(BEANS.to_string())
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(5));
+
+    run_bench();
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_bench() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address)
+        .expect("Failed to connect to the JMX test server");
+
+    let names = client.query_names("BENCH:*", "").unwrap();
+    assert_eq!(names.len(), BEANS);
+    bench(&format!("query_names ({} names)", BEANS), || {
+        client.query_names("BENCH:*", "").unwrap();
+    });
+    bench("get_mbean_info", || {
+        client.get_mbean_info("java.lang:type=Runtime").unwrap();
+    });
+    bench("snapshot_mbean", || {
+        client.snapshot_mbean("java.lang:type=Runtime").unwrap();
+    });
+}
+
+fn bench<F>(name: &str, mut request: F)
+    where F: FnMut(),
+{
+    // Warm up the JVM before measuring.
+    request();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        request();
+    }
+    let average = start.elapsed() / ITERATIONS;
+    println!("{:<30} {:>10.3} ms/iter", name, average.as_secs_f64() * 1000.0);
+}
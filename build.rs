@@ -0,0 +1,36 @@
+//! Deploy the bundled Java helper jar next to the j4rs jars.
+//!
+//! `JvmBuilder::build` adds all jars in the `jassets` directory next to the executables
+//! to the classpath, the same directory the j4rs build script deploys its own jar to.
+//! Deploying is best effort: the crate embeds the jar and loads it by itself when
+//! the helper is not on the classpath.
+//!
+//! The jar is compiled for Java 8 from `java/net/spogliani/jmx/Helper.java` (see README).
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+
+static HELPER_JAR: &str = "java/jmx-rust-helper.jar";
+
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", HELPER_JAR);
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set by cargo"));
+    let version = env::var("CARGO_PKG_VERSION").expect("CARGO_PKG_VERSION not set by cargo");
+
+    // OUT_DIR is `<target>/<profile>/build/<package>/out`, executables are in `<target>/<profile>`.
+    let jassets = match out_dir.ancestors().nth(3) {
+        None => return,
+        Some(profile) => profile.join("jassets"),
+    };
+    let deployed = jassets.join(format!("jmx-rust-helper-{}.jar", version));
+    // Deploy again if the jar is removed, for example when j4rs deploys its jars.
+    println!("cargo:rerun-if-changed={}", deployed.display());
+    let result = fs::create_dir_all(&jassets).and_then(|_| fs::copy(HELPER_JAR, &deployed));
+    if let Err(error) = result {
+        println!(
+            "cargo:warning=could not deploy {} to {}: {}", HELPER_JAR, deployed.display(), error
+        );
+    }
+}
@@ -0,0 +1,715 @@
+package net.spogliani.jmx;
+
+import java.lang.reflect.Array;
+import java.math.BigDecimal;
+import java.util.ArrayList;
+import java.util.Base64;
+import java.util.Collection;
+import java.util.HashMap;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Map;
+import java.util.Set;
+import java.util.TreeMap;
+import java.util.concurrent.BlockingQueue;
+import java.util.concurrent.ConcurrentHashMap;
+import java.util.concurrent.LinkedBlockingQueue;
+import java.util.concurrent.TimeUnit;
+import java.util.concurrent.atomic.AtomicBoolean;
+import java.util.concurrent.atomic.AtomicLong;
+import java.util.function.BiFunction;
+
+import javax.management.Attribute;
+import javax.management.AttributeList;
+import javax.management.Descriptor;
+import javax.management.MBeanAttributeInfo;
+import javax.management.MBeanInfo;
+import javax.management.MBeanOperationInfo;
+import javax.management.MBeanParameterInfo;
+import javax.management.MBeanServerConnection;
+import javax.management.MBeanServerNotification;
+import javax.management.Notification;
+import javax.management.NotificationBroadcaster;
+import javax.management.NotificationFilterSupport;
+import javax.management.NotificationListener;
+import javax.management.ObjectName;
+import javax.management.openmbean.CompositeData;
+import javax.management.openmbean.CompositeDataSupport;
+import javax.management.openmbean.CompositeType;
+import javax.management.openmbean.OpenType;
+import javax.management.openmbean.TabularData;
+import javax.management.openmbean.TabularDataSupport;
+import javax.management.openmbean.TabularType;
+import javax.management.remote.JMXConnectionNotification;
+import javax.management.remote.JMXConnector;
+import javax.management.remote.JMXConnectorFactory;
+import javax.management.remote.JMXServiceURL;
+
+
+/**
+ * Java side of the jmx crate.
+ *
+ * Each rust client creates an instance of this class and calls {@link #apply} with
+ * the name of an operation and an array of arguments.
+ * Operations convert JMX objects into lists, maps and primitives in bulk so that
+ * each request needs a single JNI call and a single serialisation of the result.
+ *
+ * Instances created by {@link #addListener} are notification listeners: they queue the
+ * notifications they receive until the rust code polls them.
+ * Listeners belong to the client that added them and fail once its connection is closed.
+ *
+ * This class must not have inner classes: when it is not on the classpath it is loaded
+ * from a class loader that is closed right after loading it.
+ */
+public final class Helper implements BiFunction<Object, Object, Object>, NotificationListener {
+  /** Notifications queued by a listener, more are dropped and the next poll fails. */
+  private static final int QUEUE_CAPACITY = 10000;
+  private static final AtomicLong NEXT_LISTENER = new AtomicLong();
+
+  /** Notification listeners of a client, by the ID returned to the rust code. */
+  private final Map<Long, Helper> listeners = new ConcurrentHashMap<>();
+
+  /** Connection, MBeans and queued notifications of a listener. */
+  private final MBeanServerConnection connection;
+  private final List<ObjectName> emitters = new ArrayList<>();
+  private final BlockingQueue<Notification> notifications =
+      new LinkedBlockingQueue<>(QUEUE_CAPACITY);
+
+  /** Set when notifications were dropped, until the next poll reports it. */
+  private final AtomicBoolean lost = new AtomicBoolean();
+
+  /** Why the connection of a client and its listeners can't be used anymore. */
+  private volatile String closed;
+
+  public Helper() {
+    this(null);
+  }
+
+  private Helper(MBeanServerConnection connection) {
+    this.connection = connection;
+  }
+
+  @Override
+  public Object apply(Object operation, Object arguments) {
+    Object[] args = (Object[]) arguments;
+    try {
+      switch ((String) operation) {
+        case "addListener":
+          return addListener(
+              (MBeanServerConnection) args[0], (String) args[1], (List<?>) args[2]
+          );
+        case "attributeInfo":
+          return attributeInfo((MBeanAttributeInfo) args[0]);
+        case "close":
+          close("the client was dropped");
+          return Boolean.TRUE;
+        case "connect":
+          return connect((JMXServiceURL) args[0], (String) args[1], (String) args[2]);
+        case "getAttribute":
+          return getAttribute((MBeanServerConnection) args[0], (String) args[1], (String) args[2]);
+        case "invoke":
+          return invoke(
+              (MBeanServerConnection) args[0], (String) args[1], (String) args[2],
+              (List<?>) args[3]
+          );
+        case "mbeanInfo":
+          return mbeanInfo((MBeanServerConnection) args[0], (String) args[1]);
+        case "mbeanInfoFrom":
+          return mbeanInfo((MBeanInfo) args[0]);
+        case "pollListener":
+          return pollListener(((Number) args[0]).longValue(), ((Number) args[1]).longValue());
+        case "queryNames":
+          return queryNames((MBeanServerConnection) args[0], (String) args[1], (String) args[2]);
+        case "removeListener":
+          removeListener(((Number) args[0]).longValue());
+          return Boolean.TRUE;
+        case "setAttribute":
+          setAttribute(
+              (MBeanServerConnection) args[0], (String) args[1], (String) args[2], args[3]
+          );
+          return Boolean.TRUE;
+        case "snapshot":
+          return snapshot((MBeanServerConnection) args[0], (String) args[1]);
+        case "watch":
+          ((JMXConnector) args[0]).addConnectionNotificationListener(this, null, null);
+          return Boolean.TRUE;
+        default:
+          throw new IllegalArgumentException("unknown helper operation " + operation);
+      }
+    } catch (RuntimeException e) {
+      throw e;
+    } catch (Exception e) {
+      throw new RuntimeException(e);
+    }
+  }
+
+  /**
+   * Queue the notifications of a listener, or track the connection of a client.
+   *
+   * Listeners have a connection, clients receive the notifications of their connector.
+   */
+  @Override
+  public void handleNotification(Notification notification, Object handback) {
+    if (connection != null) {
+      if (!notifications.offer(notification)) {
+        lost.set(true);
+      }
+      return;
+    }
+    String type = notification.getType();
+    if (JMXConnectionNotification.CLOSED.equals(type)) {
+      close("the connection to the server was closed");
+    } else if (JMXConnectionNotification.FAILED.equals(type)) {
+      close("the connection to the server failed");
+    } else if (JMXConnectionNotification.NOTIFS_LOST.equals(type)) {
+      for (Helper listener : listeners.values()) {
+        listener.lost.set(true);
+      }
+    }
+  }
+
+  /**
+   * Add a listener to the MBeans matching a name or pattern that emit notifications.
+   *
+   * Only notifications with a type starting with one of the given types are received,
+   * or all notifications if there are none.
+   */
+  private long addListener(MBeanServerConnection connection, String name, List<?> types)
+      throws Exception {
+    if (closed != null) {
+      throw new IllegalStateException(closed);
+    }
+    NotificationFilterSupport filter = null;
+    if (!types.isEmpty()) {
+      filter = new NotificationFilterSupport();
+      for (Object type : types) {
+        filter.enableType(String.valueOf(type));
+      }
+    }
+    Helper listener = new Helper(connection);
+    String broadcaster = NotificationBroadcaster.class.getName();
+    try {
+      for (ObjectName emitter : connection.queryNames(new ObjectName(name), null)) {
+        if (connection.isInstanceOf(emitter, broadcaster)) {
+          connection.addNotificationListener(emitter, listener, filter, null);
+          listener.emitters.add(emitter);
+        }
+      }
+    } catch (Exception e) {
+      listener.removeFromEmitters();
+      throw e;
+    }
+    if (listener.emitters.isEmpty()) {
+      throw new IllegalArgumentException("no mbean matching " + name + " emits notifications");
+    }
+    long id = NEXT_LISTENER.incrementAndGet();
+    listeners.put(id, listener);
+    return id;
+  }
+
+  /**
+   * Fail all further polls of a client's listeners with the given reason.
+   *
+   * Listeners are removed from their MBeans unless the connection is already gone.
+   */
+  private void close(String reason) {
+    boolean open = closed == null;
+    closed = reason;
+    for (Helper listener : listeners.values()) {
+      listener.closed = reason;
+      if (open) {
+        listener.removeFromEmitters();
+      }
+    }
+  }
+
+  /** Convert an MBeanAttributeInfo into the shape of the rust MBeanAttribute. */
+  private static Map<String, Object> attributeInfo(MBeanAttributeInfo info) {
+    Map<String, Object> attribute = new LinkedHashMap<>();
+    attribute.put("description", orEmpty(info.getDescription()));
+    attribute.put("descriptor", descriptor(info.getDescriptor()));
+    attribute.put("is_is", info.isIs());
+    attribute.put("is_readable", info.isReadable());
+    attribute.put("is_writable", info.isWritable());
+    attribute.put("name", info.getName());
+    attribute.put("type_name", orEmpty(info.getType()));
+    return attribute;
+  }
+
+  /** Connect to a JMX server, authenticating with the given credentials. */
+  private static JMXConnector connect(JMXServiceURL url, String username, String password)
+      throws Exception {
+    Map<String, Object> environment = new HashMap<>();
+    environment.put(JMXConnector.CREDENTIALS, new String[] {username, password});
+    return JMXConnectorFactory.connect(url, environment);
+  }
+
+  /** Convert the fields of a descriptor into strings. */
+  private static Map<String, String> descriptor(Descriptor descriptor) {
+    Map<String, String> fields = new TreeMap<>();
+    if (descriptor != null) {
+      descriptor(descriptor, "", fields);
+    }
+    return fields;
+  }
+
+  /**
+   * Convert the fields of a descriptor into strings, prefixing their names.
+   *
+   * Fields holding descriptors are flattened: their fields are named after the
+   * outer field, a dot and the inner field.
+   */
+  private static void descriptor(
+      Descriptor descriptor, String prefix, Map<String, String> fields
+  ) {
+    for (String name : descriptor.getFieldNames()) {
+      Object value = descriptor.getFieldValue(name);
+      if (value instanceof Descriptor) {
+        descriptor((Descriptor) value, prefix + name + ".", fields);
+      } else {
+        fields.put(prefix + name, String.valueOf(convert(value, true)));
+      }
+    }
+  }
+
+  /**
+   * Convert a value decoded from JSON into an instance of the named java type.
+   *
+   * Strings are parsed into numbers, booleans and object names and lists are converted
+   * into arrays. Numbers are converted only if they fit the type without loss.
+   */
+  private static Object coerce(Object value, String type) throws Exception {
+    if (value == null) {
+      return null;
+    }
+    String text = value.toString();
+    switch (type) {
+      case "boolean":
+      case "java.lang.Boolean":
+        if (!text.equals("true") && !text.equals("false")) {
+          throw new IllegalArgumentException("not a boolean: " + text);
+        }
+        return Boolean.valueOf(text);
+      case "byte":
+      case "java.lang.Byte":
+        return new BigDecimal(text).byteValueExact();
+      case "char":
+      case "java.lang.Character":
+        if (text.length() != 1) {
+          throw new IllegalArgumentException("not a single character: " + text);
+        }
+        return text.charAt(0);
+      case "double":
+      case "java.lang.Double":
+        return Double.valueOf(text);
+      case "float":
+      case "java.lang.Float":
+        return Float.valueOf(text);
+      case "int":
+      case "java.lang.Integer":
+        return new BigDecimal(text).intValueExact();
+      case "long":
+      case "java.lang.Long":
+        return new BigDecimal(text).longValueExact();
+      case "short":
+      case "java.lang.Short":
+        return new BigDecimal(text).shortValueExact();
+      case "java.lang.Object":
+      case "java.lang.String":
+        return text;
+      case "java.math.BigDecimal":
+        return new BigDecimal(text);
+      case "java.math.BigInteger":
+        return new BigDecimal(text).toBigIntegerExact();
+      case "javax.management.ObjectName":
+        return new ObjectName(text);
+      default:
+        break;
+    }
+    if (type.startsWith("[") && value instanceof List) {
+      Class<?> component = Class.forName(type).getComponentType();
+      List<?> items = (List<?>) value;
+      Object array = Array.newInstance(component, items.size());
+      for (int idx = 0; idx < items.size(); idx++) {
+        Array.set(array, idx, coerce(items.get(idx), component.getName()));
+      }
+      return array;
+    }
+    throw new IllegalArgumentException("unsupported type " + type);
+  }
+
+  /**
+   * Convert a value decoded from JSON into an instance of the type of a parameter.
+   *
+   * Maps are converted into TabularData with key and value columns when the open
+   * type of the parameter is a TabularType, as MXBeans do for {@code Map} parameters.
+   */
+  private static Object coerce(Object value, MBeanParameterInfo parameter) throws Exception {
+    Object openType = parameter.getDescriptor().getFieldValue("openType");
+    if (value instanceof Map && openType instanceof TabularType) {
+      TabularType type = (TabularType) openType;
+      CompositeType row = type.getRowType();
+      String[] columns = new String[] {"key", "value"};
+      TabularDataSupport table = new TabularDataSupport(type);
+      for (Map.Entry<?, ?> entry : ((Map<?, ?>) value).entrySet()) {
+        Object[] values = new Object[] {
+            coerce(entry.getKey(), row.getType("key").getClassName()),
+            coerce(entry.getValue(), row.getType("value").getClassName())
+        };
+        table.put(new CompositeDataSupport(row, columns, values));
+      }
+      return table;
+    }
+    return coerce(value, parameter.getType());
+  }
+
+  /**
+   * Convert a JMX value into lists, maps and primitives.
+   *
+   * Open types are converted: CompositeData into maps, TabularData into lists of rows.
+   * Byte arrays become base64 strings, other arrays and collections become lists.
+   * Object names and enums become strings.
+   * Other objects are returned as they are, unless {@code strict} is set in which case
+   * they are replaced by their string representation so they can always be serialised.
+   */
+  private static Object convert(Object value, boolean strict) {
+    if (value == null || value instanceof Number || value instanceof Boolean
+        || value instanceof String) {
+      return value;
+    }
+    if (value instanceof Character || value instanceof ObjectName) {
+      return value.toString();
+    }
+    if (value instanceof Enum) {
+      return ((Enum<?>) value).name();
+    }
+    if (value instanceof CompositeData) {
+      CompositeData composite = (CompositeData) value;
+      Map<String, Object> result = new LinkedHashMap<>();
+      for (String key : composite.getCompositeType().keySet()) {
+        result.put(key, convert(composite.get(key), strict));
+      }
+      return result;
+    }
+    if (value instanceof TabularData) {
+      List<Object> result = new ArrayList<>();
+      for (Object row : ((TabularData) value).values()) {
+        result.add(convert(row, strict));
+      }
+      return result;
+    }
+    if (value instanceof byte[]) {
+      return Base64.getEncoder().encodeToString((byte[]) value);
+    }
+    if (value.getClass().isArray()) {
+      int length = Array.getLength(value);
+      List<Object> result = new ArrayList<>(length);
+      for (int idx = 0; idx < length; idx++) {
+        result.add(convert(Array.get(value, idx), strict));
+      }
+      return result;
+    }
+    if (value instanceof Collection) {
+      List<Object> result = new ArrayList<>();
+      for (Object item : (Collection<?>) value) {
+        result.add(convert(item, strict));
+      }
+      return result;
+    }
+    if (value instanceof Map) {
+      Map<String, Object> result = new LinkedHashMap<>();
+      for (Map.Entry<?, ?> entry : ((Map<?, ?>) value).entrySet()) {
+        result.put(String.valueOf(entry.getKey()), convert(entry.getValue(), strict));
+      }
+      return result;
+    }
+    return strict ? value.toString() : value;
+  }
+
+  /** Describe an error in a single line, including the root cause. */
+  private static String describe(Throwable error) {
+    Throwable cause = error;
+    while (cause.getCause() != null && cause.getCause() != cause) {
+      cause = cause.getCause();
+    }
+    return cause == error ? error.toString() : error.toString() + ": " + cause.toString();
+  }
+
+  /**
+   * Fetch and convert the value of an attribute.
+   *
+   * The value is returned as the only item of a list so {@code null} values can be converted.
+   */
+  private static List<Object> getAttribute(
+      MBeanServerConnection connection, String name, String attribute
+  ) throws Exception {
+    List<Object> result = new ArrayList<>();
+    result.add(convert(connection.getAttribute(new ObjectName(name), attribute), false));
+    return result;
+  }
+
+  /**
+   * Invoke an operation, converting the parameters to the types in its signature.
+   *
+   * Overloaded operations are tried in order until one accepts the parameters.
+   * The result is returned as the only item of a list so operations returning
+   * {@code void} or {@code null} can be converted too.
+   */
+  private static List<Object> invoke(
+      MBeanServerConnection connection, String name, String operation, List<?> params
+  ) throws Exception {
+    ObjectName objectName = new ObjectName(name);
+    for (MBeanOperationInfo info : connection.getMBeanInfo(objectName).getOperations()) {
+      MBeanParameterInfo[] signature = info.getSignature();
+      if (!info.getName().equals(operation) || signature.length != params.size()) {
+        continue;
+      }
+      Object[] values = new Object[signature.length];
+      String[] types = new String[signature.length];
+      try {
+        for (int idx = 0; idx < signature.length; idx++) {
+          types[idx] = signature[idx].getType();
+          values[idx] = coerce(params.get(idx), signature[idx]);
+        }
+      } catch (Exception e) {
+        continue;
+      }
+      List<Object> result = new ArrayList<>();
+      result.add(convert(connection.invoke(objectName, operation, values, types), true));
+      return result;
+    }
+    throw new IllegalArgumentException(
+        "no operation " + operation + " of " + name + " accepts " + params.size() + " parameters"
+        + " with the given values"
+    );
+  }
+
+  /** Fetch and convert the MBeanInfo of an MBean. */
+  private static Map<String, Object> mbeanInfo(MBeanServerConnection connection, String name)
+      throws Exception {
+    return mbeanInfo(connection.getMBeanInfo(new ObjectName(name)));
+  }
+
+  /** Convert an MBeanInfo into the shape of the rust MBeanInfo. */
+  private static Map<String, Object> mbeanInfo(MBeanInfo info) {
+    List<Object> attributes = new ArrayList<>();
+    for (MBeanAttributeInfo attribute : info.getAttributes()) {
+      attributes.add(attributeInfo(attribute));
+    }
+    Map<String, Object> result = new LinkedHashMap<>();
+    result.put("attributes", attributes);
+    result.put("class_name", orEmpty(info.getClassName()));
+    result.put("description", orEmpty(info.getDescription()));
+    result.put("descriptor", descriptor(info.getDescriptor()));
+    List<Object> operations = new ArrayList<>();
+    for (MBeanOperationInfo operation : info.getOperations()) {
+      operations.add(operationInfo(operation));
+    }
+    result.put("operations", operations);
+    return result;
+  }
+
+  /** Convert an MBeanOperationInfo into the shape of the rust MBeanOperation. */
+  private static Map<String, Object> operationInfo(MBeanOperationInfo info) {
+    List<Object> signature = new ArrayList<>();
+    for (MBeanParameterInfo parameter : info.getSignature()) {
+      Map<String, Object> result = new LinkedHashMap<>();
+      result.put("description", orEmpty(parameter.getDescription()));
+      result.put("name", orEmpty(parameter.getName()));
+      result.put("type_name", orEmpty(parameter.getType()));
+      signature.add(result);
+    }
+    Map<String, Object> operation = new LinkedHashMap<>();
+    operation.put("description", orEmpty(info.getDescription()));
+    operation.put("descriptor", descriptor(info.getDescriptor()));
+    operation.put("name", info.getName());
+    operation.put("return_type", orEmpty(info.getReturnType()));
+    operation.put("signature", signature);
+    return operation;
+  }
+
+  /** Convert a notification into the shape of the rust Notification. */
+  private static Map<String, Object> notification(Notification notification) {
+    Map<String, Object> result = new LinkedHashMap<>();
+    if (notification instanceof MBeanServerNotification) {
+      ObjectName name = ((MBeanServerNotification) notification).getMBeanName();
+      result.put("mbean_name", name.toString());
+    }
+    result.put("message", notification.getMessage());
+    result.put("sequence_number", notification.getSequenceNumber());
+    result.put("source", String.valueOf(convert(notification.getSource(), true)));
+    result.put("timestamp", notification.getTimeStamp());
+    result.put("type", notification.getType());
+    result.put("user_data", convert(notification.getUserData(), true));
+    return result;
+  }
+
+  /** Find a listener of this client by ID. */
+  private Helper listener(long id) {
+    Helper listener = listeners.get(id);
+    if (listener == null) {
+      throw new IllegalArgumentException("unknown notification listener " + id);
+    }
+    return listener;
+  }
+
+  /**
+   * Wait for notifications queued for a listener and return all of them.
+   *
+   * Waits up to the given number of milliseconds for the first notification.
+   * Fails once if notifications were dropped, and once nothing is queued for listeners
+   * whose connection is closed.
+   */
+  private List<Object> pollListener(long id, long timeout) throws Exception {
+    Helper listener = listener(id);
+    if (listener.lost.getAndSet(false)) {
+      throw new IllegalStateException("notifications for listener " + id + " were lost");
+    }
+    List<Notification> notifications = new ArrayList<>();
+    listener.notifications.drainTo(notifications);
+    if (notifications.isEmpty()) {
+      if (listener.closed != null) {
+        throw new IllegalStateException(listener.closed);
+      }
+      Notification first = listener.notifications.poll(timeout, TimeUnit.MILLISECONDS);
+      if (first != null) {
+        notifications.add(first);
+        listener.notifications.drainTo(notifications);
+      }
+    }
+    List<Object> result = new ArrayList<>(notifications.size());
+    for (Notification notification : notifications) {
+      result.add(notification(notification));
+    }
+    return result;
+  }
+
+  /** Query the names of MBeans and return them as strings. */
+  private static List<String> queryNames(
+      MBeanServerConnection connection, String name, String query
+  ) throws Exception {
+    Set<ObjectName> names = connection.queryNames(new ObjectName(name), new ObjectName(query));
+    List<String> result = new ArrayList<>(names.size());
+    for (ObjectName objectName : names) {
+      result.add(objectName.toString());
+    }
+    return result;
+  }
+
+  /** Remove a listener from the MBeans it was added to and discard its notifications. */
+  private void removeListener(long id) {
+    Helper listener = listener(id);
+    listeners.remove(id);
+    if (listener.closed == null) {
+      listener.removeFromEmitters();
+    }
+  }
+
+  /** Remove this listener from its MBeans, skipping MBeans or connections that are gone. */
+  private void removeFromEmitters() {
+    for (ObjectName emitter : emitters) {
+      try {
+        connection.removeNotificationListener(emitter, this);
+      } catch (Exception e) {
+        // The MBean was unregistered or the connection closed, the listener is gone.
+      }
+    }
+    emitters.clear();
+  }
+
+  /** Set an attribute, converting the value to the type of the attribute. */
+  private static void setAttribute(
+      MBeanServerConnection connection, String name, String attribute, Object value
+  ) throws Exception {
+    ObjectName objectName = new ObjectName(name);
+    for (MBeanAttributeInfo info : connection.getMBeanInfo(objectName).getAttributes()) {
+      if (info.getName().equals(attribute)) {
+        Object converted = coerce(value, info.getType());
+        connection.setAttribute(objectName, new Attribute(attribute, converted));
+        return;
+      }
+    }
+    throw new IllegalArgumentException("attribute " + attribute + " not found in " + name);
+  }
+
+  /**
+   * Describe an MBean and fetch all its readable attributes in one request.
+   *
+   * Attributes that can't be read are reported in the errors map.
+   */
+  private static Map<String, Object> snapshot(MBeanServerConnection connection, String name)
+      throws Exception {
+    ObjectName objectName = new ObjectName(name);
+    MBeanInfo info = connection.getMBeanInfo(objectName);
+    List<String> readable = new ArrayList<>();
+    for (MBeanAttributeInfo attribute : info.getAttributes()) {
+      if (attribute.isReadable()) {
+        readable.add(attribute.getName());
+      }
+    }
+
+    // Fetch all attributes at once, then retry missing ones one by one to find out why.
+    Map<String, Object> attributes = new TreeMap<>();
+    Map<String, Object> errors = new TreeMap<>();
+    Map<String, Object> indexes = new TreeMap<>();
+    AttributeList values = connection.getAttributes(
+        objectName, readable.toArray(new String[readable.size()])
+    );
+    for (Attribute attribute : values.asList()) {
+      attributes.put(attribute.getName(), convert(attribute.getValue(), true));
+      indexes(attribute.getValue(), attribute.getName(), indexes);
+    }
+    for (String attribute : readable) {
+      if (attributes.containsKey(attribute)) {
+        continue;
+      }
+      try {
+        Object value = connection.getAttribute(objectName, attribute);
+        attributes.put(attribute, convert(value, true));
+        indexes(value, attribute, indexes);
+      } catch (Exception e) {
+        errors.put(attribute, describe(e));
+      }
+    }
+
+    Map<String, Object> result = new LinkedHashMap<>();
+    result.put("attributes", attributes);
+    result.put("errors", errors);
+    result.put("indexes", indexes);
+    result.put("info", mbeanInfo(info));
+    result.put("name", objectName.toString());
+    return result;
+  }
+
+  /**
+   * Collect the index names of the TabularData found in a value.
+   *
+   * Tables are identified by the attribute name followed by the composite item
+   * and table column names leading to them, separated by dots.
+   * Types are inspected instead of values so empty tables are included.
+   */
+  private static void indexes(Object value, String path, Map<String, Object> indexes) {
+    if (value instanceof CompositeData) {
+      typeIndexes(((CompositeData) value).getCompositeType(), path, indexes);
+    } else if (value instanceof TabularData) {
+      typeIndexes(((TabularData) value).getTabularType(), path, indexes);
+    }
+  }
+
+  private static void typeIndexes(OpenType<?> type, String path, Map<String, Object> indexes) {
+    if (type instanceof CompositeType) {
+      CompositeType composite = (CompositeType) type;
+      for (String key : composite.keySet()) {
+        typeIndexes(composite.getType(key), path + "." + key, indexes);
+      }
+    } else if (type instanceof TabularType) {
+      TabularType tabular = (TabularType) type;
+      indexes.put(path, new ArrayList<>(tabular.getIndexNames()));
+      typeIndexes(tabular.getRowType(), path, indexes);
+    }
+  }
+
+  private static String orEmpty(String value) {
+    return value == null ? "" : value;
+  }
+}
@@ -2,12 +2,19 @@ use failure::ResultExt;
 use j4rs::Instance;
 use j4rs::InvocationArg;
 use j4rs::Jvm;
+use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 
 use std::convert::TryFrom;
+use std::time::Duration;
 use super::ErrorKind;
+use super::ListenerId;
 use super::MBeanInfo;
+use super::MBeanSnapshot;
+use super::Notification;
 use super::Result;
+use super::ServerDump;
 
 use super::constants::JMX_SERVICE_URL;
 
@@ -59,18 +66,142 @@ impl MBeanAddress {
 
 /// Trait definition for all MBean clients.
 pub trait MBeanClientTrait {
+    /// Listen to notifications emitted by the MBeans matching a name or pattern.
+    ///
+    /// Only notifications with a type starting with one of `types` are received,
+    /// or all notifications if `types` is empty.
+    /// Patterns are resolved when the listener is added and MBeans that do not emit
+    /// notifications are skipped: it fails if no MBean is left.
+    /// Notifications are queued by the client until they are polled.
+    ///
+    /// The default implementation fails with `ErrorKind::Unsupported`.
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        let _ = (mbean, types);
+        Err(ErrorKind::Unsupported("add_notification_listener").into())
+    }
+
+    /// Identify the connection to the server, the identifier changes when the client reconnects.
+    ///
+    /// Lets wrappers detect reconnections made behind them.
+    /// The default implementation returns `None` for clients that can't tell.
+    fn connection_id(&self) -> Option<u64> {
+        None
+    }
+
     /// Get the value of a specific MBean attribute.
     fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
         where S1: Into<String>,
               S2: Into<String>,
               T: DeserializeOwned;
 
+    /// Capture every MBean matching the pattern with its metadata and attribute values.
+    ///
+    /// MBeans or attributes that can't be read are recorded as errors in the dump.
+    /// Only a failure to list the MBeans matching the pattern fails the dump.
+    fn dump<S>(&self, pattern: S) -> Result<ServerDump>
+        where S: Into<String>,
+    {
+        let mut dump = ServerDump::default();
+        for name in self.query_names(pattern, "")? {
+            match self.snapshot_mbean(name.as_str()) {
+                Err(error) => {
+                    dump.errors.insert(name, error.to_string());
+                },
+                Ok(snapshot) => {
+                    dump.mbeans.insert(name, snapshot);
+                },
+            }
+        }
+        Ok(dump)
+    }
+
     /// Get information about an MBean.
     fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
         where S: Into<String>;
 
+    /// Invoke an operation on an MBean and decode its result.
+    ///
+    /// Parameters are converted to the types in the operation signature.
+    /// For overloaded operations, the first signature with as many parameters
+    /// as given that accepts their values is invoked.
+    ///
+    /// The default implementation fails with `ErrorKind::Unsupported`.
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let _ = (mbean, operation, params);
+        Err(ErrorKind::Unsupported("invoke").into())
+    }
+
+    /// Wait up to `timeout` for notifications and return all those queued for a listener.
+    ///
+    /// Fails if notifications were lost or once the listener can't receive more,
+    /// for example because the client reconnected.
+    /// The default implementation fails with `ErrorKind::Unsupported`.
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        let _ = (listener, timeout);
+        Err(ErrorKind::Unsupported("poll_notifications").into())
+    }
+
     /// Query for the names of MBeans on the JMX server.
     fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
         where S1: Into<String>,
               S2: Into<String>;
+
+    /// Stop a listener and discard the notifications queued for it.
+    ///
+    /// The default implementation fails with `ErrorKind::Unsupported`.
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        let _ = listener;
+        Err(ErrorKind::Unsupported("remove_notification_listener").into())
+    }
+
+    /// Set the value of a writable MBean attribute.
+    ///
+    /// The value is converted to the type of the attribute: strings are parsed if needed.
+    ///
+    /// The default implementation fails with `ErrorKind::Unsupported`.
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let _ = (mbean, attribute, value);
+        Err(ErrorKind::Unsupported("set_attribute").into())
+    }
+
+    /// Get information about an MBean and the values of all its readable attributes.
+    ///
+    /// The default implementation requests the `MBeanInfo` and then each attribute.
+    /// Clients that can do so fetch everything with a single request.
+    fn snapshot_mbean<S>(&self, mbean: S) -> Result<MBeanSnapshot>
+        where S: Into<String>,
+    {
+        let name = mbean.into();
+        let info = self.get_mbean_info(name.as_str())?;
+        let mut snapshot = MBeanSnapshot {
+            name,
+            ..Default::default()
+        };
+        for attribute in info.attributes.iter().filter(|attribute| attribute.is_readable) {
+            let name = attribute.name.clone();
+            let value: Result<Value> = self.get_attribute(snapshot.name.as_str(), name.as_str());
+            match value {
+                Err(error) => {
+                    snapshot.errors.insert(name, error.to_string());
+                },
+                Ok(value) => {
+                    snapshot.attributes.insert(name, value);
+                },
+            }
+        }
+        snapshot.info = info;
+        Ok(snapshot)
+    }
 }
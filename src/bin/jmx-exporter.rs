@@ -0,0 +1,346 @@
+//! Serve metrics scraped from MBean servers over HTTP, like the `jmx_exporter` HTTP server.
+//!
+//! Usage: `jmx-exporter [host:]<port> <config.yaml>`
+//!
+//! The configuration file accepts all the `jmx_exporter` rule options (see `jmx::exporter`)
+//! as well as the following options to select the servers to scrape:
+//!
+//! ```yaml
+//! # Scrape a single server, metrics are not labelled with the target.
+//! hostPort: localhost:1234
+//! # Or the full JMX service URL of the server.
+//! jmxUrl: service:jmx:rmi:///jndi/rmi://localhost:1234/jmxrmi
+//!
+//! # Scrape many servers, metrics are labelled with the target name.
+//! targets:
+//!   - name: app
+//!     hostPort: localhost:1234
+//!
+//! # Scrape in the background and serve the latest results instead of scraping
+//! # on every request.
+//! scrapeIntervalSeconds: 15
+//! ```
+//!
+//! The MBeans of each target are tracked with a `jmx::Registry` between scrapes.
+extern crate jmx;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+extern crate tiny_http;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use tiny_http::Header;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+
+use jmx::ErrorKind;
+use jmx::JmxRuntime;
+use jmx::MBeanAddress;
+use jmx::MBeanClientTrait;
+use jmx::MBeanThreadedClient;
+use jmx::MBeanThreadedClientOptions;
+use jmx::Registry;
+use jmx::exporter::Exporter;
+use jmx::exporter::ExporterConfig;
+use jmx::exporter::MetricType;
+use jmx::exporter::Metrics;
+use jmx::registry::MBEAN_SERVER_DELEGATE_NAME;
+
+
+static CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+static HANDLER_THREADS: usize = 4;
+
+
+/// A server to scrape, as configured in the `targets` list.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TargetConfig {
+    host_port: Option<String>,
+    jmx_url: Option<String>,
+    name: Option<String>,
+}
+
+
+/// Options of the HTTP server, read from the same file as the exporter rules.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ServerConfig {
+    host_port: Option<String>,
+    jmx_url: Option<String>,
+    scrape_interval_seconds: u64,
+    targets: Vec<TargetConfig>,
+}
+
+
+/// Result of the scrape of a target and the time it took.
+type TargetScrape = (Duration, jmx::Result<Metrics>);
+
+
+/// A server to scrape and the label to identify it with.
+///
+/// Each target is scraped by its own thread, which owns the connection to the server
+/// and a `Registry` of its MBeans so they are not listed again for every scrape.
+struct Target {
+    name: String,
+    requests: SyncSender<Sender<TargetScrape>>,
+}
+
+impl Target {
+    /// Start the thread scraping the configured server on request.
+    fn spawn(
+        target: TargetConfig, exporter: Arc<Exporter>, options: MBeanThreadedClientOptions
+    ) -> Result<Target, String> {
+        let (address, default_name) = match (target.host_port, target.jmx_url) {
+            (Some(host_port), None) => (MBeanAddress::address(host_port.as_str()), host_port),
+            (None, Some(jmx_url)) => (MBeanAddress::service_url(jmx_url.as_str()), jmx_url),
+            _ => return Err("each target needs one of hostPort or jmxUrl".into()),
+        };
+        let client = MBeanThreadedClient::connect_with_options(
+            address.clone(), options.clone().skip_connect(true)
+        ).map_err(|error| error.to_string())?;
+        // At most one scrape per request handler and the background scraper is pending.
+        let (requests, receiver) = mpsc::sync_channel::<Sender<TargetScrape>>(HANDLER_THREADS + 1);
+        thread::spawn(move || {
+            let mut registry = None;
+            for reply in receiver {
+                let start = Instant::now();
+                let result = Target::scrape(&exporter, &client, &address, &options, &mut registry);
+                let _ = reply.send((start.elapsed(), result));
+            }
+        });
+        Ok(Target {
+            name: target.name.unwrap_or(default_name),
+            requests,
+        })
+    }
+
+    /// Scrape the MBeans of the registry, reconnecting and resyncing it as needed.
+    fn scrape<'a>(
+        exporter: &Exporter, client: &'a MBeanThreadedClient, address: &MBeanAddress,
+        options: &MBeanThreadedClientOptions,
+        registry: &mut Option<Registry<'a, MBeanThreadedClient>>,
+    ) -> jmx::Result<Metrics> {
+        // Reconnect if the server does not respond, the registry then fails to refresh.
+        let probe: jmx::Result<String> =
+            client.get_attribute(MBEAN_SERVER_DELEGATE_NAME, "MBeanServerId");
+        if probe.is_err() {
+            client.reconnect_with_options(address.clone(), options.clone())?;
+        }
+        match *registry {
+            None => *registry = Some(Registry::new(client)?),
+            Some(ref mut registry) => {
+                if registry.refresh(Duration::from_millis(0)).is_err() {
+                    registry.resync()?;
+                }
+            },
+        }
+        let registry = registry.as_ref().expect("the registry was just created");
+        exporter.scrape_registry(registry)
+    }
+}
+
+
+/// State shared by the request handlers and the background scraper.
+struct Scraper {
+    cache: Mutex<Option<String>>,
+    label_targets: bool,
+    /// Start and result of the latest on-demand scrape, locked while scraping.
+    latest: Mutex<Option<(Instant, String)>>,
+    targets: Vec<Target>,
+}
+
+impl Scraper {
+    /// Scrape all targets in parallel and render the metrics.
+    fn scrape(&self) -> String {
+        let pending: Vec<_> = self.targets.iter().map(|target| {
+            let (reply, result) = mpsc::channel();
+            let _ = target.requests.send(reply);
+            (target, result)
+        }).collect();
+
+        let mut metrics = Metrics::new();
+        for (target, result) in pending {
+            let (elapsed, result) = match result.recv() {
+                Err(_) => (Duration::from_secs(0), Err(ErrorKind::WorkerNoResponse.into())),
+                Ok(scrape) => scrape,
+            };
+            let labels = vec![("target".to_string(), target.name.clone())];
+            metrics.add(
+                "jmx_scrape_duration_seconds", MetricType::Gauge,
+                "Time this JMX scrape took, in seconds.", labels.clone(),
+                elapsed.as_secs_f64()
+            );
+            let error = match result {
+                Err(error) => {
+                    eprintln!("jmx-exporter: scrape of {} failed: {}", target.name, error);
+                    1.0
+                },
+                Ok(scraped) => {
+                    let extra = if self.label_targets { labels.clone() } else { Vec::new() };
+                    metrics.merge(scraped, &extra);
+                    0.0
+                },
+            };
+            metrics.add(
+                "jmx_scrape_error", MetricType::Gauge,
+                "Non-zero if this scrape failed.", labels, error
+            );
+        }
+        metrics.render()
+    }
+
+    /// Metrics to serve: the latest background scrape if enabled, a new scrape otherwise.
+    ///
+    /// On-demand scrapes run one at a time: requests waiting for a scrape that started
+    /// after they arrived serve its results instead of scraping again.
+    fn metrics(&self, scheduled: bool) -> Option<String> {
+        if !scheduled {
+            let arrived = Instant::now();
+            let mut latest = match self.latest.lock() {
+                Err(poisoned) => poisoned.into_inner(),
+                Ok(latest) => latest,
+            };
+            if let Some((start, ref text)) = *latest {
+                if start >= arrived {
+                    return Some(text.clone());
+                }
+            }
+            let start = Instant::now();
+            let text = self.scrape();
+            *latest = Some((start, text.clone()));
+            return Some(text);
+        }
+        match self.cache.lock() {
+            Err(poisoned) => poisoned.into_inner().clone(),
+            Ok(cache) => cache.clone(),
+        }
+    }
+
+    /// Scrape all targets on a schedule, caching the results.
+    fn run_schedule(&self, interval: Duration) {
+        loop {
+            let start = Instant::now();
+            let text = self.scrape();
+            match self.cache.lock() {
+                Err(poisoned) => *poisoned.into_inner() = Some(text),
+                Ok(mut cache) => *cache = Some(text),
+            }
+            if let Some(remaining) = interval.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    }
+}
+
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("Usage: {} [host:]<port> <config.yaml>", args[0]);
+        process::exit(1);
+    }
+    if let Err(error) = run(&args[1], &args[2]) {
+        eprintln!("jmx-exporter: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run(listen: &str, config: &str) -> Result<(), String> {
+    let yaml = fs::read_to_string(config)
+        .map_err(|error| format!("could not read configuration '{}': {}", config, error))?;
+    let exporter = ExporterConfig::from_yaml(&yaml)
+        .and_then(Exporter::new)
+        .map_err(|error| error.to_string())?;
+    let server_config: ServerConfig = serde_yaml::from_str(&yaml)
+        .map_err(|error| format!("invalid server configuration: {}", error))?;
+
+    // Collect the targets to scrape.
+    let label_targets = !server_config.targets.is_empty();
+    let mut configs = Vec::new();
+    if server_config.host_port.is_some() || server_config.jmx_url.is_some() {
+        configs.push(TargetConfig {
+            host_port: server_config.host_port,
+            jmx_url: server_config.jmx_url,
+            name: None,
+        });
+    }
+    configs.extend(server_config.targets);
+    if configs.is_empty() {
+        return Err("no targets configured: set hostPort, jmxUrl or targets".into());
+    }
+
+    // Share one JVM across the connections to all targets.
+    let exporter = Arc::new(exporter);
+    let runtime = JmxRuntime::new().map_err(|error| error.to_string())?;
+    let options = MBeanThreadedClientOptions::default().runtime(runtime);
+    let mut targets = Vec::new();
+    for config in configs {
+        targets.push(Target::spawn(config, Arc::clone(&exporter), options.clone())?);
+    }
+    let scraper = Arc::new(Scraper {
+        cache: Mutex::new(None),
+        label_targets,
+        latest: Mutex::new(None),
+        targets,
+    });
+    let scheduled = server_config.scrape_interval_seconds > 0;
+    if scheduled {
+        let interval = Duration::from_secs(server_config.scrape_interval_seconds);
+        let scraper = Arc::clone(&scraper);
+        thread::spawn(move || scraper.run_schedule(interval));
+    }
+
+    // Serve requests from a few threads so slow scrapes don't block health checks.
+    let listen = if listen.contains(':') {
+        listen.to_string()
+    } else {
+        format!("0.0.0.0:{}", listen)
+    };
+    let server = Server::http(listen.as_str())
+        .map_err(|error| format!("could not listen on '{}': {}", listen, error))?;
+    let server = Arc::new(server);
+    let handlers: Vec<_> = (0..HANDLER_THREADS).map(|_| {
+        let scraper = Arc::clone(&scraper);
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle(&scraper, scheduled, request);
+            }
+        })
+    }).collect();
+    for handler in handlers {
+        let _ = handler.join();
+    }
+    Ok(())
+}
+
+fn handle(scraper: &Scraper, scheduled: bool, request: Request) {
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let response = match path.as_str() {
+        "/healthz" => Response::from_string("ok\n"),
+        "/metrics" => match scraper.metrics(scheduled) {
+            None => Response::from_string("no scrape completed yet\n").with_status_code(503),
+            Some(text) => {
+                let header = Header::from_bytes(&b"Content-Type"[..], CONTENT_TYPE.as_bytes())
+                    .expect("static content type header is valid");
+                Response::from_string(text).with_header(header)
+            },
+        },
+        _ => Response::from_string("not found\n").with_status_code(404),
+    };
+    if let Err(error) = request.respond(response) {
+        eprintln!("jmx-exporter: could not send response: {}", error);
+    }
+}
@@ -0,0 +1,624 @@
+//! Inspect and manage MBeans from the command line, like `jmxterm`.
+//!
+//! Usage: `jmx (--address <host:port> | --url <service:jmx:...>) [OPTIONS] [SUBCOMMAND]`
+//!
+//! Subcommands:
+//!
+//!   * `domains`: list the MBean domains.
+//!   * `beans [pattern]`: list the MBeans matching a pattern.
+//!   * `info <bean>`: describe the attributes and operations of an MBean.
+//!   * `get <bean> <attr>...`: read attributes.
+//!   * `set <bean> <attr> <value>`: write an attribute.
+//!   * `invoke <bean> <operation> [param]...`: invoke an operation.
+//!   * `watch <bean> <attr>... [--interval <seconds>]`: read attributes periodically.
+//!   * `dump [pattern]`: read every attribute of the MBeans matching a pattern.
+//!
+//! Results are printed as aligned tables or, with `--output json`, as JSON.
+//! Without a subcommand an interactive shell starts, with completion of
+//! commands, MBean names, attribute names and operation names.
+extern crate clap;
+extern crate failure;
+extern crate jmx;
+extern crate rustyline;
+extern crate serde;
+extern crate serde_json;
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use clap::App;
+use clap::AppSettings;
+use clap::Arg;
+use clap::ArgGroup;
+use clap::ArgMatches;
+use clap::SubCommand;
+use failure::Fail;
+use rustyline::Context;
+use rustyline::Editor;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use serde::Serialize;
+use serde_json::Value;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanClientOptions;
+use jmx::MBeanClientTrait;
+
+
+/// Commands available in the interactive shell in addition to the subcommands.
+static SHELL_COMMANDS: &[&str] = &["exit", "help", "quit"];
+
+
+/// Format of the command results.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Output {
+    Json,
+    Table,
+}
+
+
+fn main() {
+    let matches = app().get_matches();
+    let output = match matches.value_of("output") {
+        Some("json") => Output::Json,
+        _ => Output::Table,
+    };
+    let client = match connect(&matches) {
+        Ok(client) => client,
+        Err(error) => {
+            report(&error);
+            process::exit(1);
+        },
+    };
+    let result = match matches.subcommand() {
+        (_, Some(command)) => run(&client, output, matches.subcommand_name().unwrap(), command),
+        (_, None) => shell(&client, output),
+    };
+    if let Err(error) = result {
+        report(&error);
+        process::exit(1);
+    }
+}
+
+/// Command line definition, with the connection options and all subcommands.
+fn app() -> App<'static, 'static> {
+    App::new("jmx")
+        .about("Inspect and manage MBeans on a remote JMX server")
+        .arg(Arg::with_name("address")
+             .long("address")
+             .short("a")
+             .takes_value(true)
+             .value_name("HOST:PORT")
+             .help("Address of the JMX server to connect to"))
+        .arg(Arg::with_name("url")
+             .long("url")
+             .takes_value(true)
+             .value_name("SERVICE_URL")
+             .help("Full JMX service URL of the server to connect to"))
+        .group(ArgGroup::with_name("server")
+               .args(&["address", "url"])
+               .required(true))
+        .arg(Arg::with_name("user")
+             .long("user")
+             .short("u")
+             .takes_value(true)
+             .env("JMX_USER")
+             .requires("password")
+             .help("Username to authenticate with"))
+        .arg(Arg::with_name("password")
+             .long("password")
+             .short("p")
+             .takes_value(true)
+             .env("JMX_PASSWORD")
+             .hide_env_values(true)
+             .requires("user")
+             .help("Password to authenticate with"))
+        .arg(Arg::with_name("output")
+             .long("output")
+             .short("o")
+             .takes_value(true)
+             .possible_values(&["json", "table"])
+             .default_value("table")
+             .help("Format of the results"))
+        .subcommands(commands())
+}
+
+/// Subcommands available both on the command line and in the interactive shell.
+fn commands() -> Vec<App<'static, 'static>> {
+    let bean = || Arg::with_name("bean")
+        .required(true)
+        .help("Name of the MBean");
+    let pattern = || Arg::with_name("pattern")
+        .default_value("*:*")
+        .help("ObjectName pattern of the MBeans");
+    vec![
+        SubCommand::with_name("beans")
+            .about("List the MBeans matching a pattern")
+            .arg(pattern()),
+        SubCommand::with_name("domains")
+            .about("List the MBean domains"),
+        SubCommand::with_name("dump")
+            .about("Read every attribute of the MBeans matching a pattern")
+            .arg(pattern()),
+        SubCommand::with_name("get")
+            .about("Read MBean attributes")
+            .arg(bean())
+            .arg(Arg::with_name("attribute")
+                 .required(true)
+                 .multiple(true)
+                 .help("Names of the attributes")),
+        SubCommand::with_name("info")
+            .about("Describe the attributes and operations of an MBean")
+            .arg(bean()),
+        SubCommand::with_name("invoke")
+            .about("Invoke an MBean operation")
+            .arg(bean())
+            .arg(Arg::with_name("operation")
+                 .required(true)
+                 .help("Name of the operation"))
+            .arg(Arg::with_name("param")
+                 .multiple(true)
+                 .help("Parameters of the operation, JSON arrays are passed as arrays")),
+        SubCommand::with_name("set")
+            .about("Write an MBean attribute")
+            .arg(bean())
+            .arg(Arg::with_name("attribute")
+                 .required(true)
+                 .help("Name of the attribute"))
+            .arg(Arg::with_name("value")
+                 .required(true)
+                 .help("New value, converted to the attribute type")),
+        SubCommand::with_name("watch")
+            .about("Read MBean attributes periodically")
+            .arg(bean())
+            .arg(Arg::with_name("attribute")
+                 .required(true)
+                 .multiple(true)
+                 .help("Names of the attributes"))
+            .arg(Arg::with_name("interval")
+                 .long("interval")
+                 .short("i")
+                 .takes_value(true)
+                 .default_value("1")
+                 .validator(|value| validate_interval(&value))
+                 .help("Seconds between reads"))
+            .arg(Arg::with_name("count")
+                 .long("count")
+                 .short("c")
+                 .takes_value(true)
+                 .validator(|value| validate_number::<u64>(&value))
+                 .help("Stop after this many reads")),
+    ]
+}
+
+/// Accept a positive number of seconds that fits a `Duration`.
+fn validate_interval(value: &str) -> Result<(), String> {
+    value.parse::<f64>().ok()
+        .filter(|seconds| *seconds > 0.0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .map(|_| ())
+        .ok_or_else(|| format!("'{}' is not a positive number of seconds", value))
+}
+
+fn validate_number<T: std::str::FromStr>(value: &str) -> Result<(), String> {
+    value.parse::<T>().map(|_| ()).map_err(|_| format!("'{}' is not a valid number", value))
+}
+
+/// Connect to the server selected on the command line.
+fn connect(matches: &ArgMatches) -> jmx::Result<MBeanClient> {
+    let address = match matches.value_of("url") {
+        Some(url) => MBeanAddress::service_url(url),
+        None => MBeanAddress::address(matches.value_of("address").unwrap()),
+    };
+    let mut options = MBeanClientOptions::default();
+    if let (Some(user), Some(password)) = (matches.value_of("user"), matches.value_of("password")) {
+        options = options.credentials(user, password);
+    }
+    MBeanClient::connect_with_options(address, options)
+}
+
+/// Print an error and all its causes.
+fn report(error: &jmx::Error) {
+    eprint!("jmx: {}", error);
+    let error: &dyn Fail = error;
+    for cause in error.iter_causes() {
+        eprint!(": {}", cause);
+    }
+    eprintln!();
+}
+
+
+/// Run a subcommand and print its results.
+fn run(client: &MBeanClient, output: Output, name: &str, args: &ArgMatches) -> jmx::Result<()> {
+    match name {
+        "beans" => {
+            let beans = client.query_names(args.value_of("pattern").unwrap(), "")?;
+            print_list(output, beans);
+        },
+        "domains" => {
+            let domains: BTreeSet<String> = client.query_names("*:*", "")?.into_iter()
+                .filter_map(|name| name.split(':').next().map(String::from))
+                .collect();
+            print_list(output, domains.into_iter().collect());
+        },
+        "dump" => {
+            let dump = client.dump(args.value_of("pattern").unwrap())?;
+            if output == Output::Json {
+                print_json(&dump);
+                return Ok(());
+            }
+            for (name, snapshot) in dump.mbeans {
+                println!("{}", name);
+                let mut rows: Vec<Vec<String>> = snapshot.attributes.iter()
+                    .map(|(attribute, value)| vec![format!("  {}", attribute), format_value(value)])
+                    .collect();
+                rows.extend(snapshot.errors.iter().map(|(attribute, error)| {
+                    vec![format!("  {}", attribute), format!("<error: {}>", error)]
+                }));
+                rows.sort();
+                print_table(&rows);
+            }
+            for (name, error) in dump.errors {
+                println!("{}\n  <error: {}>", name, error);
+            }
+        },
+        "get" => {
+            let bean = args.value_of("bean").unwrap();
+            let attributes: Vec<&str> = args.values_of("attribute").unwrap().collect();
+            let values = read_attributes(client, bean, &attributes)?;
+            print_values(output, &attributes, values);
+        },
+        "info" => {
+            let info = client.get_mbean_info(args.value_of("bean").unwrap())?;
+            if output == Output::Json {
+                print_json(&info);
+                return Ok(());
+            }
+            println!("Class: {}", info.class_name);
+            if !info.description.is_empty() {
+                println!("Description: {}", info.description);
+            }
+            println!("Attributes:");
+            let rows: Vec<Vec<String>> = info.attributes.iter().map(|attribute| {
+                let access = match (attribute.is_readable, attribute.is_writable) {
+                    (true, true) => "rw",
+                    (true, false) => "r",
+                    (false, true) => "w",
+                    (false, false) => "-",
+                };
+                vec![
+                    format!("  {}", attribute.name), access.to_string(),
+                    attribute.type_name.clone(), attribute.description.clone(),
+                ]
+            }).collect();
+            print_table(&rows);
+            println!("Operations:");
+            let rows: Vec<Vec<String>> = info.operations.iter().map(|operation| {
+                let params: Vec<String> = operation.signature.iter()
+                    .map(|param| format!("{} {}", param.type_name, param.name))
+                    .collect();
+                vec![
+                    format!("  {}", operation.return_type),
+                    format!("{}({})", operation.name, params.join(", ")),
+                    operation.description.clone(),
+                ]
+            }).collect();
+            print_table(&rows);
+        },
+        "invoke" => {
+            let params = args.values_of("param")
+                .map(|params| params.map(parse_param).collect())
+                .unwrap_or_default();
+            let result: Value = client.invoke(
+                args.value_of("bean").unwrap(), args.value_of("operation").unwrap(), params
+            )?;
+            match output {
+                Output::Json => print_json(&result),
+                Output::Table if result.is_null() => (),
+                Output::Table => println!("{}", format_value(&result)),
+            }
+        },
+        "set" => {
+            client.set_attribute(
+                args.value_of("bean").unwrap(), args.value_of("attribute").unwrap(),
+                args.value_of("value").unwrap()
+            )?;
+        },
+        "watch" => {
+            let bean = args.value_of("bean").unwrap();
+            let attributes: Vec<&str> = args.values_of("attribute").unwrap().collect();
+            let interval: f64 = args.value_of("interval").unwrap().parse().unwrap();
+            let count: Option<u64> = args.value_of("count").map(|count| count.parse().unwrap());
+            let mut reads = 0;
+            loop {
+                let values = read_attributes(client, bean, &attributes)?;
+                match output {
+                    Output::Json => {
+                        let object: serde_json::Map<String, Value> = attributes.iter()
+                            .map(|attribute| attribute.to_string())
+                            .zip(values)
+                            .collect();
+                        println!("{}", Value::Object(object));
+                    },
+                    Output::Table => {
+                        let values: Vec<String> = values.iter().map(format_value).collect();
+                        println!("{}", values.join("\t"));
+                    },
+                }
+                reads += 1;
+                if count.map_or(false, |count| reads >= count) {
+                    break;
+                }
+                thread::sleep(Duration::from_secs_f64(interval));
+            }
+        },
+        _ => unreachable!("unknown subcommand {}", name),
+    }
+    Ok(())
+}
+
+/// Read the given attributes of an MBean, in order.
+fn read_attributes(
+    client: &MBeanClient, bean: &str, attributes: &[&str]
+) -> jmx::Result<Vec<Value>> {
+    attributes.iter().map(|attribute| client.get_attribute(bean, *attribute)).collect()
+}
+
+/// Operation parameters are strings converted by the server, unless they are JSON arrays.
+fn parse_param(param: &str) -> Value {
+    match serde_json::from_str(param) {
+        Ok(Value::Array(items)) => Value::Array(items),
+        _ => Value::String(param.to_string()),
+    }
+}
+
+
+/// Format a value for tables: strings as they are, everything else as JSON.
+fn format_value(value: &Value) -> String {
+    match *value {
+        Value::String(ref text) => text.clone(),
+        ref value => value.to_string(),
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    let text = serde_json::to_string_pretty(value).expect("results can always be encoded");
+    println!("{}", text);
+}
+
+fn print_list(output: Output, items: Vec<String>) {
+    match output {
+        Output::Json => print_json(&items),
+        Output::Table => {
+            for item in items {
+                println!("{}", item);
+            }
+        },
+    }
+}
+
+/// Print rows of cells with the columns aligned.
+fn print_table(rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(idx) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    for row in rows {
+        let mut line = String::new();
+        for (idx, cell) in row.iter().enumerate() {
+            if idx + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:width$}  ", cell, width = widths[idx]));
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+fn print_values(output: Output, attributes: &[&str], values: Vec<Value>) {
+    match output {
+        Output::Json => {
+            let object: serde_json::Map<String, Value> = attributes.iter()
+                .map(|attribute| attribute.to_string())
+                .zip(values)
+                .collect();
+            print_json(&object);
+        },
+        Output::Table => {
+            let rows: Vec<Vec<String>> = attributes.iter()
+                .zip(values.iter())
+                .map(|(attribute, value)| vec![attribute.to_string(), format_value(value)])
+                .collect();
+            print_table(&rows);
+        },
+    }
+}
+
+
+/// Run commands read from the terminal until the user exits.
+fn shell(client: &MBeanClient, output: Output) -> jmx::Result<()> {
+    let mut editor = Editor::<ShellHelper>::new();
+    editor.set_helper(Some(ShellHelper {
+        beans: RefCell::new(None),
+        client,
+    }));
+    loop {
+        let line = match editor.readline("jmx> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(error) => {
+                eprintln!("jmx: could not read command: {}", error);
+                return Ok(());
+            },
+        };
+        let words = split_words(&line);
+        if words.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str());
+        match words[0].as_str() {
+            "exit" | "quit" => return Ok(()),
+            "help" => {
+                let _ = shell_app().print_long_help();
+                println!();
+                continue;
+            },
+            _ => (),
+        }
+        let matches = match shell_app().get_matches_from_safe(words) {
+            Ok(matches) => matches,
+            Err(error) => {
+                println!("{}", error.message);
+                continue;
+            },
+        };
+        let (name, args) = matches.subcommand();
+        if let Err(error) = run(client, output, name, args.unwrap()) {
+            report(&error);
+        }
+        // Commands may have changed the registered MBeans.
+        if let Some(helper) = editor.helper_mut() {
+            helper.beans.replace(None);
+        }
+    }
+}
+
+/// Definition of the commands accepted by the interactive shell.
+fn shell_app() -> App<'static, 'static> {
+    App::new("jmx")
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::SubcommandRequired)
+        .setting(AppSettings::DisableVersion)
+        .global_setting(AppSettings::DisableHelpFlags)
+        .template("Commands:\n{subcommands}\n\nType exit or quit to leave the shell.")
+        .subcommands(commands())
+}
+
+/// Split a command line into words, honouring single and double quotes.
+fn split_words(line: &str) -> Vec<String> {
+    split_words_with_start(line).0.into_iter().map(|(_, word)| word).collect()
+}
+
+/// Split a command line into words and the index where each word starts.
+///
+/// Also returns whether the line ends inside an unterminated quote.
+fn split_words_with_start(line: &str) -> (Vec<(usize, String)>, bool) {
+    let mut words = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    let mut quote: Option<char> = None;
+    for (idx, chr) in line.char_indices() {
+        match (quote, chr) {
+            (Some(open), chr) if chr == open => quote = None,
+            (Some(_), chr) => current.get_or_insert_with(|| (idx, String::new())).1.push(chr),
+            (None, '"') | (None, '\'') => {
+                quote = Some(chr);
+                current.get_or_insert_with(|| (idx, String::new()));
+            },
+            (None, chr) if chr.is_whitespace() => words.extend(current.take()),
+            (None, chr) => current.get_or_insert_with(|| (idx, String::new())).1.push(chr),
+        }
+    }
+    words.extend(current);
+    (words, quote.is_some())
+}
+
+
+/// Complete command, MBean, attribute and operation names in the interactive shell.
+struct ShellHelper<'a> {
+    /// Names of the registered MBeans, cached between completions.
+    beans: RefCell<Option<Vec<String>>>,
+    client: &'a MBeanClient,
+}
+
+impl<'a> ShellHelper<'a> {
+    fn beans(&self) -> Vec<String> {
+        let mut beans = self.beans.borrow_mut();
+        if beans.is_none() {
+            *beans = self.client.query_names("*:*", "").ok();
+        }
+        beans.clone().unwrap_or_default()
+    }
+
+    /// Names that can be used for the word at the given position of a command.
+    fn candidates(&self, words: &[String], position: usize) -> Vec<String> {
+        if position == 0 {
+            let commands = commands();
+            let names = commands.iter().map(|command| command.get_name());
+            return names.chain(SHELL_COMMANDS.iter().cloned()).map(String::from).collect();
+        }
+        let command = words[0].as_str();
+        if position == 1 && command != "domains" {
+            return self.beans();
+        }
+        let info = match self.client.get_mbean_info(words[1].as_str()) {
+            Ok(info) => info,
+            Err(_) => return Vec::new(),
+        };
+        match (command, position) {
+            ("get", _) | ("watch", _) => info.attributes.into_iter()
+                .filter(|attribute| attribute.is_readable)
+                .map(|attribute| attribute.name)
+                .collect(),
+            ("invoke", 2) => info.operations.into_iter()
+                .map(|operation| operation.name)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            ("set", 2) => info.attributes.into_iter()
+                .filter(|attribute| attribute.is_writable)
+                .map(|attribute| attribute.name)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<'a> Completer for ShellHelper<'a> {
+    type Candidate = String;
+
+    fn complete(
+        &self, line: &str, pos: usize, _ctx: &Context<'_>
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let (mut words, open_quote) = split_words_with_start(line);
+        let in_word = !words.is_empty() && (open_quote || !line.ends_with(char::is_whitespace));
+        let (start, prefix) = match in_word {
+            true => words.pop().unwrap(),
+            false => (pos, String::new()),
+        };
+        let words: Vec<String> = words.into_iter().map(|(_, word)| word).collect();
+        let candidates = self.candidates(&words, words.len()).into_iter()
+            .filter(|candidate| candidate.starts_with(&prefix))
+            .map(|candidate| match candidate.contains(char::is_whitespace) {
+                true => format!("\"{}\"", candidate),
+                false => candidate,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl<'a> Highlighter for ShellHelper<'a> {}
+
+impl<'a> Hinter for ShellHelper<'a> {
+    type Hint = String;
+}
+
+impl<'a> Validator for ShellHelper<'a> {}
+
+impl<'a> rustyline::Helper for ShellHelper<'a> {}
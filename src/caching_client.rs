@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::ListenerId;
+use super::MBeanClientTrait;
+use super::MBeanInfo;
+use super::MBeanSnapshot;
+use super::Notification;
+use super::ObjectName;
+use super::Result;
+
+#[cfg(feature = "thread-support")]
+use super::MBeanAddress;
+#[cfg(feature = "thread-support")]
+use super::MBeanThreadedClient;
+#[cfg(feature = "thread-support")]
+use super::MBeanThreadedClientOptions;
+
+use super::registry::MBEAN_SERVER_DELEGATE_NAME;
+use super::registry::UNREGISTRATION_NOTIFICATION;
+
+
+/// Descriptor field of MBeans whose `MBeanInfo` never changes.
+const IMMUTABLE_INFO_FIELD: &str = "immutableInfo";
+
+
+/// `MBeanInfo` cached for an MBean.
+struct CachedInfo {
+    /// Time the entry stops being valid, `None` if the info is immutable.
+    expires: Option<Instant>,
+    info: MBeanInfo,
+}
+
+
+/// Wrap an MBean client to cache the `MBeanInfo` of each MBean.
+///
+/// Entries expire after a TTL unless the MBean descriptor declares `immutableInfo`.
+/// All entries for an MBean are dropped when the `MBeanServerDelegate` announces
+/// the MBean was unregistered: the notifications are checked before each lookup.
+///
+/// Reconnections clear the cache, whether made through `reconnect` or behind the cache
+/// on the wrapped client: they are detected with `MBeanClientTrait::connection_id`.
+/// Reconnections of clients without a connection identifier are only covered by the TTL.
+pub struct CachingClient<C>
+    where C: MBeanClientTrait,
+{
+    cache: Mutex<HashMap<ObjectName, CachedInfo>>,
+    /// Identifier of the connection the cached entries were fetched with.
+    connection: Mutex<Option<u64>>,
+    inner: C,
+    listener: Mutex<Option<ListenerId>>,
+    options: CachingClientOptions,
+}
+
+impl<C> CachingClient<C>
+    where C: MBeanClientTrait,
+{
+    /// Cache the `MBeanInfo`s fetched through the given client.
+    pub fn new(inner: C) -> Result<CachingClient<C>> {
+        CachingClient::with_options(inner, CachingClientOptions::default())
+    }
+
+    /// Cache the `MBeanInfo`s fetched through the given client with the given options.
+    ///
+    /// Fails if the unregistration notifications can't be listened to, or if the TTL
+    /// is unlimited but the client has no `connection_id` to detect reconnections with.
+    pub fn with_options(inner: C, options: CachingClientOptions) -> Result<CachingClient<C>> {
+        let connection = inner.connection_id();
+        if options.ttl.is_none() && connection.is_none() {
+            return Err(ErrorKind::Unsupported("connection_id").into());
+        }
+        let client = CachingClient {
+            cache: Mutex::new(HashMap::new()),
+            connection: Mutex::new(connection),
+            inner,
+            listener: Mutex::new(None),
+            options,
+        };
+        if client.options.invalidate_on_unregister {
+            client.listen()?;
+        }
+        Ok(client)
+    }
+
+    /// Access the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Drop the cached `MBeanInfo` of an MBean.
+    pub fn invalidate<S>(&self, mbean: S) -> Result<()>
+        where S: AsRef<str>,
+    {
+        let name = ObjectName::parse(mbean)?;
+        self.cache().remove(&name);
+        Ok(())
+    }
+
+    /// Drop all cached `MBeanInfo`s.
+    pub fn invalidate_all(&self) {
+        self.cache().clear();
+    }
+
+    /// Check if a valid `MBeanInfo` is cached for an MBean.
+    ///
+    /// Unregistration notifications received since the last lookup are not checked.
+    pub fn is_cached<S>(&self, mbean: S) -> bool
+        where S: AsRef<str>,
+    {
+        match ObjectName::parse(mbean) {
+            Err(_) => false,
+            Ok(name) => self.lookup(&name).is_some(),
+        }
+    }
+}
+
+#[cfg(feature = "thread-support")]
+impl CachingClient<MBeanThreadedClient> {
+    /// Re-connect the wrapped client to the given address, clearing the cache.
+    ///
+    /// The wrapped client keeps its options, see `MBeanThreadedClient::reconnect`.
+    pub fn reconnect(&self, address: MBeanAddress) -> Result<()> {
+        self.reconnect_inner(|inner| inner.reconnect(address))
+    }
+
+    /// Re-connect the wrapped client to the given address and options, clearing the cache.
+    ///
+    /// See `MBeanThreadedClient::reconnect_with_options` for details.
+    pub fn reconnect_with_options(
+        &self, address: MBeanAddress, options: MBeanThreadedClientOptions
+    ) -> Result<()> {
+        self.reconnect_inner(|inner| inner.reconnect_with_options(address, options))
+    }
+
+    fn reconnect_inner<F>(&self, reconnect: F) -> Result<()>
+        where F: FnOnce(&MBeanThreadedClient) -> Result<()>,
+    {
+        self.stop_listening();
+        self.invalidate_all();
+        reconnect(&self.inner)?;
+        *self.connection() = self.inner.connection_id();
+        if self.options.invalidate_on_unregister {
+            self.listen()?;
+        }
+        Ok(())
+    }
+}
+
+impl<C> CachingClient<C>
+    where C: MBeanClientTrait,
+{
+    fn cache(&self) -> MutexGuard<'_, HashMap<ObjectName, CachedInfo>> {
+        match self.cache.lock() {
+            Ok(cache) => cache,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Drop the entries invalidated since the last check.
+    ///
+    /// The whole cache is dropped and the listener added again if the wrapped client
+    /// reconnected, or if the notifications can't be polled because some were lost.
+    fn check_invalidations(&self) -> Result<()> {
+        let connection = self.inner.connection_id();
+        let reconnected = {
+            let mut current = self.connection();
+            let reconnected = *current != connection;
+            *current = connection;
+            reconnected
+        };
+        if reconnected {
+            return self.restart();
+        }
+        let listener = *self.listener();
+        let listener = match listener {
+            None => return Ok(()),
+            Some(listener) => listener,
+        };
+        let timeout = Duration::from_millis(0);
+        let notifications = match self.inner.poll_notifications(listener, timeout) {
+            Ok(notifications) => notifications,
+            Err(_) => return self.restart(),
+        };
+        let mut cache = self.cache();
+        for notification in notifications {
+            if notification.notification_type != UNREGISTRATION_NOTIFICATION {
+                continue;
+            }
+            let name = notification.mbean_name.as_ref()
+                .and_then(|name| ObjectName::parse(name).ok());
+            if let Some(name) = name {
+                cache.remove(&name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Listen to the unregistration notifications of the `MBeanServerDelegate`.
+    fn listen(&self) -> Result<()> {
+        let types = vec![UNREGISTRATION_NOTIFICATION.to_string()];
+        let listener = self.inner.add_notification_listener(MBEAN_SERVER_DELEGATE_NAME, types)?;
+        *self.listener() = Some(listener);
+        Ok(())
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Option<u64>> {
+        match self.connection.lock() {
+            Ok(connection) => connection,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn listener(&self) -> MutexGuard<'_, Option<ListenerId>> {
+        match self.listener.lock() {
+            Ok(listener) => listener,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Find the cached `MBeanInfo` of an MBean, if it has not expired.
+    fn lookup(&self, name: &ObjectName) -> Option<MBeanInfo> {
+        let cache = self.cache();
+        let entry = cache.get(name)?;
+        match entry.expires {
+            Some(expires) if expires <= Instant::now() => None,
+            _ => Some(entry.info.clone()),
+        }
+    }
+
+    /// Drop the whole cache and listen to unregistrations again, if enabled.
+    fn restart(&self) -> Result<()> {
+        self.stop_listening();
+        self.invalidate_all();
+        if self.options.invalidate_on_unregister {
+            self.listen()?;
+        }
+        Ok(())
+    }
+
+    /// Cache the `MBeanInfo` of an MBean.
+    fn store(&self, name: ObjectName, info: &MBeanInfo) {
+        let immutable = info.descriptor.get(IMMUTABLE_INFO_FIELD)
+            .map_or(false, |value| value == "true");
+        let expires = match self.options.ttl {
+            _ if immutable => None,
+            None => None,
+            Some(ttl) => Some(Instant::now() + ttl),
+        };
+        self.cache().insert(name, CachedInfo {
+            expires,
+            info: info.clone(),
+        });
+    }
+
+    /// Remove the unregistration listener, ignoring failures of lost connections.
+    fn stop_listening(&self) {
+        let listener = self.listener().take();
+        if let Some(listener) = listener {
+            let _ = self.inner.remove_notification_listener(listener);
+        }
+    }
+}
+
+impl<C> Drop for CachingClient<C>
+    where C: MBeanClientTrait,
+{
+    fn drop(&mut self) {
+        self.stop_listening();
+    }
+}
+
+impl<C> MBeanClientTrait for CachingClient<C>
+    where C: MBeanClientTrait,
+{
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        self.inner.add_notification_listener(mbean, types)
+    }
+
+    fn connection_id(&self) -> Option<u64> {
+        self.inner.connection_id()
+    }
+
+    fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.inner.get_attribute(mbean, attribute)
+    }
+
+    /// Return the cached `MBeanInfo` of the MBean, fetching it if needed.
+    fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        let name = ObjectName::parse(mbean.as_str())?;
+        self.check_invalidations()?;
+        if let Some(info) = self.lookup(&name) {
+            return Ok(info);
+        }
+        let info = self.inner.get_mbean_info(mbean)?;
+        self.store(name, &info);
+        Ok(info)
+    }
+
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.inner.invoke(mbean, operation, params)
+    }
+
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        self.inner.poll_notifications(listener, timeout)
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.inner.query_names(name, query)
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        self.inner.remove_notification_listener(listener)
+    }
+
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        self.inner.set_attribute(mbean, attribute, value)
+    }
+
+    /// Snapshot the MBean with the wrapped client, caching the `MBeanInfo` it includes.
+    fn snapshot_mbean<S>(&self, mbean: S) -> Result<MBeanSnapshot>
+        where S: Into<String>,
+    {
+        let snapshot = self.inner.snapshot_mbean(mbean)?;
+        if let Ok(name) = ObjectName::parse(snapshot.name.as_str()) {
+            self.store(name, &snapshot.info);
+        }
+        Ok(snapshot)
+    }
+}
+
+
+/// Additional `CachingClient` options.
+#[derive(Clone, Debug)]
+pub struct CachingClientOptions {
+    invalidate_on_unregister: bool,
+    ttl: Option<Duration>,
+}
+
+impl CachingClientOptions {
+    /// Drop cached entries when their MBean is unregistered, enabled by default.
+    ///
+    /// Requires the server to have an `MBeanServerDelegate`, as all JMX servers do.
+    pub fn invalidate_on_unregister(mut self, invalidate: bool) -> Self {
+        self.invalidate_on_unregister = invalidate;
+        self
+    }
+
+    /// Set how long the `MBeanInfo` of MBeans without `immutableInfo` is cached.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Cache the `MBeanInfo` of all MBeans until they are unregistered.
+    ///
+    /// Only for clients with a `connection_id`, so reconnections are detected.
+    pub fn ttl_unlimited(mut self) -> Self {
+        self.ttl = None;
+        self
+    }
+}
+
+impl Default for CachingClientOptions {
+    fn default() -> Self {
+        CachingClientOptions {
+            invalidate_on_unregister: true,
+            ttl: Some(Duration::from_secs(60)),
+        }
+    }
+}
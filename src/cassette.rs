@@ -0,0 +1,593 @@
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+
+use failure::ResultExt;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::ListenerId;
+use super::MBeanClientTrait;
+use super::MBeanInfo;
+use super::Notification;
+use super::Result;
+
+
+/// Outcome of a recorded request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response<T> {
+    /// The request failed with the given error message.
+    Err(String),
+
+    /// The request succeeded with the given value.
+    Ok(T),
+}
+
+impl<T> Response<T> {
+    /// Record the outcome of a request.
+    fn record(result: &Result<T>) -> Response<T>
+        where T: Clone,
+    {
+        match *result {
+            Err(ref error) => Response::Err(error.to_string()),
+            Ok(ref value) => Response::Ok(value.clone()),
+        }
+    }
+
+    /// Convert the recorded outcome back into a result.
+    fn replay(self) -> Result<T> {
+        match self {
+            Response::Err(message) => Err(ErrorKind::ReplayedError(message).into()),
+            Response::Ok(value) => Ok(value),
+        }
+    }
+}
+
+
+/// A request made to an MBean client and the response it received.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Interaction {
+    AddNotificationListener {
+        mbean: String,
+        types: Vec<String>,
+        response: Response<ListenerId>,
+    },
+    GetAttribute {
+        mbean: String,
+        attribute: String,
+        response: Response<Value>,
+    },
+    GetMBeanInfo {
+        mbean: String,
+        response: Response<MBeanInfo>,
+    },
+    Invoke {
+        mbean: String,
+        operation: String,
+        params: Vec<Value>,
+        response: Response<Value>,
+    },
+    PollNotifications {
+        listener: ListenerId,
+        response: Response<Vec<Notification>>,
+    },
+    QueryNames {
+        name: String,
+        query: String,
+        response: Response<Vec<String>>,
+    },
+    RemoveNotificationListener {
+        listener: ListenerId,
+        response: Response<()>,
+    },
+    SetAttribute {
+        mbean: String,
+        attribute: String,
+        value: Value,
+        response: Response<()>,
+    },
+}
+
+impl Interaction {
+    /// Check if this interaction was recorded for the same request as `other`.
+    fn same_request(&self, other: &Interaction) -> bool {
+        match (self, other) {
+            (
+                Interaction::AddNotificationListener { mbean, types, .. },
+                Interaction::AddNotificationListener {
+                    mbean: other_mbean, types: other_types, ..
+                },
+            ) => mbean == other_mbean && types == other_types,
+            (
+                Interaction::GetAttribute { mbean, attribute, .. },
+                Interaction::GetAttribute { mbean: other_mbean, attribute: other_attribute, .. },
+            ) => mbean == other_mbean && attribute == other_attribute,
+            (
+                Interaction::GetMBeanInfo { mbean, .. },
+                Interaction::GetMBeanInfo { mbean: other_mbean, .. },
+            ) => mbean == other_mbean,
+            (
+                Interaction::Invoke { mbean, operation, params, .. },
+                Interaction::Invoke {
+                    mbean: other_mbean, operation: other_operation, params: other_params, ..
+                },
+            ) => mbean == other_mbean && operation == other_operation && params == other_params,
+            (
+                Interaction::PollNotifications { listener, .. },
+                Interaction::PollNotifications { listener: other_listener, .. },
+            ) => listener == other_listener,
+            (
+                Interaction::QueryNames { name, query, .. },
+                Interaction::QueryNames { name: other_name, query: other_query, .. },
+            ) => name == other_name && query == other_query,
+            (
+                Interaction::RemoveNotificationListener { listener, .. },
+                Interaction::RemoveNotificationListener { listener: other_listener, .. },
+            ) => listener == other_listener,
+            (
+                Interaction::SetAttribute { mbean, attribute, value, .. },
+                Interaction::SetAttribute {
+                    mbean: other_mbean, attribute: other_attribute, value: other_value, ..
+                },
+            ) => mbean == other_mbean && attribute == other_attribute && value == other_value,
+            _ => false,
+        }
+    }
+}
+
+
+/// Ordered list of interactions with an MBean server.
+///
+/// Cassettes are stored as JSON documents.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Read a cassette from a JSON file.
+    pub fn load<P>(path: P) -> Result<Cassette>
+        where P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|_| ErrorKind::CassetteIo(path.display().to_string()))?;
+        Cassette::from_reader(file)
+    }
+
+    /// Read a cassette from a JSON stream.
+    pub fn from_reader<R>(reader: R) -> Result<Cassette>
+        where R: Read,
+    {
+        let cassette = serde_json::from_reader(reader).with_context(|_| ErrorKind::CassetteFormat)?;
+        Ok(cassette)
+    }
+
+    /// Write the cassette to a JSON file, replacing it if it exists.
+    pub fn save<P>(&self, path: P) -> Result<()>
+        where P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|_| ErrorKind::CassetteIo(path.display().to_string()))?;
+        self.to_writer(file)
+    }
+
+    /// Write the cassette to a JSON stream.
+    pub fn to_writer<W>(&self, writer: W) -> Result<()>
+        where W: Write,
+    {
+        serde_json::to_writer_pretty(writer, self).with_context(|_| ErrorKind::CassetteFormat)?;
+        Ok(())
+    }
+}
+
+
+/// Wrap an MBean client to record all requests and responses into a `Cassette`.
+///
+/// Attribute values are recorded in their JSON form so they can be decoded
+/// into any compatible type when replayed.
+pub struct RecordingClient<C> {
+    cassette: Mutex<Cassette>,
+    inner: C,
+}
+
+impl<C> RecordingClient<C>
+    where C: MBeanClientTrait,
+{
+    /// Start recording interactions with the given client.
+    pub fn new(inner: C) -> RecordingClient<C> {
+        RecordingClient {
+            cassette: Mutex::new(Cassette::default()),
+            inner,
+        }
+    }
+
+    /// Copy of the interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.lock().clone()
+    }
+
+    /// Stop recording and return the wrapped client with the recorded interactions.
+    pub fn into_inner(self) -> (C, Cassette) {
+        let cassette = match self.cassette.into_inner() {
+            Ok(cassette) => cassette,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        (self.inner, cassette)
+    }
+
+    /// Write the interactions recorded so far to a JSON file.
+    pub fn save<P>(&self, path: P) -> Result<()>
+        where P: AsRef<Path>,
+    {
+        self.lock().save(path)
+    }
+}
+
+impl<C> RecordingClient<C> {
+    fn lock(&self) -> MutexGuard<'_, Cassette> {
+        match self.cassette.lock() {
+            Ok(cassette) => cassette,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn record(&self, interaction: Interaction) {
+        self.lock().interactions.push(interaction);
+    }
+}
+
+impl<C> MBeanClientTrait for RecordingClient<C>
+    where C: MBeanClientTrait,
+{
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        let result = self.inner.add_notification_listener(mbean.clone(), types.clone());
+        self.record(Interaction::AddNotificationListener {
+            mbean,
+            types,
+            response: Response::record(&result),
+        });
+        result
+    }
+
+    fn connection_id(&self) -> Option<u64> {
+        self.inner.connection_id()
+    }
+
+    fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let result: Result<Value> = self.inner.get_attribute(mbean.clone(), attribute.clone());
+        self.record(Interaction::GetAttribute {
+            mbean,
+            attribute,
+            response: Response::record(&result),
+        });
+        let value: T = serde_json::from_value(result?).with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(value)
+    }
+
+    fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        let result = self.inner.get_mbean_info(mbean.clone());
+        self.record(Interaction::GetMBeanInfo {
+            mbean,
+            response: Response::record(&result),
+        });
+        result
+    }
+
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let mbean = mbean.into();
+        let operation = operation.into();
+        let result: Result<Value> = self.inner.invoke(
+            mbean.clone(), operation.clone(), params.clone()
+        );
+        self.record(Interaction::Invoke {
+            mbean,
+            operation,
+            params,
+            response: Response::record(&result),
+        });
+        let value: T = serde_json::from_value(result?).with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(value)
+    }
+
+    /// Polls that return no notifications are not recorded.
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        let result = self.inner.poll_notifications(listener, timeout);
+        let empty = result.as_ref().map(|notifications| notifications.is_empty()).unwrap_or(false);
+        if !empty {
+            self.record(Interaction::PollNotifications {
+                listener,
+                response: Response::record(&result),
+            });
+        }
+        result
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let name = name.into();
+        let query = query.into();
+        let result = self.inner.query_names(name.clone(), query.clone());
+        self.record(Interaction::QueryNames {
+            name,
+            query,
+            response: Response::record(&result),
+        });
+        result
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        let result = self.inner.remove_notification_listener(listener);
+        self.record(Interaction::RemoveNotificationListener {
+            listener,
+            response: Response::record(&result),
+        });
+        result
+    }
+
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let result = self.inner.set_attribute(mbean.clone(), attribute.clone(), value.clone());
+        self.record(Interaction::SetAttribute {
+            mbean,
+            attribute,
+            value,
+            response: Response::record(&result),
+        });
+        result
+    }
+}
+
+
+/// MBean client serving responses from a `Cassette`.
+///
+/// Recorded responses are returned in the order they were recorded for each request.
+/// Once all the responses for a request are used, the last one is returned for any
+/// further identical requests so polling loops can run for longer than the recording.
+/// Requests that were never recorded fail with `ErrorKind::ReplayMissing`.
+///
+/// Notifications are the exception: each recorded poll is replayed once, without
+/// waiting, and further polls return no notifications.
+pub struct ReplayClient {
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl ReplayClient {
+    /// Replay the interactions in the given cassette.
+    pub fn new(cassette: Cassette) -> ReplayClient {
+        let interactions = cassette.interactions.into_iter()
+            .map(|interaction| (interaction, false))
+            .collect();
+        ReplayClient {
+            interactions: Mutex::new(interactions),
+        }
+    }
+
+    /// Replay the interactions in a cassette JSON file.
+    pub fn load<P>(path: P) -> Result<ReplayClient>
+        where P: AsRef<Path>,
+    {
+        Cassette::load(path).map(ReplayClient::new)
+    }
+}
+
+impl ReplayClient {
+    /// Find the next recorded interaction for the given request.
+    fn replay(&self, request: Interaction, description: String) -> Result<Interaction> {
+        match self.next(&request, true) {
+            Some(interaction) => Ok(interaction),
+            None => Err(ErrorKind::ReplayMissing(description).into()),
+        }
+    }
+
+    /// Find the first unused interaction for the request, or the last used one if `reuse` is set.
+    fn next(&self, request: &Interaction, reuse: bool) -> Option<Interaction> {
+        let mut interactions = match self.interactions.lock() {
+            Ok(interactions) => interactions,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut last = None;
+        for (idx, &mut (ref interaction, ref mut used)) in interactions.iter_mut().enumerate() {
+            if !interaction.same_request(request) {
+                continue;
+            }
+            if !*used {
+                *used = true;
+                return Some(interaction.clone());
+            }
+            last = Some(idx);
+        }
+        match last {
+            Some(idx) if reuse => Some(interactions[idx].0.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl MBeanClientTrait for ReplayClient {
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        let description = format!("add_notification_listener({}, {:?})", mbean, types);
+        let request = Interaction::AddNotificationListener {
+            mbean,
+            types,
+            response: Response::Err(String::new()),
+        };
+        match self.replay(request, description)? {
+            Interaction::AddNotificationListener { response, .. } => response.replay(),
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    /// Replays never reconnect, the identifier never changes.
+    fn connection_id(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let description = format!("get_attribute({}, {})", mbean, attribute);
+        let request = Interaction::GetAttribute {
+            mbean,
+            attribute,
+            response: Response::Ok(Value::Null),
+        };
+        match self.replay(request, description)? {
+            Interaction::GetAttribute { response, .. } => {
+                let value = response.replay()?;
+                let value: T = serde_json::from_value(value)
+                    .with_context(|_| ErrorKind::ValueDecode)?;
+                Ok(value)
+            },
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        let description = format!("get_mbean_info({})", mbean);
+        let request = Interaction::GetMBeanInfo {
+            mbean,
+            response: Response::Err(String::new()),
+        };
+        match self.replay(request, description)? {
+            Interaction::GetMBeanInfo { response, .. } => response.replay(),
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let mbean = mbean.into();
+        let operation = operation.into();
+        let description = format!("invoke({}, {}, {:?})", mbean, operation, params);
+        let request = Interaction::Invoke {
+            mbean,
+            operation,
+            params,
+            response: Response::Ok(Value::Null),
+        };
+        match self.replay(request, description)? {
+            Interaction::Invoke { response, .. } => {
+                let value = response.replay()?;
+                let value: T = serde_json::from_value(value)
+                    .with_context(|_| ErrorKind::ValueDecode)?;
+                Ok(value)
+            },
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn poll_notifications(
+        &self, listener: ListenerId, _timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        let request = Interaction::PollNotifications {
+            listener,
+            response: Response::Ok(Vec::new()),
+        };
+        match self.next(&request, false) {
+            None => Ok(Vec::new()),
+            Some(Interaction::PollNotifications { response, .. }) => response.replay(),
+            Some(_) => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let name = name.into();
+        let query = query.into();
+        let description = format!("query_names({}, {})", name, query);
+        let request = Interaction::QueryNames {
+            name,
+            query,
+            response: Response::Ok(Vec::new()),
+        };
+        match self.replay(request, description)? {
+            Interaction::QueryNames { response, .. } => response.replay(),
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        let description = format!("remove_notification_listener({})", listener.0);
+        let request = Interaction::RemoveNotificationListener {
+            listener,
+            response: Response::Ok(()),
+        };
+        match self.replay(request, description)? {
+            Interaction::RemoveNotificationListener { response, .. } => response.replay(),
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let description = format!("set_attribute({}, {}, {})", mbean, attribute, value);
+        let request = Interaction::SetAttribute {
+            mbean,
+            attribute,
+            value,
+            response: Response::Ok(()),
+        };
+        match self.replay(request, description)? {
+            Interaction::SetAttribute { response, .. } => response.replay(),
+            _ => unreachable!("replayed interaction does not match the request"),
+        }
+    }
+}
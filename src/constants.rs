@@ -1,10 +1,11 @@
-pub static JAVA_LANG_INTEGER: &'static str = "java.lang.Integer";
+pub static JAVA_IO_FILE: &'static str = "java.io.File";
+pub static JAVA_LANG_CLASS_LOADER: &'static str = "java.lang.ClassLoader";
 pub static JAVA_LANG_OBJECT: &'static str = "java.lang.Object";
-pub static JAVA_REFLECT_ARRAY: &'static str = "java.lang.reflect.Array";
+pub static JAVA_NET_URL: &'static str = "java.net.URL";
+pub static JAVA_NET_URL_CLASS_LOADER: &'static str = "java.net.URLClassLoader";
+pub static JAVA_UTIL_BI_FUNCTION: &'static str = "java.util.function.BiFunction";
 
+pub static JMX_CONNECTOR: &'static str = "javax.management.remote.JMXConnector";
 pub static JMX_CONNECTOR_FACTORY: &'static str = "javax.management.remote.JMXConnectorFactory";
-pub static JMX_MBEAN_ATTRIBUTE_INFO: &'static str = "javax.management.MBeanAttributeInfo";
-pub static JMX_MBEAN_FEATURE_INFO: &'static str = "javax.management.MBeanFeatureInfo";
-pub static JMX_OBJECT_NAME: &'static str = "javax.management.ObjectName";
-pub static JMX_QUERY_EXP: &'static str = "javax.management.QueryExp";
+pub static JMX_HELPER: &'static str = "net.spogliani.jmx.Helper";
 pub static JMX_SERVICE_URL: &'static str = "javax.management.remote.JMXServiceURL";
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use super::MBeanClientTrait;
+use super::MBeanInfo;
+use super::ObjectName;
+use super::Result;
+use super::Sample;
+use super::dump::AttributeState;
+
+
+/// Descriptor field and value marking an attribute as a counter.
+const METRIC_TYPE_FIELD: &str = "metricType";
+const METRIC_TYPE_COUNTER: &str = "counter";
+
+
+/// Change of a counter between two samples.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CounterRate {
+    /// Name of the counter attribute.
+    pub attribute: String,
+
+    /// Increase of the counter since the previous sample.
+    ///
+    /// After a reset this is the value of the counter, assuming it restarted from zero.
+    pub delta: f64,
+
+    /// Name of the MBean the counter was read from.
+    pub name: String,
+
+    /// Increase of the counter per second since the previous sample.
+    pub rate: f64,
+
+    /// The counter decreased since the previous sample, for example because the
+    /// server restarted.
+    pub reset: bool,
+
+    /// Time of the sample the rate was computed at.
+    pub timestamp: SystemTime,
+}
+
+
+/// Compute rates and deltas of monotonically increasing attributes from `Sample`s.
+///
+/// Attributes are marked as counters explicitly, with an ObjectName pattern and an
+/// attribute name, or from the `metricType=counter` field of their descriptor.
+/// Samples of attributes not marked as counters are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct Counters {
+    /// Attributes marked from descriptors, by MBean name.
+    described: HashMap<String, Vec<String>>,
+    /// Last numeric value and time of each counter, by MBean and attribute name.
+    last: HashMap<(String, String), (f64, SystemTime)>,
+    /// Attributes marked explicitly, with the pattern of the MBeans they belong to.
+    marked: Vec<(ObjectName, String)>,
+}
+
+impl Counters {
+    /// Create a tracker with no counters.
+    pub fn new() -> Counters {
+        Counters::default()
+    }
+
+    /// Check if an attribute of an MBean is marked as a counter.
+    pub fn is_counter(&self, name: &str, attribute: &str) -> bool {
+        let described = self.described.get(name)
+            .map_or(false, |attributes| attributes.iter().any(|marked| marked == attribute));
+        if described {
+            return true;
+        }
+        let name = match ObjectName::parse(name) {
+            Err(_) => return false,
+            Ok(name) => name,
+        };
+        self.marked.iter()
+            .any(|(pattern, marked)| marked == attribute && pattern.matches(&name))
+    }
+
+    /// Fetch the information of the MBeans matching a pattern and mark their counters.
+    ///
+    /// MBeans that can't be described are skipped.
+    pub fn learn<C, S>(&mut self, client: &C, pattern: S) -> Result<()>
+        where C: MBeanClientTrait,
+              S: Into<String>,
+    {
+        for name in client.query_names(pattern, "")? {
+            if let Ok(info) = client.get_mbean_info(name.as_str()) {
+                self.learn_info(name, &info);
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark the attributes of an MBean described with `metricType=counter` as counters.
+    pub fn learn_info<S>(&mut self, name: S, info: &MBeanInfo)
+        where S: Into<String>,
+    {
+        let counters: Vec<String> = info.attributes.iter()
+            .filter(|attribute| {
+                attribute.descriptor.get(METRIC_TYPE_FIELD)
+                    .map_or(false, |kind| kind.eq_ignore_ascii_case(METRIC_TYPE_COUNTER))
+            })
+            .map(|attribute| attribute.name.clone())
+            .collect();
+        let name = name.into();
+        if counters.is_empty() {
+            self.described.remove(&name);
+        } else {
+            self.described.insert(name, counters);
+        }
+    }
+
+    /// Mark an attribute of the MBeans matching a pattern as a counter.
+    pub fn mark<S1, S2>(&mut self, pattern: S1, attribute: S2) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+    {
+        let pattern = ObjectName::parse(pattern.as_ref())?;
+        self.marked.push((pattern, attribute.into()));
+        Ok(())
+    }
+
+    /// Record a sample and compute the change of the counter since the previous one.
+    ///
+    /// Returns `None` for the first sample of a counter, for samples of attributes
+    /// that are not counters or are not numbers and for samples older than the last.
+    /// Errors are skipped: the next rate is computed against the last value read.
+    pub fn update(&mut self, sample: &Sample) -> Option<CounterRate> {
+        let value = match sample.value {
+            AttributeState::Value(ref value) => value.as_f64()?,
+            AttributeState::Error(_) => return None,
+        };
+        if !self.is_counter(&sample.name, &sample.attribute) {
+            return None;
+        }
+        let key = (sample.name.clone(), sample.attribute.clone());
+        let previous = self.last.get(&key).cloned();
+        let (last, since) = match previous {
+            None => {
+                self.last.insert(key, (value, sample.timestamp));
+                return None;
+            },
+            Some(previous) => previous,
+        };
+        let elapsed = sample.timestamp.duration_since(since).ok()?.as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        self.last.insert(key, (value, sample.timestamp));
+        let reset = value < last;
+        let delta = if reset { value } else { value - last };
+        Some(CounterRate {
+            attribute: sample.attribute.clone(),
+            delta,
+            name: sample.name.clone(),
+            rate: delta / elapsed,
+            reset,
+            timestamp: sample.timestamp,
+        })
+    }
+}
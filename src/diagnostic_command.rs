@@ -0,0 +1,295 @@
+//! Run `jcmd` diagnostic commands through the `DiagnosticCommand` MBean.
+//!
+//! HotSpot based JVMs expose each diagnostic command as an operation named after
+//! the command: `GC.class_histogram` is invoked as `gcClassHistogram`.
+//! The operations take the arguments of the command as an array of strings and
+//! return the text `jcmd` would print.
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::MBeanClientTrait;
+use super::MBeanOperation;
+use super::Result;
+
+
+/// Name of the `DiagnosticCommandMBean`.
+pub const DIAGNOSTIC_COMMAND_MBEAN_NAME: &str = "com.sun.management:type=DiagnosticCommand";
+
+/// Descriptor fields describing a command and its arguments.
+const ARGUMENTS_PREFIX: &str = "dcmd.arguments.";
+const ARGUMENT_FIELD: &str = ".dcmd.arg.";
+const DESCRIPTION_FIELD: &str = "dcmd.description";
+const ENABLED_FIELD: &str = "dcmd.enabled";
+const HELP_FIELD: &str = "dcmd.help";
+const NAME_FIELD: &str = "dcmd.name";
+
+
+/// Row of the `GC.class_histogram` output.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ClassHistogramRow {
+    /// Total size of the instances of the class, in bytes.
+    pub bytes: u64,
+    pub class_name: String,
+    pub instances: u64,
+
+    /// Module of the class, with its version, requires Java 9 or later.
+    pub module: Option<String>,
+
+    /// Position of the class in the histogram, starting from 1 for the largest.
+    pub rank: u32,
+}
+
+
+/// Argument or option accepted by a diagnostic command.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DiagnosticArgument {
+    pub default: Option<String>,
+    pub description: String,
+    pub is_mandatory: bool,
+
+    /// The argument can be given more than once.
+    pub is_multiple: bool,
+
+    /// The argument is an option given as `name=value` rather than by position.
+    pub is_option: bool,
+    pub name: String,
+
+    /// Position of the argument, `-1` for options.
+    pub position: i32,
+
+    /// Type of the value, such as `BOOLEAN`, `INT`, `STRING` or `MEMORY SIZE`.
+    pub type_name: String,
+}
+
+
+/// Diagnostic command available on a JVM.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DiagnosticCommand {
+    /// Arguments of the command: positional arguments first, in order, then options by name.
+    pub arguments: Vec<DiagnosticArgument>,
+    pub description: String,
+    pub enabled: bool,
+
+    /// Help text, as printed by `jcmd <pid> help <command>`.
+    pub help: String,
+
+    /// Name of the command, such as `GC.class_histogram`.
+    pub name: String,
+
+    /// Name of the MBean operation running the command.
+    pub operation: String,
+}
+
+impl DiagnosticCommand {
+    /// Describe a command from the descriptor of its operation.
+    ///
+    /// Returns `None` for operations that are not diagnostic commands.
+    pub fn from_operation(operation: &MBeanOperation) -> Option<DiagnosticCommand> {
+        let fields = &operation.descriptor;
+        let name = fields.get(NAME_FIELD)?.clone();
+        let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+
+        // Group the flattened argument fields by argument.
+        let mut arguments: BTreeMap<&str, DiagnosticArgument> = BTreeMap::new();
+        for (key, value) in fields {
+            if !key.starts_with(ARGUMENTS_PREFIX) {
+                continue;
+            }
+            let key = &key[ARGUMENTS_PREFIX.len()..];
+            let (argument, field) = match key.find(ARGUMENT_FIELD) {
+                None => continue,
+                Some(idx) => (&key[..idx], &key[idx + ARGUMENT_FIELD.len()..]),
+            };
+            let argument = arguments.entry(argument).or_insert_with(|| DiagnosticArgument {
+                name: argument.to_string(),
+                position: -1,
+                ..Default::default()
+            });
+            match field {
+                "default" => argument.default = Some(value.clone()),
+                "description" => argument.description = value.clone(),
+                "isMandatory" => argument.is_mandatory = value == "true",
+                "isMultiple" => argument.is_multiple = value == "true",
+                "isOption" => argument.is_option = value == "true",
+                "name" => argument.name = value.clone(),
+                "position" => argument.position = value.parse().unwrap_or(-1),
+                "type" => argument.type_name = value.clone(),
+                _ => (),
+            }
+        }
+        let mut arguments: Vec<DiagnosticArgument> = arguments.into_values().collect();
+        arguments.sort_by(|left, right| {
+            (left.is_option, left.position, &left.name)
+                .cmp(&(right.is_option, right.position, &right.name))
+        });
+
+        Some(DiagnosticCommand {
+            arguments,
+            description: field(DESCRIPTION_FIELD),
+            enabled: fields.get(ENABLED_FIELD).map_or(true, |enabled| enabled == "true"),
+            help: field(HELP_FIELD),
+            name,
+            operation: operation.name.clone(),
+        })
+    }
+}
+
+
+/// Proxy for the `com.sun.management.DiagnosticCommandMBean`.
+///
+/// Only available with HotSpot based JVMs.
+pub struct DiagnosticCommandMBean<'a, C: 'a> {
+    client: &'a C,
+}
+
+impl<'a, C> DiagnosticCommandMBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> DiagnosticCommandMBean<'a, C> {
+        DiagnosticCommandMBean { client }
+    }
+
+    /// Statistics about the objects on the heap, from `GC.class_histogram`.
+    ///
+    /// Only objects reachable from the GC roots are counted unless `all` is set.
+    pub fn class_histogram(&self, all: bool) -> Result<Vec<ClassHistogramRow>> {
+        let arguments: &[&str] = if all { &["-all"] } else { &[] };
+        let text = self.execute("GC.class_histogram", arguments)?;
+        parse_class_histogram(&text)
+    }
+
+    /// Diagnostic commands available on the JVM, sorted by name.
+    pub fn commands(&self) -> Result<Vec<DiagnosticCommand>> {
+        let info = self.client.get_mbean_info(DIAGNOSTIC_COMMAND_MBEAN_NAME)?;
+        let mut commands: Vec<DiagnosticCommand> = info.operations.iter()
+            .filter_map(DiagnosticCommand::from_operation)
+            .collect();
+        commands.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(commands)
+    }
+
+    /// Run a diagnostic command and return its output.
+    ///
+    /// Arguments are given as on the `jcmd` command line: options as `name=value`
+    /// and positional arguments as values.
+    ///
+    /// The `MBeanInfo` of the MBean is fetched to find the signature of the operation:
+    /// wrap the client in a `CachingClient` to avoid fetching it for every command.
+    pub fn execute<S: AsRef<str>>(&self, command: &str, arguments: &[S]) -> Result<String> {
+        let operation = operation_name(command);
+        let arguments: Vec<Value> = arguments.iter()
+            .map(|argument| Value::from(argument.as_ref()))
+            .collect();
+
+        // Operations of commands that accept no arguments have no parameters at all.
+        let info = self.client.get_mbean_info(DIAGNOSTIC_COMMAND_MBEAN_NAME)?;
+        let no_parameters = info.operations.iter()
+            .find(|candidate| candidate.name == operation)
+            .map_or(false, |candidate| candidate.signature.is_empty());
+        let params = if no_parameters && arguments.is_empty() {
+            Vec::new()
+        } else {
+            vec![Value::Array(arguments)]
+        };
+        self.client.invoke(DIAGNOSTIC_COMMAND_MBEAN_NAME, operation, params)
+    }
+
+    /// Heap layout and usage, from `GC.heap_info`.
+    pub fn gc_heap_info(&self) -> Result<String> {
+        self.execute::<&str>("GC.heap_info", &[])
+    }
+
+    /// Stack traces of all threads, from `Thread.print`.
+    ///
+    /// Ownable synchronizers locked by each thread are listed if `locks` is set.
+    pub fn thread_print(&self, locks: bool) -> Result<String> {
+        let arguments: &[&str] = if locks { &["-l"] } else { &[] };
+        self.execute("Thread.print", arguments)
+    }
+
+    /// VM flags set on the command line or by the JVM, from `VM.flags`.
+    ///
+    /// All flags, including defaults, are listed if `all` is set.
+    pub fn vm_flags(&self, all: bool) -> Result<String> {
+        let arguments: &[&str] = if all { &["-all"] } else { &[] };
+        self.execute("VM.flags", arguments)
+    }
+
+    /// System properties of the JVM, from `VM.system_properties`.
+    pub fn vm_system_properties(&self) -> Result<String> {
+        self.execute::<&str>("VM.system_properties", &[])
+    }
+}
+
+
+/// Name of the MBean operation running a diagnostic command.
+///
+/// Follows the naming of the JVM: the first segment of the command is lower cased
+/// and the segments after each `.` or `_` are capitalised.
+pub fn operation_name(command: &str) -> String {
+    let mut name = String::with_capacity(command.len());
+    let mut first = true;
+    let mut upper = false;
+    for c in command.chars() {
+        if c == '.' || c == '_' {
+            first = false;
+            upper = true;
+        } else if upper {
+            upper = false;
+            name.extend(c.to_uppercase());
+        } else if first {
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Parse the output of `GC.class_histogram` into rows.
+///
+/// Headers and the totals line are skipped.
+pub fn parse_class_histogram(text: &str) -> Result<Vec<ClassHistogramRow>> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        let rank = match words.next() {
+            Some(word) if word.ends_with(':') => &word[..word.len() - 1],
+            _ => continue,
+        };
+        let row = parse_class_histogram_row(rank, words);
+        match row {
+            None => return Err(ErrorKind::DiagnosticOutput(line.trim().to_string()).into()),
+            Some(row) => rows.push(row),
+        }
+    }
+    Ok(rows)
+}
+
+fn parse_class_histogram_row<'a, I>(rank: &str, mut words: I) -> Option<ClassHistogramRow>
+    where I: Iterator<Item = &'a str>,
+{
+    let rank = rank.parse().ok()?;
+    let instances = words.next()?.parse().ok()?;
+    let bytes = words.next()?.parse().ok()?;
+    let class_name = words.next()?.to_string();
+    let module = match words.next() {
+        None => None,
+        Some(module) if module.starts_with('(') && module.ends_with(')') => {
+            Some(module[1..module.len() - 1].to_string())
+        },
+        Some(_) => return None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(ClassHistogramRow {
+        bytes,
+        class_name,
+        instances,
+        module,
+        rank,
+    })
+}
@@ -0,0 +1,151 @@
+//! Capture the state of an MBean server and compare captures.
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use super::MBeanInfo;
+use super::MBeanSnapshot;
+
+
+/// Value of an attribute in a dump, or the reason it could not be read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeState {
+    /// The attribute could not be read for the given reason.
+    Error(String),
+
+    /// The attribute had the given value.
+    Value(Value),
+}
+
+
+/// Something that changed between two dumps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub after: T,
+    pub before: T,
+}
+
+
+/// Differences between two dumps of the same MBean server.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diff {
+    /// MBeans only present in the newer dump.
+    pub added: Vec<String>,
+
+    /// MBeans present in both dumps that changed.
+    pub changed: BTreeMap<String, MBeanDiff>,
+
+    /// MBeans only present in the older dump.
+    pub removed: Vec<String>,
+
+    /// MBeans that could not be captured in either dump, so can't be compared.
+    pub unavailable: Vec<String>,
+}
+
+impl Diff {
+    /// Check if the dumps are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty()
+            && self.removed.is_empty() && self.unavailable.is_empty()
+    }
+}
+
+
+/// Differences between two snapshots of the same MBean.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MBeanDiff {
+    /// Attributes that were added, removed or changed value.
+    ///
+    /// Attributes missing from one of the snapshots are `None` on that side.
+    pub attributes: BTreeMap<String, Change<Option<AttributeState>>>,
+
+    /// The MBean metadata, if it changed.
+    pub info: Option<Change<MBeanInfo>>,
+}
+
+impl MBeanDiff {
+    /// Compare two snapshots of an MBean.
+    fn compare(before: &MBeanSnapshot, after: &MBeanSnapshot) -> MBeanDiff {
+        let before_attributes = MBeanDiff::attributes(before);
+        let after_attributes = MBeanDiff::attributes(after);
+        let names: BTreeSet<&String> = before_attributes.keys()
+            .chain(after_attributes.keys())
+            .collect();
+        let mut attributes = BTreeMap::new();
+        for name in names {
+            let before = before_attributes.get(name).cloned();
+            let after = after_attributes.get(name).cloned();
+            if before != after {
+                attributes.insert(name.clone(), Change { after, before });
+            }
+        }
+        let info = if before.info == after.info {
+            None
+        } else {
+            Some(Change {
+                after: after.info.clone(),
+                before: before.info.clone(),
+            })
+        };
+        MBeanDiff { attributes, info }
+    }
+
+    /// Merge values and errors of a snapshot into a single map.
+    fn attributes(snapshot: &MBeanSnapshot) -> BTreeMap<String, AttributeState> {
+        let values = snapshot.attributes.iter()
+            .map(|(name, value)| (name.clone(), AttributeState::Value(value.clone())));
+        let errors = snapshot.errors.iter()
+            .map(|(name, error)| (name.clone(), AttributeState::Error(error.clone())));
+        values.chain(errors).collect()
+    }
+
+    /// Check if the snapshots are identical.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.info.is_none()
+    }
+}
+
+
+/// Every MBean matching a pattern with its metadata and readable attribute values.
+///
+/// Dumps are serializable so they can be stored and compared later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerDump {
+    /// MBeans that could not be captured, with the reason.
+    pub errors: BTreeMap<String, String>,
+
+    /// Snapshots of the MBeans, by name.
+    pub mbeans: BTreeMap<String, MBeanSnapshot>,
+}
+
+impl ServerDump {
+    /// Compare this dump with a newer one.
+    pub fn diff(&self, other: &ServerDump) -> Diff {
+        let mut diff = Diff::default();
+        let names: BTreeSet<&String> = self.mbeans.keys()
+            .chain(self.errors.keys())
+            .chain(other.mbeans.keys())
+            .chain(other.errors.keys())
+            .collect();
+        for name in names {
+            if self.errors.contains_key(name) || other.errors.contains_key(name) {
+                diff.unavailable.push(name.clone());
+                continue;
+            }
+            match (self.mbeans.get(name), other.mbeans.get(name)) {
+                (None, Some(_)) => diff.added.push(name.clone()),
+                (Some(_), None) => diff.removed.push(name.clone()),
+                (Some(before), Some(after)) => {
+                    let changes = MBeanDiff::compare(before, after);
+                    if !changes.is_empty() {
+                        diff.changed.insert(name.clone(), changes);
+                    }
+                },
+                (None, None) => unreachable!("MBean name not found in either dump"),
+            }
+        }
+        diff
+    }
+}
@@ -54,6 +54,37 @@ impl From<J4RsError> for Error {
 /// Exhaustive list of possible errors emitted by this crate.
 #[derive(Debug, Fail)]
 pub enum ErrorKind {
+    #[cfg(feature = "record-replay")]
+    #[fail(display = "could not decode or encode cassette")]
+    CassetteFormat,
+
+    #[cfg(feature = "record-replay")]
+    #[fail(display = "could not access cassette file '{}'", _0)]
+    CassetteIo(String),
+
+    #[fail(display = "could not parse diagnostic command output: '{}'", _0)]
+    DiagnosticOutput(String),
+
+    #[cfg(feature = "exporter")]
+    #[fail(display = "invalid exporter configuration: {}", _0)]
+    ExporterConfig(String),
+
+    #[cfg(feature = "exporter")]
+    #[fail(display = "could not read exporter configuration '{}'", _0)]
+    ExporterConfigIo(String),
+
+    #[fail(display = "java helper operation '{}' failed", _0)]
+    HelperInvoke(&'static str),
+
+    #[fail(display = "could not load the java helper class")]
+    HelperLoad,
+
+    #[fail(display = "could not write the java helper jar to '{}'", _0)]
+    HelperWrite(String),
+
+    #[fail(display = "invalid object name '{}'", _0)]
+    InvalidObjectName(String),
+
     #[fail(display = "could not cast java object to class '{}'", _0)]
     JavaCast(String),
 
@@ -72,15 +103,48 @@ pub enum ErrorKind {
     #[fail(display = "could not invoke static method '{}.{}'", _0, _1)]
     JavaInvokeStatic(&'static str, &'static str),
 
+    #[fail(display = "could not attach the current thread to the JVM")]
+    JvmAttach,
+
     #[fail(display = "could not initialise JVM instance")]
     JvmInit,
 
+    #[fail(display = "no logging framework has a logger named '{}'", _0)]
+    LoggerNotFound(String),
+
+    #[cfg(feature = "mock")]
+    #[fail(display = "mock client failure: {}", _0)]
+    MockFailure(String),
+
     #[fail(display = "the JMX client is not connected")]
     NotConnected,
 
+    #[fail(display = "could not create flight recording file '{}'", _0)]
+    RecordingFile(String),
+
+    #[fail(display = "could not write the data of flight recording {}", _0)]
+    RecordingWrite(i64),
+
+    #[cfg(feature = "record-replay")]
+    #[fail(display = "replayed error: {}", _0)]
+    ReplayedError(String),
+
+    #[cfg(feature = "record-replay")]
+    #[fail(display = "no recorded response for request {}", _0)]
+    ReplayMissing(String),
+
     #[fail(display = "could not cast java object to rust '{}' type", _0)]
     RustCast(&'static str),
 
+    #[fail(display = "the client does not support '{}'", _0)]
+    Unsupported(&'static str),
+
+    #[fail(display = "could not decode value into the requested type")]
+    ValueDecode,
+
+    #[fail(display = "could not encode value")]
+    ValueEncode,
+
     #[cfg(feature = "thread-support")]
     #[fail(display = "could not decode mbean attribute value")]
     WorkerDecode,
@@ -93,6 +157,10 @@ pub enum ErrorKind {
     #[fail(display = "could not send request to background worker")]
     WorkerNoSend,
 
+    #[cfg(feature = "thread-support")]
+    #[fail(display = "the background worker requests buffer is full")]
+    WorkerQueueFull,
+
     #[cfg(feature = "thread-support")]
     #[fail(display = "could not spawn background worker thread")]
     WorkerSpawn,
@@ -0,0 +1,742 @@
+//! Export MBean attributes as Prometheus metrics.
+//!
+//! Rules are configured with the YAML format of the Prometheus `jmx_exporter`:
+//!
+//! ```yaml
+//! lowercaseOutputName: true
+//! whitelistObjectNames: ["java.lang:type=Memory"]
+//! rules:
+//!   - pattern: 'java.lang<type=Memory><HeapMemoryUsage>(\w+)'
+//!     name: jvm_memory_heap_$1_bytes
+//!     type: GAUGE
+//! ```
+//!
+//! Each attribute, and each item of `CompositeData` and `TabularData` attributes,
+//! is matched against the rules in the `domain<key=value, ...><key1, key2, ...>attrName: value`
+//! format. The first matching rule decides how the value is exported.
+//! Patterns are Rust regular expressions rather than Java ones: look-around is not supported.
+//!
+//! `TabularData` rows are labelled with their index columns and all other columns
+//! are exported as values, as `jmx_exporter` does.
+//! When the snapshot does not know the index of a table, for example with mock clients,
+//! columns with string values are used as labels instead.
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use failure::ResultExt;
+use regex::Captures;
+use regex::Regex;
+use serde_json::Value;
+use serde_yaml;
+
+use super::ErrorKind;
+use super::MBeanClientTrait;
+use super::MBeanSnapshot;
+use super::ObjectName;
+use super::Registry;
+use super::Result;
+
+
+/// Type of an exported metric.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    #[default]
+    Untyped,
+}
+
+impl MetricType {
+    /// Name of the type in the OpenMetrics text format.
+    fn openmetrics_name(self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Untyped => "unknown",
+        }
+    }
+}
+
+/// Configuration of a single export rule.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RuleConfig {
+    /// Convert attribute names to snake case before they are matched.
+    pub attr_name_snake_case: bool,
+
+    /// Help text of the metric, can reference capture groups.
+    pub help: Option<String>,
+
+    /// Labels of the metric: both names and values can reference capture groups.
+    pub labels: BTreeMap<String, String>,
+
+    /// Name of the metric, can reference capture groups.
+    ///
+    /// Attributes matched by a rule without a name are exported with the default format.
+    pub name: Option<String>,
+
+    /// Regular expression the attribute must match, matches everything if not set.
+    pub pattern: Option<String>,
+
+    /// Type of the metric.
+    #[serde(rename = "type")]
+    pub metric_type: MetricType,
+
+    /// Value of the metric instead of the attribute value, can reference capture groups.
+    pub value: Option<String>,
+
+    /// Factor the value of the metric is multiplied by.
+    pub value_factor: f64,
+}
+
+impl Default for RuleConfig {
+    fn default() -> RuleConfig {
+        RuleConfig {
+            attr_name_snake_case: false,
+            help: None,
+            labels: BTreeMap::new(),
+            name: None,
+            pattern: None,
+            metric_type: MetricType::Untyped,
+            value: None,
+            value_factor: 1.0,
+        }
+    }
+}
+
+
+/// Configuration of an `Exporter`, compatible with the `jmx_exporter` YAML format.
+///
+/// Unknown options are ignored.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExporterConfig {
+    /// Object name patterns of MBeans to skip.
+    #[serde(alias = "excludeObjectNames")]
+    pub blacklist_object_names: Vec<String>,
+
+    /// Convert label names to lower case.
+    pub lowercase_output_label_names: bool,
+
+    /// Convert metric names to lower case.
+    pub lowercase_output_name: bool,
+
+    /// Ordered list of export rules, all attributes use the default format if empty.
+    pub rules: Vec<RuleConfig>,
+
+    /// Object name patterns of MBeans to export, all MBeans are exported if empty.
+    #[serde(alias = "includeObjectNames")]
+    pub whitelist_object_names: Vec<String>,
+}
+
+impl ExporterConfig {
+    /// Load a configuration from a YAML file.
+    pub fn load<P>(path: P) -> Result<ExporterConfig>
+        where P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut yaml = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut yaml))
+            .with_context(|_| ErrorKind::ExporterConfigIo(path.display().to_string()))?;
+        ExporterConfig::from_yaml(&yaml)
+    }
+
+    /// Parse a configuration from a YAML document.
+    pub fn from_yaml(yaml: &str) -> Result<ExporterConfig> {
+        let config = serde_yaml::from_str(yaml)
+            .with_context(|error| ErrorKind::ExporterConfig(error.to_string()))?;
+        Ok(config)
+    }
+}
+
+
+/// A single sample of a metric.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+
+/// All samples of a metric.
+///
+/// Names of counter families do not include the `_total` suffix of their samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricFamily {
+    pub help: String,
+    pub metric_type: MetricType,
+    pub name: String,
+    pub samples: Vec<Sample>,
+}
+
+
+/// Collection of metric families, ready to be rendered.
+///
+/// Families are kept in the order they are first added.
+/// Samples with the same name and labels as an existing sample are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    families: Vec<MetricFamily>,
+    index: HashMap<String, usize>,
+    seen: HashSet<(String, Vec<(String, String)>)>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Add a sample to the family with the given name, creating the family if needed.
+    ///
+    /// The type and help of existing families are not changed.
+    /// Returns `false` if the sample was ignored as a duplicate.
+    pub fn add<S1, S2>(
+        &mut self, name: S1, metric_type: MetricType, help: S2,
+        labels: Vec<(String, String)>, value: f64
+    ) -> bool
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let mut name = name.into();
+        if metric_type == MetricType::Counter && name.ends_with("_total") {
+            let len = name.len() - "_total".len();
+            name.truncate(len);
+        }
+        if !self.seen.insert((name.clone(), labels.clone())) {
+            return false;
+        }
+        let families = &mut self.families;
+        let idx = *self.index.entry(name.clone()).or_insert_with(|| {
+            families.push(MetricFamily {
+                help: help.into(),
+                metric_type,
+                name,
+                samples: Vec::new(),
+            });
+            families.len() - 1
+        });
+        self.families[idx].samples.push(Sample { labels, value });
+        true
+    }
+
+    /// Metric families in the collection.
+    pub fn families(&self) -> &[MetricFamily] {
+        &self.families
+    }
+
+    /// Look up a metric family by name.
+    pub fn family(&self, name: &str) -> Option<&MetricFamily> {
+        self.index.get(name).map(|idx| &self.families[*idx])
+    }
+
+    /// Add all samples from another collection, with extra labels prepended to each.
+    ///
+    /// Sample labels that clash with an extra label are renamed with an `exported_` prefix,
+    /// as Prometheus does for labels clashing with target labels.
+    pub fn merge(&mut self, other: Metrics, labels: &[(String, String)]) {
+        for family in other.families {
+            for sample in family.samples {
+                let mut sample_labels = labels.to_vec();
+                for (mut name, value) in sample.labels {
+                    while sample_labels.iter().any(|(existing, _)| existing == &name) {
+                        name = format!("exported_{}", name);
+                    }
+                    sample_labels.push((name, value));
+                }
+                self.add(
+                    family.name.as_str(), family.metric_type, family.help.as_str(),
+                    sample_labels, sample.value
+                );
+            }
+        }
+    }
+
+    /// Render the metrics in the OpenMetrics text format.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for family in &self.families {
+            let suffix = match family.metric_type {
+                MetricType::Counter => "_total",
+                _ => "",
+            };
+            text.push_str(&format!(
+                "# TYPE {} {}\n", family.name, family.metric_type.openmetrics_name()
+            ));
+            if !family.help.is_empty() {
+                text.push_str(&format!("# HELP {} {}\n", family.name, escape(&family.help)));
+            }
+            for sample in &family.samples {
+                text.push_str(&family.name);
+                text.push_str(suffix);
+                if !sample.labels.is_empty() {
+                    let labels: Vec<String> = sample.labels.iter()
+                        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+                        .collect();
+                    text.push_str(&format!("{{{}}}", labels.join(",")));
+                }
+                text.push_str(&format!(" {}\n", format_value(sample.value)));
+            }
+        }
+        text.push_str("# EOF\n");
+        text
+    }
+
+    /// Look up the value of the sample with exactly the given labels.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        self.family(name)?.samples.iter()
+            .find(|sample| {
+                sample.labels.len() == labels.len() &&
+                    sample.labels.iter().zip(labels)
+                        .all(|(actual, expected)| actual.0 == expected.0 && actual.1 == expected.1)
+            })
+            .map(|sample| sample.value)
+    }
+}
+
+
+/// An export rule with its pattern compiled.
+struct Rule {
+    config: RuleConfig,
+    pattern: Option<Regex>,
+}
+
+
+/// Scrape MBeans and convert their attributes to metrics.
+pub struct Exporter {
+    blacklist: Vec<ObjectName>,
+    config: ExporterConfig,
+    rules: Vec<Rule>,
+    whitelist: Vec<ObjectName>,
+}
+
+impl Exporter {
+    /// Create an exporter, validating the configuration and compiling its rules.
+    pub fn new(config: ExporterConfig) -> Result<Exporter> {
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            if rule.name.is_none() &&
+                (rule.help.is_some() || !rule.labels.is_empty() ||
+                 rule.metric_type != MetricType::Untyped)
+            {
+                let error = "must provide name, if help, labels or type is given".to_string();
+                return Err(ErrorKind::ExporterConfig(error).into());
+            }
+            if rule.name.is_some() && rule.pattern.is_none() {
+                let error = "must provide pattern, if name is given".to_string();
+                return Err(ErrorKind::ExporterConfig(error).into());
+            }
+            let pattern = match rule.pattern {
+                None => None,
+                Some(ref pattern) => {
+                    let pattern = Regex::new(&format!("^.*(?:{}).*$", pattern))
+                        .with_context(|error| ErrorKind::ExporterConfig(error.to_string()))?;
+                    Some(pattern)
+                },
+            };
+            rules.push(Rule {
+                config: rule.clone(),
+                pattern,
+            });
+        }
+        if rules.is_empty() {
+            rules.push(Rule {
+                config: RuleConfig::default(),
+                pattern: None,
+            });
+        }
+        let blacklist = Exporter::parse_names(&config.blacklist_object_names)?;
+        let whitelist = Exporter::parse_names(&config.whitelist_object_names)?;
+        Ok(Exporter {
+            blacklist,
+            config,
+            rules,
+            whitelist,
+        })
+    }
+
+    /// Create an exporter from a YAML configuration document.
+    pub fn from_yaml(yaml: &str) -> Result<Exporter> {
+        Exporter::new(ExporterConfig::from_yaml(yaml)?)
+    }
+
+    /// Access the configuration of the exporter.
+    pub fn config(&self) -> &ExporterConfig {
+        &self.config
+    }
+
+    /// Convert the attributes of an MBean snapshot to metrics.
+    ///
+    /// Attributes that could not be read are skipped.
+    pub fn collect(&self, snapshot: &MBeanSnapshot, metrics: &mut Metrics) -> Result<()> {
+        let name = ObjectName::parse(&snapshot.name)?;
+        let properties = name.properties().to_vec();
+        for (attribute, value) in &snapshot.attributes {
+            let description = snapshot.info.attributes.iter()
+                .find(|info| &info.name == attribute)
+                .map(|info| info.description.as_str())
+                .unwrap_or("");
+            let bean = BeanValue {
+                attr_keys: Vec::new(),
+                attr_name: attribute.clone(),
+                description,
+                domain: name.domain(),
+                indexes: &snapshot.indexes,
+                path: attribute.clone(),
+                properties: properties.clone(),
+            };
+            self.process(bean, value, metrics);
+        }
+        Ok(())
+    }
+
+    /// Check if the exporter is configured to export the MBean with the given name.
+    pub fn exports(&self, name: &ObjectName) -> bool {
+        let whitelisted = self.whitelist.is_empty() ||
+            self.whitelist.iter().any(|pattern| pattern.matches(name));
+        whitelisted && !self.blacklist.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// Scrape all exported MBeans through the given client.
+    ///
+    /// MBeans that can't be read are skipped, only a failure to list MBeans fails the scrape.
+    pub fn scrape<C>(&self, client: &C) -> Result<Metrics>
+        where C: MBeanClientTrait,
+    {
+        let mut names = BTreeSet::new();
+        if self.whitelist.is_empty() {
+            names.extend(client.query_names("*:*", "")?);
+        }
+        for pattern in &self.whitelist {
+            names.extend(client.query_names(pattern.to_string(), "")?);
+        }
+        let names = names.iter().filter_map(|name| ObjectName::parse(name).ok());
+        self.scrape_names(client, names)
+    }
+
+    /// Scrape the exported MBeans of a registry, without listing MBeans on the server.
+    ///
+    /// Refresh the registry before scraping to pick up MBean registrations.
+    pub fn scrape_registry<C>(&self, registry: &Registry<C>) -> Result<Metrics>
+        where C: MBeanClientTrait,
+    {
+        let mut names = BTreeSet::new();
+        if self.whitelist.is_empty() {
+            names.extend(registry.names());
+        }
+        for pattern in &self.whitelist {
+            names.extend(registry.query(pattern));
+        }
+        self.scrape_names(registry.client(), names.into_iter().cloned())
+    }
+}
+
+impl Exporter {
+    fn parse_names(names: &[String]) -> Result<Vec<ObjectName>> {
+        names.iter().map(ObjectName::parse).collect()
+    }
+
+    /// Collect the metrics of the exported MBeans among the given names.
+    fn scrape_names<C, I>(&self, client: &C, names: I) -> Result<Metrics>
+        where C: MBeanClientTrait,
+              I: IntoIterator<Item = ObjectName>,
+    {
+        let mut metrics = Metrics::new();
+        for name in names {
+            if !self.exports(&name) {
+                continue;
+            }
+            if let Ok(snapshot) = client.snapshot_mbean(name.to_string()) {
+                self.collect(&snapshot, &mut metrics)?;
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Flatten open type values and record the simple values they contain.
+    fn process(&self, bean: BeanValue, value: &Value, metrics: &mut Metrics) {
+        match *value {
+            Value::Object(ref items) => {
+                for (key, item) in items {
+                    let mut attr_keys = bean.attr_keys.clone();
+                    attr_keys.push(bean.attr_name.clone());
+                    let nested = BeanValue {
+                        attr_keys,
+                        attr_name: key.clone(),
+                        path: format!("{}.{}", bean.path, key),
+                        properties: bean.properties.clone(),
+                        ..bean
+                    };
+                    self.process(nested, item, metrics);
+                }
+            },
+            Value::Array(ref rows) => {
+                let index = bean.indexes.get(&bean.path);
+                let is_label = |key: &String, column: &Value| match index {
+                    Some(index) => index.contains(key),
+                    None => column.is_string(),
+                };
+                for row in rows {
+                    let columns = match *row {
+                        Value::Object(ref columns) => columns,
+                        _ => continue,
+                    };
+                    let mut properties = bean.properties.clone();
+                    let labels = columns.iter().filter(|(key, column)| is_label(key, column));
+                    for (key, column) in labels {
+                        let label = match *column {
+                            Value::Null => continue,
+                            Value::String(ref label) => label.clone(),
+                            ref column => column.to_string(),
+                        };
+                        let mut key = key.clone();
+                        while properties.iter().any(|(existing, _)| existing == &key) {
+                            key.push('_');
+                        }
+                        properties.push((key, label));
+                    }
+                    let values = columns.iter().filter(|(key, column)| !is_label(key, column));
+                    for (key, column) in values {
+                        let mut attr_keys = bean.attr_keys.clone();
+                        let mut attr_name = bean.attr_name.clone();
+                        if key.to_lowercase() != "value" {
+                            attr_keys.push(attr_name);
+                            attr_name = key.clone();
+                        }
+                        let nested = BeanValue {
+                            attr_keys,
+                            attr_name,
+                            path: format!("{}.{}", bean.path, key),
+                            properties: properties.clone(),
+                            ..bean
+                        };
+                        self.process(nested, column, metrics);
+                    }
+                }
+            },
+            Value::Null => (),
+            _ => self.record(&bean, value, metrics),
+        }
+    }
+
+    /// Record a simple value with the first matching rule.
+    fn record(&self, bean: &BeanValue, value: &Value, metrics: &mut Metrics) {
+        let bean_name = format!(
+            "{}<{}><{}>", bean.domain,
+            bean.properties.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(", "),
+            bean.attr_keys.join(", ")
+        );
+        let value_text = match *value {
+            Value::String(ref text) => text.clone(),
+            ref value => value.to_string(),
+        };
+        for rule in &self.rules {
+            let attr_name = if rule.config.attr_name_snake_case {
+                snake_case(&bean.attr_name)
+            } else {
+                bean.attr_name.clone()
+            };
+            let match_name = format!("{}{}", bean_name, attr_name);
+            let match_text = format!("{}: {}", match_name, value_text);
+            let captures = match rule.pattern {
+                None => None,
+                Some(ref pattern) => {
+                    match pattern.captures(&match_text) {
+                        None => continue,
+                        Some(captures) => Some(captures),
+                    }
+                },
+            };
+            let replace = |template: &str| -> String {
+                match captures {
+                    None => template.to_string(),
+                    Some(ref captures) => expand(captures, template),
+                }
+            };
+
+            // Rules that match stop the search even if the value can't be exported.
+            let number = match rule.config.value {
+                Some(ref template) if !template.is_empty() => replace(template).trim().parse().ok(),
+                _ => match *value {
+                    Value::Bool(flag) => Some(if flag { 1.0 } else { 0.0 }),
+                    Value::Number(ref number) => number.as_f64(),
+                    _ => None,
+                },
+            };
+            let number = match number {
+                None => return,
+                Some(number) => number * rule.config.value_factor,
+            };
+            let help = match rule.config.help {
+                None => format!("{} ({})", bean.description, match_name),
+                Some(ref help) => replace(help),
+            };
+            let name = match rule.config.name {
+                None => {
+                    self.default_export(bean, rule.config.metric_type, help, number, metrics);
+                    return;
+                },
+                Some(ref name) => safe_name(&replace(name)),
+            };
+            if name.is_empty() {
+                return;
+            }
+            let name = if self.config.lowercase_output_name { name.to_lowercase() } else { name };
+            let mut labels = Vec::new();
+            for (label, label_value) in &rule.config.labels {
+                let mut label = safe_name(&replace(label));
+                if self.config.lowercase_output_label_names {
+                    label = label.to_lowercase();
+                }
+                let label_value = replace(label_value);
+                if !label.is_empty() && !label_value.is_empty() {
+                    labels.push((label, label_value));
+                }
+            }
+            metrics.add(name, rule.config.metric_type, help, labels, number);
+            return;
+        }
+    }
+
+    /// Record a value named after the MBean and attribute, with the remaining properties as labels.
+    fn default_export(
+        &self, bean: &BeanValue, metric_type: MetricType, help: String, value: f64,
+        metrics: &mut Metrics
+    ) {
+        let mut name = bean.domain.to_string();
+        if let Some((_, first)) = bean.properties.first() {
+            name.push('_');
+            name.push_str(first);
+        }
+        for key in &bean.attr_keys {
+            name.push('_');
+            name.push_str(key);
+        }
+        name.push('_');
+        name.push_str(&bean.attr_name);
+        let mut name = safe_name(&name);
+        if self.config.lowercase_output_name {
+            name = name.to_lowercase();
+        }
+        let labels = bean.properties.iter().skip(1)
+            .map(|(key, value)| {
+                let mut key = safe_name(key);
+                if self.config.lowercase_output_label_names {
+                    key = key.to_lowercase();
+                }
+                (key, value.clone())
+            })
+            .collect();
+        metrics.add(name, metric_type, help, labels, value);
+    }
+}
+
+
+/// Position of a value within an MBean, while open types are flattened.
+struct BeanValue<'a> {
+    attr_keys: Vec<String>,
+    attr_name: String,
+    description: &'a str,
+    domain: &'a str,
+    indexes: &'a BTreeMap<String, Vec<String>>,
+    /// Attribute name and the keys leading to the value, as in `MBeanSnapshot::indexes`.
+    path: String,
+    properties: Vec<(String, String)>,
+}
+
+
+/// Escape label values and help text.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n").replace('"', "\\\"")
+}
+
+/// Expand `$N` and `${name}` references to capture groups, as `java.util.regex` does.
+fn expand(captures: &Captures, template: &str) -> String {
+    let mut expanded = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    expanded.push(escaped);
+                }
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                expanded.push_str(captures.name(&name).map_or("", |group| group.as_str()));
+            },
+            '$' if chars.peek().map_or(false, |c| c.is_ascii_digit()) => {
+                // Like Java, keep reading digits as long as they form a valid group number.
+                let mut group = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    let next = group * 10 + digit as usize;
+                    if group > 0 && next >= captures.len() {
+                        break;
+                    }
+                    group = next;
+                    chars.next();
+                }
+                expanded.push_str(captures.get(group).map_or("", |group| group.as_str()));
+            },
+            c => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+/// Format a sample value for the OpenMetrics text format.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value.is_infinite() && value > 0.0 {
+        "+Inf".into()
+    } else if value.is_infinite() {
+        "-Inf".into()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Replace characters not allowed in metric names and collapse repeated underscores.
+fn safe_name(name: &str) -> String {
+    let mut safe = String::with_capacity(name.len());
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        safe.push('_');
+    }
+    let mut underscore = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == ':' {
+            safe.push(c);
+            underscore = false;
+        } else if !underscore {
+            safe.push('_');
+            underscore = true;
+        }
+    }
+    safe
+}
+
+/// Convert a camel case attribute name to lower snake case.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut previous_upper = true;
+    for (idx, c) in name.chars().enumerate() {
+        let upper = c.is_uppercase();
+        if idx > 0 && upper && !previous_upper {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+        previous_upper = upper || c == '_';
+    }
+    snake
+}
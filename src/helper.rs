@@ -0,0 +1,215 @@
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use failure::ResultExt;
+use j4rs::Instance;
+use j4rs::InvocationArg;
+use j4rs::Jvm;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::ErrorKind;
+use super::Result;
+
+use super::constants::JAVA_IO_FILE;
+use super::constants::JAVA_LANG_CLASS_LOADER;
+use super::constants::JAVA_LANG_OBJECT;
+use super::constants::JAVA_NET_URL;
+use super::constants::JAVA_NET_URL_CLASS_LOADER;
+use super::constants::JAVA_UTIL_BI_FUNCTION;
+use super::constants::JMX_HELPER;
+
+
+/// Jar with the compiled `net.spogliani.jmx.Helper` class (see `build.rs`).
+static HELPER_JAR: &[u8] = include_bytes!("../java/jmx-rust-helper.jar");
+
+/// Path of the helper class file in the classpath.
+static HELPER_RESOURCE: &str = "net/spogliani/jmx/Helper.class";
+
+/// Helper class loaded from `HELPER_JAR` when it is not on the JVM classpath.
+static LOADED_CLASS: Mutex<Option<Instance>> = Mutex::new(None);
+
+
+/// Rust side of the bundled `net.spogliani.jmx.Helper` java class.
+///
+/// The helper converts JMX objects into standard java collections in bulk so each
+/// request needs a single JNI call instead of one call per element.
+///
+/// The build script deploys the helper jar to the j4rs `jassets` directory so the class
+/// is on the classpath of JVMs built by j4rs.
+/// When it is not, for example because the JVM was built with a different classpath,
+/// the jar embedded in the crate is loaded once per process with a dedicated
+/// `java.net.URLClassLoader` from a directory only the current user can access.
+///
+/// Each client creates its own instance of the helper.
+pub(crate) struct Helper {
+    instance: Instance,
+}
+
+impl Helper {
+    /// Create a helper instance, loading the class if it is not on the classpath.
+    pub(crate) fn load(jvm: &Jvm) -> Result<Helper> {
+        let instance = if Helper::on_classpath(jvm)? {
+            jvm.create_instance(JMX_HELPER, &[]).with_context(|_| ErrorKind::HelperLoad)?
+        } else {
+            Helper::define(jvm)?
+        };
+        let instance = jvm.cast(&instance, JAVA_UTIL_BI_FUNCTION)
+            .with_context(|_| ErrorKind::JavaCast(JAVA_UTIL_BI_FUNCTION.to_string()))?;
+        Ok(Helper { instance })
+    }
+
+    /// Invoke a helper operation that returns a java object.
+    pub(crate) fn apply(
+        &self, jvm: &Jvm, operation: &'static str, args: Vec<InvocationArg>
+    ) -> Result<Instance> {
+        let args = jvm.create_java_array(JAVA_LANG_OBJECT, &args)
+            .with_context(|_| ErrorKind::JavaCreateInstance(JAVA_LANG_OBJECT))?;
+        let args = jvm.cast(&args, JAVA_LANG_OBJECT)
+            .with_context(|_| ErrorKind::JavaCast(JAVA_LANG_OBJECT.to_string()))?;
+        let result = jvm.invoke(
+            &self.instance, "apply",
+            &[Helper::value(&operation.to_string()), InvocationArg::from(args)]
+        ).with_context(|_| ErrorKind::HelperInvoke(operation))?;
+        Ok(result)
+    }
+
+    /// Invoke a helper operation and decode its result.
+    pub(crate) fn call<T>(
+        &self, jvm: &Jvm, operation: &'static str, args: Vec<InvocationArg>
+    ) -> Result<T>
+        where T: DeserializeOwned,
+    {
+        let result = self.apply(jvm, operation, args)?;
+        let result: T = jvm.to_rust(result).with_context(|_| ErrorKind::RustCast("<dynamic>"))?;
+        Ok(result)
+    }
+
+    /// Wrap a java instance into a helper operation argument.
+    pub(crate) fn instance(jvm: &Jvm, instance: &Instance) -> Result<InvocationArg> {
+        let instance = jvm.cast(instance, JAVA_LANG_OBJECT)
+            .with_context(|_| ErrorKind::JavaCast(JAVA_LANG_OBJECT.to_string()))?;
+        Ok(InvocationArg::from(instance))
+    }
+
+    /// Wrap a rust value into a helper operation argument.
+    ///
+    /// Values are passed to java as JSON and decoded into strings, numbers, lists and maps.
+    pub(crate) fn value<T>(value: &T) -> InvocationArg
+        where T: Serialize + 'static,
+    {
+        InvocationArg::new(value, JAVA_LANG_OBJECT)
+    }
+}
+
+impl Helper {
+    /// Create a helper instance from the class loaded from the embedded jar.
+    fn define(jvm: &Jvm) -> Result<Instance> {
+        let mut class = match LOADED_CLASS.lock() {
+            Ok(class) => class,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if class.is_none() {
+            *class = Some(Helper::load_class(jvm)?);
+        }
+        let class = class.as_ref().expect("the helper class was just loaded");
+        let instance = jvm.invoke(class, "newInstance", &[])
+            .with_context(|_| ErrorKind::HelperLoad)?;
+        Ok(instance)
+    }
+
+    /// Check if the helper class is on the classpath, without raising java exceptions.
+    fn on_classpath(jvm: &Jvm) -> Result<bool> {
+        let resources = jvm.invoke_static(
+            JAVA_LANG_CLASS_LOADER, "getSystemResources",
+            &[InvocationArg::try_from(HELPER_RESOURCE)?]
+        ).with_context(
+            |_| ErrorKind::JavaInvokeStatic(JAVA_LANG_CLASS_LOADER, "getSystemResources")
+        )?;
+        let found = jvm.invoke(&resources, "hasMoreElements", &[]).with_context(
+            |_| ErrorKind::JavaInvoke(resources.class_name().to_string(), "hasMoreElements")
+        )?;
+        let found: bool = jvm.to_rust(found).with_context(|_| ErrorKind::RustCast("bool"))?;
+        Ok(found)
+    }
+
+    /// Write the embedded jar to a private directory and load the class from it.
+    ///
+    /// The class loader is closed and the directory removed once the class is loaded,
+    /// which is why the helper class must not depend on other classes in the jar.
+    fn load_class(jvm: &Jvm) -> Result<Instance> {
+        let dir = Helper::private_dir()?;
+        let result = Helper::write_jar(&dir).and_then(|jar| {
+            let jar = jar.to_string_lossy().into_owned();
+            let url = jvm.create_instance(JAVA_IO_FILE, &[InvocationArg::try_from(jar)?])
+                .with_context(|_| ErrorKind::JavaCreateInstance(JAVA_IO_FILE))?;
+            let url = jvm.invoke(&url, "toURI", &[])
+                .with_context(|_| ErrorKind::JavaInvoke(JAVA_IO_FILE.to_string(), "toURI"))?;
+            let url = jvm.invoke(&url, "toURL", &[])
+                .with_context(|_| ErrorKind::JavaInvoke(url.class_name().to_string(), "toURL"))?;
+            let urls = jvm.create_java_array(JAVA_NET_URL, &[InvocationArg::from(url)])
+                .with_context(|_| ErrorKind::JavaCreateInstance(JAVA_NET_URL))?;
+            let loader = jvm.create_instance(
+                JAVA_NET_URL_CLASS_LOADER, &[InvocationArg::from(urls)]
+            ).with_context(|_| ErrorKind::JavaCreateInstance(JAVA_NET_URL_CLASS_LOADER))?;
+            let class = jvm.invoke(&loader, "loadClass", &[InvocationArg::try_from(JMX_HELPER)?])
+                .with_context(|_| ErrorKind::HelperLoad)?;
+            jvm.invoke(&loader, "close", &[]).with_context(
+                |_| ErrorKind::JavaInvoke(JAVA_NET_URL_CLASS_LOADER.to_string(), "close")
+            )?;
+            Ok(class)
+        });
+        let _ = fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// Create a new directory in the system temp directory only the current user can access.
+    ///
+    /// Directories are never reused so files created by other users can't be loaded.
+    fn private_dir() -> Result<PathBuf> {
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|time| time.subsec_nanos())
+            .unwrap_or(0);
+        let mut attempt = 0;
+        loop {
+            let dir = env::temp_dir().join(
+                format!("jmx-rust-helper-{}-{}-{}", process::id(), nanos, attempt)
+            );
+            let created = builder.create(&dir);
+            if let Err(ref error) = created {
+                if error.kind() == io::ErrorKind::AlreadyExists && attempt < 16 {
+                    attempt += 1;
+                    continue;
+                }
+            }
+            created.with_context(|_| ErrorKind::HelperWrite(dir.display().to_string()))?;
+            return Ok(dir);
+        }
+    }
+
+    /// Write the embedded helper jar in the given directory.
+    fn write_jar(dir: &Path) -> Result<PathBuf> {
+        let jar = dir.join("jmx-rust-helper.jar");
+        let path = jar.display().to_string();
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&jar)
+            .with_context(|_| ErrorKind::HelperWrite(path.clone()))?;
+        file.write_all(HELPER_JAR).with_context(|_| ErrorKind::HelperWrite(path))?;
+        Ok(jar)
+    }
+}
@@ -0,0 +1,361 @@
+//! Control Java Flight Recorder through the `FlightRecorderMXBean`.
+//!
+//! Recordings are created, configured, started and stopped on the remote JVM and
+//! their data can be streamed over JMX into a local file once they are stopped.
+//! Requires Java 11 or later, or a Java 8 JVM with Flight Recorder backported.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use base64;
+use failure::ResultExt;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::MBeanClientTrait;
+use super::Result;
+use super::platform::TableRow;
+use super::platform::table;
+
+
+/// Name of the `FlightRecorderMXBean`.
+pub const FLIGHT_RECORDER_MXBEAN_NAME: &str = "jdk.management.jfr:type=FlightRecorder";
+
+
+/// Predefined recording configuration, from `jdk.management.jfr.ConfigurationInfo`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ConfigurationInfo {
+    /// The configuration in the XML format of `.jfc` files.
+    pub contents: String,
+    pub description: String,
+    pub label: String,
+
+    /// Name to pass to `FlightRecorderMXBean::set_predefined_configuration`.
+    pub name: String,
+    pub provider: String,
+
+    /// Settings of the events, by `<event name>#<setting>`.
+    #[serde(deserialize_with = "table")]
+    pub settings: BTreeMap<String, String>,
+}
+
+
+/// Description of a recording, from `jdk.management.jfr.RecordingInfo`.
+///
+/// Durations are in seconds and sizes in bytes, `0` means unlimited.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    /// File the recording is written to when it stops, on the host of the JVM.
+    pub destination: Option<String>,
+    pub dump_on_exit: bool,
+    pub duration: i64,
+    pub id: i64,
+    pub max_age: i64,
+    pub max_size: i64,
+    pub name: String,
+
+    /// Settings of the events, by `<event name>#<setting>`.
+    #[serde(deserialize_with = "table")]
+    pub settings: BTreeMap<String, String>,
+
+    /// Amount of data recorded so far, in bytes.
+    pub size: i64,
+
+    /// Start and stop times, in milliseconds since the epoch, `0` if not started or stopped.
+    pub start_time: i64,
+    pub state: RecordingState,
+    pub stop_time: i64,
+    pub to_disk: bool,
+}
+
+
+/// Options of a recording, set with `FlightRecorderMXBean::set_recording_options`.
+///
+/// Options that are not set keep their current value.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RecordingOptions {
+    destination: Option<String>,
+    disk: Option<bool>,
+    dump_on_exit: Option<bool>,
+    duration: Option<Duration>,
+    max_age: Option<Duration>,
+    max_size: Option<u64>,
+    name: Option<String>,
+}
+
+impl RecordingOptions {
+    /// Write the recording to a file on the host of the JVM when it stops.
+    pub fn destination<S: Into<String>>(mut self, destination: S) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    /// Store the recording on disk rather than in memory.
+    pub fn disk(mut self, disk: bool) -> Self {
+        self.disk = Some(disk);
+        self
+    }
+
+    /// Dump the recording to its destination when the JVM exits.
+    pub fn dump_on_exit(mut self, dump_on_exit: bool) -> Self {
+        self.dump_on_exit = Some(dump_on_exit);
+        self
+    }
+
+    /// Stop the recording automatically after the given time, rounded up to whole seconds.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Discard recorded data older than the given time, rounded up to whole seconds.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Discard the oldest recorded data once the recording is larger than `bytes`.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+impl RecordingOptions {
+    /// Format the options as expected by `FlightRecorderMXBean.setRecordingOptions`.
+    fn to_map(&self) -> BTreeMap<String, String> {
+        let mut options = BTreeMap::new();
+        let mut insert = |option: &str, value: Option<String>| {
+            if let Some(value) = value {
+                options.insert(option.to_string(), value);
+            }
+        };
+        insert("destination", self.destination.clone());
+        insert("disk", self.disk.map(|disk| disk.to_string()));
+        insert("dumpOnExit", self.dump_on_exit.map(|dump| dump.to_string()));
+        insert("duration", self.duration.map(seconds));
+        insert("maxAge", self.max_age.map(seconds));
+        insert("maxSize", self.max_size.map(|max_size| max_size.to_string()));
+        insert("name", self.name.clone());
+        options
+    }
+}
+
+
+/// State of a recording, from `jdk.jfr.RecordingState`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RecordingState {
+    Closed,
+    Delayed,
+    New,
+    Running,
+    Stopped,
+}
+
+
+/// Proxy for a `jdk.management.jfr.FlightRecorderMXBean`.
+pub struct FlightRecorderMXBean<'a, C: 'a> {
+    client: &'a C,
+}
+
+impl<'a, C> FlightRecorderMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> FlightRecorderMXBean<'a, C> {
+        FlightRecorderMXBean { client }
+    }
+
+    /// Create a copy of a recording, stopped if `stop` is set, and return its ID.
+    pub fn clone_recording(&self, id: i64, stop: bool) -> Result<i64> {
+        self.invoke("cloneRecording", vec![Value::from(id), Value::from(stop)])
+    }
+
+    /// Close a recording and release its data.
+    pub fn close_recording(&self, id: i64) -> Result<()> {
+        self.run("closeRecording", vec![Value::from(id)])
+    }
+
+    /// Predefined configurations available on the JVM, such as `default` and `profile`.
+    pub fn configurations(&self) -> Result<Vec<ConfigurationInfo>> {
+        self.client.get_attribute(FLIGHT_RECORDER_MXBEAN_NAME, "Configurations")
+    }
+
+    /// Write the data of a recording to a file on the host of the JVM.
+    pub fn copy_to<S: Into<String>>(&self, id: i64, path: S) -> Result<()> {
+        self.run("copyTo", vec![Value::from(id), Value::from(path.into())])
+    }
+
+    /// Stream the data of a recording into a local file and return its size in bytes.
+    ///
+    /// The file is created or truncated. Only stopped recordings can be streamed.
+    pub fn download<P: AsRef<Path>>(&self, id: i64, path: P) -> Result<u64> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|_| ErrorKind::RecordingFile(path.display().to_string()))?;
+        let mut writer = BufWriter::new(file);
+        let size = self.download_to(id, &mut writer)?;
+        writer.flush().with_context(|_| ErrorKind::RecordingWrite(id))?;
+        Ok(size)
+    }
+
+    /// Stream the data of a recording into a writer and return its size in bytes.
+    ///
+    /// Only stopped recordings can be streamed.
+    pub fn download_to<W: Write>(&self, id: i64, writer: &mut W) -> Result<u64> {
+        let stream = self.open_stream(id)?;
+        let result = self.copy_stream(id, stream, writer);
+        let closed = self.close_stream(stream);
+        let size = result?;
+        closed?;
+        Ok(size)
+    }
+
+    /// Create a recording, with the default configuration and options, and return its ID.
+    pub fn new_recording(&self) -> Result<i64> {
+        self.invoke("newRecording", Vec::new())
+    }
+
+    pub fn object_name(&self) -> &str {
+        FLIGHT_RECORDER_MXBEAN_NAME
+    }
+
+    /// Options of a recording, such as `name`, `maxAge` or `destination`.
+    ///
+    /// Options with no value are omitted.
+    pub fn recording_options(&self, id: i64) -> Result<BTreeMap<String, String>> {
+        self.table("getRecordingOptions", id)
+    }
+
+    /// Settings of the events of a recording, by `<event name>#<setting>`.
+    pub fn recording_settings(&self, id: i64) -> Result<BTreeMap<String, String>> {
+        self.table("getRecordingSettings", id)
+    }
+
+    /// Recordings known to the JVM, including stopped recordings not yet closed.
+    pub fn recordings(&self) -> Result<Vec<RecordingInfo>> {
+        self.client.get_attribute(FLIGHT_RECORDER_MXBEAN_NAME, "Recordings")
+    }
+
+    /// Configure the events of a recording with the contents of a `.jfc` file.
+    pub fn set_configuration<S: Into<String>>(&self, id: i64, contents: S) -> Result<()> {
+        self.run("setConfiguration", vec![Value::from(id), Value::from(contents.into())])
+    }
+
+    /// Configure the events of a recording with a predefined configuration, by name.
+    pub fn set_predefined_configuration<S: Into<String>>(&self, id: i64, name: S) -> Result<()> {
+        let params = vec![Value::from(id), Value::from(name.into())];
+        self.run("setPredefinedConfiguration", params)
+    }
+
+    pub fn set_recording_options(&self, id: i64, options: &RecordingOptions) -> Result<()> {
+        self.run("setRecordingOptions", vec![Value::from(id), map(options.to_map())])
+    }
+
+    /// Replace the settings of the events of a recording.
+    pub fn set_recording_settings(
+        &self, id: i64, settings: &BTreeMap<String, String>
+    ) -> Result<()> {
+        self.run("setRecordingSettings", vec![Value::from(id), map(settings.clone())])
+    }
+
+    pub fn start_recording(&self, id: i64) -> Result<()> {
+        self.run("startRecording", vec![Value::from(id)])
+    }
+
+    /// Create and start a recording with a predefined configuration and return its ID.
+    pub fn start_new_recording<S: Into<String>>(
+        &self, configuration: S, options: &RecordingOptions
+    ) -> Result<i64> {
+        let id = self.new_recording()?;
+        self.set_predefined_configuration(id, configuration)?;
+        self.set_recording_options(id, options)?;
+        self.start_recording(id)?;
+        Ok(id)
+    }
+
+    /// Stop a running recording, fails if it is not running.
+    pub fn stop_recording(&self, id: i64) -> Result<bool> {
+        self.invoke("stopRecording", vec![Value::from(id)])
+    }
+
+    /// Create a stopped recording with the data of all recordings and return its ID.
+    pub fn take_snapshot(&self) -> Result<i64> {
+        self.invoke("takeSnapshot", Vec::new())
+    }
+}
+
+impl<'a, C> FlightRecorderMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    fn close_stream(&self, stream: i64) -> Result<()> {
+        self.run("closeStream", vec![Value::from(stream)])
+    }
+
+    fn copy_stream<W: Write>(&self, id: i64, stream: i64, writer: &mut W) -> Result<u64> {
+        let mut size = 0;
+        while let Some(block) = self.read_stream(stream)? {
+            writer.write_all(&block).with_context(|_| ErrorKind::RecordingWrite(id))?;
+            size += block.len() as u64;
+        }
+        Ok(size)
+    }
+
+    fn invoke<T>(&self, operation: &str, params: Vec<Value>) -> Result<T>
+        where T: DeserializeOwned,
+    {
+        self.client.invoke(FLIGHT_RECORDER_MXBEAN_NAME, operation, params)
+    }
+
+    fn open_stream(&self, id: i64) -> Result<i64> {
+        self.invoke("openStream", vec![Value::from(id), Value::Null])
+    }
+
+    /// Read the next block of a stream, `None` once all the data was read.
+    ///
+    /// Blocks are received as base64 strings.
+    fn read_stream(&self, stream: i64) -> Result<Option<Vec<u8>>> {
+        let block: Option<String> = self.invoke("readStream", vec![Value::from(stream)])?;
+        match block {
+            None => Ok(None),
+            Some(block) => {
+                let block = base64::decode(&block).with_context(|_| ErrorKind::ValueDecode)?;
+                Ok(Some(block))
+            },
+        }
+    }
+
+    fn run(&self, operation: &str, params: Vec<Value>) -> Result<()> {
+        let _: Value = self.invoke(operation, params)?;
+        Ok(())
+    }
+
+    fn table(&self, operation: &str, id: i64) -> Result<BTreeMap<String, String>> {
+        let rows: Vec<TableRow<Option<String>>> = self.invoke(operation, vec![Value::from(id)])?;
+        Ok(rows.into_iter().filter_map(|row| Some((row.key, row.value?))).collect())
+    }
+}
+
+
+/// Format a duration in whole seconds, rounding up so short durations don't become `0 s`.
+///
+/// Flight Recorder treats a `0 s` duration or maximum age as unlimited.
+fn seconds(duration: Duration) -> String {
+    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    format!("{} s", seconds)
+}
+
+/// Encode a map of strings for a `Map<String, String>` parameter.
+fn map(map: BTreeMap<String, String>) -> Value {
+    Value::Object(map.into_iter().map(|(key, value)| (key, Value::from(value))).collect())
+}
@@ -1,16 +1,30 @@
+extern crate base64;
 extern crate failure;
 extern crate j4rs;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
 
 
 mod base;
+mod caching_client;
 mod constants;
+pub mod diagnostic_command;
 mod error;
+pub mod dump;
+mod helper;
+pub mod jfr;
+pub mod logging;
 mod mbean_client;
 mod mbean_info;
-mod util;
+mod notification;
+mod object_name;
+pub mod platform;
+pub mod registry;
+mod runtime;
+mod snapshot;
+pub mod thread_dump;
 
 
 pub use self::error::Error;
@@ -19,21 +33,96 @@ pub use self::error::Result;
 
 pub use self::base::MBeanAddress;
 pub use self::base::MBeanClientTrait;
+pub use self::caching_client::CachingClient;
+pub use self::caching_client::CachingClientOptions;
+pub use self::dump::ServerDump;
 pub use self::mbean_client::MBeanClient;
 pub use self::mbean_client::MBeanClientOptions;
+pub use self::mbean_info::MBeanAttribute;
 pub use self::mbean_info::MBeanInfo;
+pub use self::mbean_info::MBeanOperation;
+pub use self::mbean_info::MBeanParameter;
+pub use self::notification::ListenerId;
+pub use self::notification::Notification;
+pub use self::notification::NotificationListener;
+pub use self::object_name::ObjectName;
+pub use self::registry::Registry;
+pub use self::registry::RegistryChange;
+pub use self::runtime::JmxRuntime;
+pub use self::snapshot::MBeanSnapshot;
 
 
-// Threaded support feature.
+// Optional dependencies.
 #[cfg(feature = "thread-support")]
 extern crate crossbeam_channel;
-#[cfg(feature = "thread-support")]
-extern crate serde_json;
+#[cfg(feature = "async")]
+extern crate futures_channel;
+#[cfg(feature = "exporter")]
+extern crate regex;
+#[cfg(feature = "exporter")]
+extern crate serde_yaml;
+
+
+// Mock client feature.
+#[cfg(feature = "mock")]
+pub mod mock;
+
 
+// Metrics exporter feature.
+#[cfg(feature = "exporter")]
+pub mod exporter;
+
+
+// Record and replay feature.
+#[cfg(feature = "record-replay")]
+pub mod cassette;
+
+
+// Threaded support feature.
+#[cfg(feature = "thread-support")]
+mod counter;
 #[cfg(feature = "thread-support")]
 mod mbean_thread;
+#[cfg(feature = "thread-support")]
+mod poller;
+#[cfg(feature = "thread-support")]
+mod target_pool;
 
+#[cfg(feature = "thread-support")]
+pub use self::counter::CounterRate;
+#[cfg(feature = "thread-support")]
+pub use self::counter::Counters;
 #[cfg(feature = "thread-support")]
 pub use self::mbean_thread::MBeanThreadedClient;
 #[cfg(feature = "thread-support")]
 pub use self::mbean_thread::MBeanThreadedClientOptions;
+#[cfg(feature = "thread-support")]
+pub use self::mbean_thread::WorkerDispatch;
+#[cfg(feature = "thread-support")]
+pub use self::poller::Poller;
+#[cfg(feature = "thread-support")]
+pub use self::poller::PollerOptions;
+#[cfg(feature = "thread-support")]
+pub use self::poller::Sample;
+#[cfg(feature = "thread-support")]
+pub use self::poller::Subscription;
+#[cfg(feature = "thread-support")]
+pub use self::target_pool::JmxTargetPool;
+#[cfg(feature = "thread-support")]
+pub use self::target_pool::JmxTargetPoolOptions;
+#[cfg(feature = "thread-support")]
+pub use self::target_pool::TargetHealth;
+
+
+// Async support feature.
+#[cfg(feature = "async")]
+mod mbean_async;
+
+#[cfg(feature = "async")]
+pub use self::mbean_async::MBeanAsyncClient;
+#[cfg(feature = "async")]
+pub use self::mbean_async::MBeanAttributeResponse;
+#[cfg(feature = "async")]
+pub use self::mbean_async::MBeanReconnectResponse;
+#[cfg(feature = "async")]
+pub use self::mbean_async::MBeanResponse;
@@ -0,0 +1,341 @@
+//! Control the levels of the loggers of a remote JVM, whatever framework it logs with.
+//!
+//! The logging frameworks are detected from the MBeans they register:
+//!
+//!   * `java.util.logging` through the `LoggingMXBean`, always registered.
+//!   * Log4j 2 through the `LoggerConfigAdminMBean`s of each logger context,
+//!     and the `LoggerContextAdminMBean` to configure new loggers.
+//!   * Logback through the `JMXConfigurator`, registered by `<jmxConfigurator/>`.
+//!
+//! The root logger is named `""` for all frameworks.
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::MBeanClientTrait;
+use super::ObjectName;
+use super::Result;
+
+
+/// Name of the `java.util.logging` `LoggingMXBean`.
+pub const JUL_LOGGING_MXBEAN_NAME: &str = "java.util.logging:type=Logging";
+
+/// Names of the Log4j 2 `LoggerContextAdminMBean`s, as a pattern.
+pub const LOG4J2_CONTEXT_PATTERN: &str = "org.apache.logging.log4j2:type=*";
+
+/// Names of the Log4j 2 `LoggerConfigAdminMBean`s, as a pattern.
+pub const LOG4J2_LOGGER_CONFIG_PATTERN: &str = "org.apache.logging.log4j2:component=Loggers,*";
+
+/// Names of the Logback `JMXConfigurator`s, as a pattern.
+pub const LOGBACK_CONFIGURATOR_PATTERN: &str =
+    "ch.qos.logback.classic:Type=ch.qos.logback.classic.jmx.JMXConfigurator,*";
+
+/// Name of the root logger for Logback.
+const LOGBACK_ROOT: &str = "ROOT";
+
+
+/// Logging framework of a logger.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum LoggingFramework {
+    JavaUtilLogging,
+    Log4j2,
+    Logback,
+}
+
+
+/// Level of a logger, translated into the level names of each framework.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Level {
+    All,
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Off,
+}
+
+impl Level {
+    /// Name of the level for the given framework.
+    ///
+    /// `java.util.logging` uses `FINEST` for `Trace`, `FINE` for `Debug`,
+    /// `WARNING` for `Warn` and `SEVERE` for `Error`.
+    pub fn name(&self, framework: LoggingFramework) -> &'static str {
+        let jul = framework == LoggingFramework::JavaUtilLogging;
+        match *self {
+            Level::All => "ALL",
+            Level::Trace if jul => "FINEST",
+            Level::Trace => "TRACE",
+            Level::Debug if jul => "FINE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn if jul => "WARNING",
+            Level::Warn => "WARN",
+            Level::Error if jul => "SEVERE",
+            Level::Error => "ERROR",
+            Level::Off => "OFF",
+        }
+    }
+
+    /// Parse the name of a level of any framework, ignoring case.
+    ///
+    /// Levels with no exact match map to the closest level: `FINER` to `Trace`,
+    /// `CONFIG` to `Info` and `FATAL` to `Error`.
+    pub fn parse(name: &str) -> Option<Level> {
+        let level = match name.to_ascii_uppercase().as_str() {
+            "ALL" => Level::All,
+            "TRACE" | "FINEST" | "FINER" => Level::Trace,
+            "DEBUG" | "FINE" => Level::Debug,
+            "INFO" | "CONFIG" => Level::Info,
+            "WARN" | "WARNING" => Level::Warn,
+            "ERROR" | "SEVERE" | "FATAL" => Level::Error,
+            "OFF" => Level::Off,
+            _ => return None,
+        };
+        Some(level)
+    }
+}
+
+
+/// Logger known to a logging framework.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct Logger {
+    pub framework: LoggingFramework,
+
+    /// Level set on the logger, as named by the framework, `None` if inherited.
+    pub level: Option<String>,
+    pub name: String,
+}
+
+
+/// Read and set logger levels with all the logging frameworks of a JVM.
+pub struct LoggerControl<'a, C: 'a> {
+    client: &'a C,
+    frameworks: Vec<LoggingFramework>,
+    logback: Vec<String>,
+}
+
+impl<'a, C> LoggerControl<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Detect the logging frameworks that registered MBeans with the server.
+    pub fn detect(client: &'a C) -> Result<LoggerControl<'a, C>> {
+        let mut frameworks = Vec::new();
+        if !client.query_names(JUL_LOGGING_MXBEAN_NAME, "")?.is_empty() {
+            frameworks.push(LoggingFramework::JavaUtilLogging);
+        }
+        let control = LoggerControl {
+            client,
+            frameworks: Vec::new(),
+            logback: client.query_names(LOGBACK_CONFIGURATOR_PATTERN, "")?,
+        };
+        if !control.log4j2_configs()?.is_empty() {
+            frameworks.push(LoggingFramework::Log4j2);
+        }
+        if !control.logback.is_empty() {
+            frameworks.push(LoggingFramework::Logback);
+        }
+        Ok(LoggerControl { frameworks, ..control })
+    }
+
+    /// Logging frameworks detected on the JVM.
+    pub fn frameworks(&self) -> &[LoggingFramework] {
+        &self.frameworks
+    }
+
+    /// Loggers with the given name, from all the frameworks that know it.
+    pub fn logger(&self, name: &str) -> Result<Vec<Logger>> {
+        let loggers = self.loggers()?;
+        Ok(loggers.into_iter().filter(|logger| logger.name == name).collect())
+    }
+
+    /// All the loggers of all the frameworks, sorted by framework and name.
+    ///
+    /// Log4j 2 only lists the loggers with a configuration.
+    pub fn loggers(&self) -> Result<Vec<Logger>> {
+        let mut loggers = Vec::new();
+        for framework in &self.frameworks {
+            match *framework {
+                LoggingFramework::JavaUtilLogging => loggers.extend(self.jul_loggers()?),
+                LoggingFramework::Log4j2 => loggers.extend(self.log4j2_loggers()?),
+                LoggingFramework::Logback => loggers.extend(self.logback_loggers()?),
+            }
+        }
+        loggers.sort();
+        Ok(loggers)
+    }
+
+    /// Set the level of a logger, or let it inherit the level of its parent with `None`.
+    ///
+    /// The level is set with every framework that can: `java.util.logging` only
+    /// knows loggers created by the application, as it can't create loggers over JMX.
+    /// Log4j 2 adds a configuration for loggers without one, if the configuration of
+    /// the logger context is XML: the context is reconfigured from the updated text,
+    /// which discards levels previously set over JMX. Log4j 2 configurations always
+    /// have a level, so they can't inherit it with `None`.
+    /// Logback creates the logger if needed.
+    /// Returns the frameworks the level was set with and fails if there are none.
+    pub fn set_level(&self, name: &str, level: Option<Level>) -> Result<Vec<LoggingFramework>> {
+        let mut changed = Vec::new();
+        for framework in &self.frameworks {
+            let set = match *framework {
+                LoggingFramework::JavaUtilLogging => self.jul_set_level(name, level)?,
+                LoggingFramework::Log4j2 => self.log4j2_set_level(name, level)?,
+                LoggingFramework::Logback => self.logback_set_level(name, level)?,
+            };
+            if set {
+                changed.push(*framework);
+            }
+        }
+        if changed.is_empty() {
+            return Err(ErrorKind::LoggerNotFound(name.to_string()).into());
+        }
+        Ok(changed)
+    }
+}
+
+impl<'a, C> LoggerControl<'a, C>
+    where C: MBeanClientTrait,
+{
+    fn jul_loggers(&self) -> Result<Vec<Logger>> {
+        let names: Vec<String> =
+            self.client.get_attribute(JUL_LOGGING_MXBEAN_NAME, "LoggerNames")?;
+        let mut loggers = Vec::new();
+        for name in names {
+            let params = vec![Value::from(name.as_str())];
+            let level: Option<String> =
+                self.client.invoke(JUL_LOGGING_MXBEAN_NAME, "getLoggerLevel", params)?;
+
+            // Loggers can be garbage collected between the two calls.
+            if let Some(level) = level {
+                loggers.push(Logger {
+                    framework: LoggingFramework::JavaUtilLogging,
+                    level: some_level(level),
+                    name,
+                });
+            }
+        }
+        Ok(loggers)
+    }
+
+    fn jul_set_level(&self, name: &str, level: Option<Level>) -> Result<bool> {
+        let params = vec![Value::from(name)];
+        let current: Option<String> =
+            self.client.invoke(JUL_LOGGING_MXBEAN_NAME, "getLoggerLevel", params)?;
+        if current.is_none() {
+            return Ok(false);
+        }
+        let level = level.map(|level| level.name(LoggingFramework::JavaUtilLogging));
+        let params = vec![Value::from(name), Value::from(level)];
+        let _: Value = self.client.invoke(JUL_LOGGING_MXBEAN_NAME, "setLoggerLevel", params)?;
+        Ok(true)
+    }
+
+    /// Add the configuration of a logger to the XML configurations of all logger contexts.
+    fn log4j2_add_config(&self, name: &str, level: &str) -> Result<bool> {
+        let logger = format!(
+            "<Logger name=\"{}\" level=\"{}\"/>\n", escape_xml(name), level
+        );
+        let mut added = false;
+        for context in self.client.query_names(LOG4J2_CONTEXT_PATTERN, "")? {
+            let mut text: String = self.client.get_attribute(context.as_str(), "ConfigText")?;
+            let end = match text.to_ascii_lowercase().rfind("</loggers>") {
+                None => continue,
+                Some(end) => end,
+            };
+            text.insert_str(end, &logger);
+            let params = vec![Value::from(text), Value::from("UTF-8")];
+            let _: Value = self.client.invoke(context.as_str(), "setConfigText", params)?;
+            added = true;
+        }
+        Ok(added)
+    }
+
+    /// Names of the logger configuration MBeans, skipping the async logger ring buffers.
+    fn log4j2_configs(&self) -> Result<Vec<String>> {
+        let mut configs = Vec::new();
+        for name in self.client.query_names(LOG4J2_LOGGER_CONFIG_PATTERN, "")? {
+            if ObjectName::parse(name.as_str())?.key_property("subtype").is_none() {
+                configs.push(name);
+            }
+        }
+        Ok(configs)
+    }
+
+    fn log4j2_loggers(&self) -> Result<Vec<Logger>> {
+        let mut loggers = Vec::new();
+        for config in self.log4j2_configs()? {
+            let name: String = self.client.get_attribute(config.as_str(), "Name")?;
+            let level: String = self.client.get_attribute(config.as_str(), "Level")?;
+            loggers.push(Logger {
+                framework: LoggingFramework::Log4j2,
+                level: some_level(level),
+                name,
+            });
+        }
+        Ok(loggers)
+    }
+
+    fn log4j2_set_level(&self, name: &str, level: Option<Level>) -> Result<bool> {
+        let level = match level {
+            None => return Ok(false),
+            Some(level) => level.name(LoggingFramework::Log4j2),
+        };
+        let mut set = false;
+        for config in self.log4j2_configs()? {
+            let config_name: String = self.client.get_attribute(config.as_str(), "Name")?;
+            if config_name == name {
+                self.client.set_attribute(config.as_str(), "Level", level)?;
+                set = true;
+            }
+        }
+        if !set {
+            set = self.log4j2_add_config(name, level)?;
+        }
+        Ok(set)
+    }
+
+    fn logback_loggers(&self) -> Result<Vec<Logger>> {
+        let mut loggers = Vec::new();
+        for configurator in &self.logback {
+            let names: Vec<String> =
+                self.client.get_attribute(configurator.as_str(), "LoggerList")?;
+            for name in names {
+                let params = vec![Value::from(name.as_str())];
+                let level: String =
+                    self.client.invoke(configurator.as_str(), "getLoggerLevel", params)?;
+                let name = if name == LOGBACK_ROOT { String::new() } else { name };
+                loggers.push(Logger {
+                    framework: LoggingFramework::Logback,
+                    level: some_level(level),
+                    name,
+                });
+            }
+        }
+        Ok(loggers)
+    }
+
+    fn logback_set_level(&self, name: &str, level: Option<Level>) -> Result<bool> {
+        let name = if name.is_empty() { LOGBACK_ROOT } else { name };
+        let level = level.map_or("null", |level| level.name(LoggingFramework::Logback));
+        for configurator in &self.logback {
+            let params = vec![Value::from(name), Value::from(level)];
+            let _: Value = self.client.invoke(configurator.as_str(), "setLoggerLevel", params)?;
+        }
+        Ok(!self.logback.is_empty())
+    }
+}
+
+
+/// Escape text for an XML attribute value.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('"', "&quot;")
+}
+
+/// Frameworks report inherited levels as empty strings.
+fn some_level(level: String) -> Option<String> {
+    if level.is_empty() {
+        None
+    } else {
+        Some(level)
+    }
+}
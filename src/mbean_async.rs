@@ -0,0 +1,314 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::task::Context;
+use std::task::Poll;
+
+use failure::ResultExt;
+use futures_channel::oneshot;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::MBeanAddress;
+use super::MBeanInfo;
+use super::MBeanSnapshot;
+use super::MBeanThreadedClientOptions;
+use super::Result;
+
+use super::mbean_thread::MBeanReply;
+use super::mbean_thread::MBeanRequest;
+use super::mbean_thread::MBeanWorkerPool;
+
+
+/// Future resolved with the response of the background worker.
+///
+/// Dropping the future before it resolves cancels interest in the response
+/// but the worker will still process the request.
+#[must_use = "futures do nothing unless polled"]
+pub struct MBeanResponse<T> {
+    // Reason the request could not be sent to the worker, until the future is polled.
+    error: Option<::Error>,
+    // The receiver is `None` if the request could not be sent to the worker.
+    receiver: Option<oneshot::Receiver<Result<T>>>,
+}
+
+impl<T> MBeanResponse<T> {
+    /// Send a request to the worker and return a future for its response.
+    ///
+    /// Requests are never waited on: if the worker buffer is full the future
+    /// resolves with an error instead.
+    fn request<F>(workers: &MBeanWorkerPool, request: F) -> MBeanResponse<T>
+        where F: FnOnce(MBeanReply<T>) -> MBeanRequest,
+    {
+        let (sender, receiver) = oneshot::channel();
+        match workers.try_send(request(MBeanReply::Future(sender))) {
+            Err(error) => MBeanResponse::failed(error),
+            Ok(()) => MBeanResponse {
+                error: None,
+                receiver: Some(receiver),
+            },
+        }
+    }
+
+    /// Return a future that resolves with the given error.
+    fn failed(error: ::Error) -> MBeanResponse<T> {
+        MBeanResponse {
+            error: Some(error),
+            receiver: None,
+        }
+    }
+}
+
+impl<T> Future for MBeanResponse<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let this = self.get_mut();
+        let receiver = match this.receiver {
+            None => {
+                let error = this.error.take().unwrap_or_else(|| ErrorKind::WorkerNoSend.into());
+                return Poll::Ready(Err(error));
+            },
+            Some(ref mut receiver) => receiver,
+        };
+        match Pin::new(receiver).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(oneshot::Canceled)) => {
+                Poll::Ready(Err(ErrorKind::WorkerNoResponse.into()))
+            },
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+        }
+    }
+}
+
+
+/// Future resolved with the decoded value of an MBean attribute or operation result.
+#[must_use = "futures do nothing unless polled"]
+pub struct MBeanAttributeResponse<T> {
+    response: MBeanResponse<Value>,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Future for MBeanAttributeResponse<T>
+    where T: DeserializeOwned,
+{
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let value = match Pin::new(&mut self.get_mut().response).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(value) => value,
+        };
+        let value = value.and_then(|value| {
+            let value: T = serde_json::from_value(value).with_context(|_| ErrorKind::WorkerDecode)?;
+            Ok(value)
+        });
+        Poll::Ready(value)
+    }
+}
+
+
+/// Future resolved once all background workers have re-connected.
+///
+/// Resolves with the first error returned by any worker, if any.
+#[must_use = "futures do nothing unless polled"]
+pub struct MBeanReconnectResponse {
+    error: Option<::Error>,
+    responses: Vec<MBeanResponse<()>>,
+}
+
+impl Future for MBeanReconnectResponse {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        let mut idx = 0;
+        while idx < this.responses.len() {
+            match Pin::new(&mut this.responses[idx]).poll(cx) {
+                Poll::Pending => idx += 1,
+                Poll::Ready(result) => {
+                    drop(this.responses.swap_remove(idx));
+                    if let Err(error) = result {
+                        this.error.get_or_insert(error);
+                    }
+                },
+            }
+        }
+        if !this.responses.is_empty() {
+            return Poll::Pending;
+        }
+        match this.error.take() {
+            None => Poll::Ready(Ok(())),
+            Some(error) => Poll::Ready(Err(error)),
+        }
+    }
+}
+
+
+/// Implementation of an asynchronous `MBeanClient`.
+///
+/// Requests are processed by a pool of background workers, like `MBeanThreadedClient` does,
+/// but methods return futures instead of blocking the calling thread.
+/// The futures do not depend on any particular async runtime.
+///
+/// Methods never block the calling thread: when a requests buffer size is set
+/// and the buffer is full the returned future fails with `ErrorKind::WorkerQueueFull`.
+///
+/// Dropping the client does not wait for the background workers: they process
+/// the requests already sent and exit in the background.
+pub struct MBeanAsyncClient {
+    /// Options used by `reconnect`.
+    options: Mutex<Arc<MBeanThreadedClientOptions>>,
+    workers: MBeanWorkerPool,
+}
+
+impl MBeanAsyncClient {
+    /// Create an `MBeanAsyncClient` instance that is not connected to any server.
+    ///
+    /// Use `MBeanAsyncClient::reconnect` to connect the client to a server.
+    /// The `skip_connect` option is ignored as no connection is made.
+    pub fn new(options: MBeanThreadedClientOptions) -> Result<MBeanAsyncClient> {
+        let mut workers = MBeanWorkerPool::spawn(&options)?;
+        workers.detach();
+        Ok(MBeanAsyncClient {
+            options: Mutex::new(options.for_reconnect()),
+            workers,
+        })
+    }
+
+    /// Get the value of a specific MBean attribute.
+    pub fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> MBeanAttributeResponse<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let (mbean, attribute) = (mbean.into(), attribute.into());
+        let response = MBeanResponse::request(&self.workers, |reply| {
+            MBeanRequest::GetAttribute(mbean, attribute, reply)
+        });
+        MBeanAttributeResponse {
+            response,
+            value: PhantomData,
+        }
+    }
+
+    /// Get information about an MBean.
+    pub fn get_mbean_info<S>(&self, mbean: S) -> MBeanResponse<MBeanInfo>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        MBeanResponse::request(&self.workers, |reply| MBeanRequest::GetMBeanInfo(mbean, reply))
+    }
+
+    /// Invoke an operation on an MBean and decode its result.
+    ///
+    /// See `MBeanClientTrait::invoke` for how parameters are converted.
+    pub fn invoke<S1, S2, T>(
+        &self, mbean: S1, operation: S2, params: Vec<Value>
+    ) -> MBeanAttributeResponse<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let (mbean, operation) = (mbean.into(), operation.into());
+        let response = MBeanResponse::request(&self.workers, |reply| {
+            MBeanRequest::Invoke(mbean, operation, params, reply)
+        });
+        MBeanAttributeResponse {
+            response,
+            value: PhantomData,
+        }
+    }
+
+    /// Query for the names of MBeans on the JMX server.
+    pub fn query_names<S1, S2>(&self, name: S1, query: S2) -> MBeanResponse<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let (name, query) = (name.into(), query.into());
+        MBeanResponse::request(&self.workers, |reply| MBeanRequest::QueryNames(name, query, reply))
+    }
+
+    /// Set the value of a writable MBean attribute.
+    ///
+    /// Values that can't be encoded fail the request without reaching the worker.
+    pub fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> MBeanResponse<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let value = match serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode) {
+            Err(error) => return MBeanResponse::failed(error.into()),
+            Ok(value) => value,
+        };
+        let (mbean, attribute) = (mbean.into(), attribute.into());
+        MBeanResponse::request(&self.workers, |reply| {
+            MBeanRequest::SetAttribute(mbean, attribute, value, reply)
+        })
+    }
+
+    /// Get information about an MBean and the values of all its readable attributes.
+    pub fn snapshot_mbean<S>(&self, mbean: S) -> MBeanResponse<MBeanSnapshot>
+        where S: Into<String>,
+    {
+        let mbean = mbean.into();
+        MBeanResponse::request(&self.workers, |reply| MBeanRequest::SnapshotMBean(mbean, reply))
+    }
+
+    /// Request the MBean client to re-connect to the given address.
+    ///
+    /// The options the client was created with are used again, including the client
+    /// options factory and credentials, unless replaced by `reconnect_with_options`.
+    pub fn reconnect(&self, address: MBeanAddress) -> MBeanReconnectResponse {
+        let options = Arc::clone(&self.lock_options());
+        self.send_reconnect(address, options)
+    }
+
+    /// Request the MBean client to re-connect to the given address with the given options.
+    ///
+    /// All workers in the pool are re-connected.
+    /// The pool size and dispatch options are ignored.
+    /// The options replace those used by later calls to `reconnect`.
+    pub fn reconnect_with_options(
+        &self, address: MBeanAddress, options: MBeanThreadedClientOptions
+    ) -> MBeanReconnectResponse {
+        *self.lock_options() = options.for_reconnect();
+        self.send_reconnect(address, Arc::new(options))
+    }
+}
+
+impl MBeanAsyncClient {
+    fn lock_options(&self) -> MutexGuard<'_, Arc<MBeanThreadedClientOptions>> {
+        match self.options.lock() {
+            Err(poisoned) => poisoned.into_inner(),
+            Ok(options) => options,
+        }
+    }
+
+    /// Send a reconnect request to all workers.
+    fn send_reconnect(
+        &self, address: MBeanAddress, options: Arc<MBeanThreadedClientOptions>
+    ) -> MBeanReconnectResponse {
+        let mut responses = Vec::with_capacity(self.workers.size());
+        let sent = self.workers.try_broadcast(|| {
+            let (sender, receiver) = oneshot::channel();
+            responses.push(MBeanResponse {
+                error: None,
+                receiver: Some(receiver),
+            });
+            MBeanRequest::Reconnect(
+                address.clone(), Arc::clone(&options), MBeanReply::Future(sender)
+            )
+        });
+        MBeanReconnectResponse {
+            error: sent.err(),
+            responses,
+        }
+    }
+}
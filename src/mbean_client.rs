@@ -3,21 +3,25 @@ use j4rs::Instance;
 use j4rs::InvocationArg;
 use j4rs::Jvm;
 use j4rs::JvmBuilder;
+use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+use std::time::Duration;
 
 use super::ErrorKind;
+use super::ListenerId;
 use super::MBeanAddress;
 use super::MBeanClientTrait;
 use super::MBeanInfo;
+use super::MBeanSnapshot;
+use super::Notification;
 use super::Result;
 
+use super::constants::JMX_CONNECTOR;
 use super::constants::JMX_CONNECTOR_FACTORY;
-use super::constants::JMX_OBJECT_NAME;
-use super::constants::JMX_QUERY_EXP;
 
-use std::convert::TryFrom;
-
-use super::util::to_vec;
+use super::helper::Helper;
 
 
 /// Interface to a remote MBean server.
@@ -29,6 +33,7 @@ use super::util::to_vec;
 ///   * javax.management.MBeanServerConnection
 pub struct MBeanClient {
     connection: Instance,
+    helper: Helper,
     jvm: Jvm,
     // We access the server from the connection.
     _server: Instance,
@@ -44,19 +49,43 @@ impl MBeanClient {
     pub fn connect_with_options(
         address: MBeanAddress, options: MBeanClientOptions
     ) -> Result<MBeanClient> {
-        let jvm = options.jvm.build().with_context(|_| ErrorKind::JvmInit)?;
-        let service_url = address.for_java(&jvm)?;
-        MBeanClient::connect_service_url(jvm, service_url)
+        let jvm = options.build_jvm()?;
+        MBeanClient::connect_with_jvm(address, jvm, options.credentials.as_ref())
     }
 }
 
 impl MBeanClient {
+    /// Create an `MBeanClient` instance connected to the given address using an existing `Jvm`.
+    pub(crate) fn connect_with_jvm(
+        address: MBeanAddress, jvm: Jvm, credentials: Option<&Credentials>
+    ) -> Result<MBeanClient> {
+        let service_url = address.for_java(&jvm)?;
+        MBeanClient::connect_service_url(jvm, service_url, credentials)
+    }
+
     /// Helper to create an MBeanClient given a service url.
-    fn connect_service_url(jvm: Jvm, service_url: Instance) -> Result<MBeanClient> {
-        let server = MBeanClient::mbean_server(&jvm, service_url)?;
+    fn connect_service_url(
+        jvm: Jvm, service_url: Instance, credentials: Option<&Credentials>
+    ) -> Result<MBeanClient> {
+        let helper = Helper::load(&jvm)?;
+        let server = match credentials {
+            None => MBeanClient::mbean_server(&jvm, service_url)?,
+            Some(credentials) => {
+                let server = helper.apply(&jvm, "connect", vec![
+                    Helper::instance(&jvm, &service_url)?,
+                    Helper::value(&credentials.username), Helper::value(&credentials.password)
+                ])?;
+                jvm.cast(&server, JMX_CONNECTOR)
+                    .with_context(|_| ErrorKind::JavaCast(JMX_CONNECTOR.to_string()))?
+            },
+        };
         let connection = MBeanClient::get_connection(&jvm, &server)?;
+
+        // Fail the polls of notification listeners once the connection is closed.
+        let _: bool = helper.call(&jvm, "watch", vec![Helper::instance(&jvm, &server)?])?;
         Ok(MBeanClient {
             connection,
+            helper,
             jvm,
             _server: server,
         })
@@ -80,82 +109,150 @@ impl MBeanClient {
     }
 }
 
+impl Drop for MBeanClient {
+    fn drop(&mut self) {
+        // Remove the notification listeners of this client from the server.
+        let _: Result<bool> = self.helper.call(&self.jvm, "close", Vec::new());
+    }
+}
+
+/// Notification listeners belong to the client that added them.
+/// Polls fail once the connection to the server is closed or if notifications
+/// were dropped because too many were queued.
 impl MBeanClientTrait for MBeanClient {
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        let id = self.helper.call(&self.jvm, "addListener", vec![
+            connection, Helper::value(&mbean.into()), Helper::value(&types)
+        ])?;
+        Ok(ListenerId(id))
+    }
+
+    /// The client never reconnects, the identifier never changes.
+    fn connection_id(&self) -> Option<u64> {
+        Some(0)
+    }
+
     fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
         where S1: Into<String>,
               S2: Into<String>,
               T: DeserializeOwned,
     {
-        let object_name = self.jvm.create_instance(
-            JMX_OBJECT_NAME, &vec![InvocationArg::try_from(mbean.into())?]
-        ).with_context(|_| ErrorKind::JavaCreateInstance(JMX_OBJECT_NAME))?;
-        let value = self.jvm.invoke(
-            &self.connection, "getAttribute",
-            &vec![InvocationArg::from(object_name), InvocationArg::try_from(attribute.into())?]
-        ).with_context(
-            |_| ErrorKind::JavaInvoke(self.connection.class_name().to_string(), "getAttribute")
-        )?;
-        let value: T = self.jvm.to_rust(value).with_context(|_| ErrorKind::RustCast("<dynamic>"))?;
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        let mut value: Vec<Value> = self.helper.call(&self.jvm, "getAttribute", vec![
+            connection, Helper::value(&mbean.into()), Helper::value(&attribute.into())
+        ])?;
+        let value = value.pop().unwrap_or(Value::Null);
+        let value: T = serde_json::from_value(value).with_context(|_| ErrorKind::ValueDecode)?;
         Ok(value)
     }
 
     fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
         where S: Into<String>,
     {
-        let object_name = self.jvm.create_instance(
-            JMX_OBJECT_NAME, &vec![InvocationArg::try_from(mbean.into())?]
-        ).with_context(|_| ErrorKind::JavaCreateInstance(JMX_OBJECT_NAME))?;
-        let info = self.jvm.invoke(
-            &self.connection, "getMBeanInfo",
-            &vec![InvocationArg::from(object_name)]
-        ).with_context(
-            |_| ErrorKind::JavaInvoke(self.connection.class_name().to_string(), "getMBeanInfo")
-        )?;
-        MBeanInfo::from_instance(&self.jvm, info)
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        self.helper.call(&self.jvm, "mbeanInfo", vec![connection, Helper::value(&mbean.into())])
     }
 
-    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
         where S1: Into<String>,
               S2: Into<String>,
+              T: DeserializeOwned,
     {
-        let name = self.jvm.create_instance(
-            JMX_OBJECT_NAME, &vec![InvocationArg::try_from(name.into())?]
-        ).with_context(|_| ErrorKind::JavaCreateInstance(JMX_OBJECT_NAME))?;
-        let query = self.jvm.create_instance(
-            JMX_OBJECT_NAME, &vec![InvocationArg::try_from(query.into())?]
-        ).with_context(|_| ErrorKind::JavaCreateInstance(JMX_OBJECT_NAME))?;
-        let query = self.jvm.cast(&query, JMX_QUERY_EXP)
-            .with_context(|_| ErrorKind::JavaCast(JMX_QUERY_EXP.to_string()))?;
-        let names = self.jvm.invoke(
-            &self.connection, "queryNames",
-            &vec![InvocationArg::from(name), InvocationArg::from(query)]
-        ).with_context(
-            |_| ErrorKind::JavaInvoke(self.connection.class_name().to_string(), "queryNames")
-        )?;
-        let names = self.jvm.invoke(&names, "toArray", &vec![]).with_context(
-            |_| ErrorKind::JavaInvoke(names.class_name().to_string(), "toArray")
-        )?;
-        let names = to_vec(&self.jvm, names, JMX_OBJECT_NAME)?;
-        let mut result = Vec::new();
-        for instance in names {
-            let instance = self.jvm.invoke(&instance, "toString", &vec![]).with_context(
-                |_| ErrorKind::JavaInvoke(instance.class_name().to_string(), "toString")
-            )?;
-            let name: String = self.jvm.to_rust(instance)
-                .with_context(|_| ErrorKind::RustCast("String"))?;
-            result.push(name);
-        }
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        let mut result: Vec<Value> = self.helper.call(&self.jvm, "invoke", vec![
+            connection, Helper::value(&mbean.into()), Helper::value(&operation.into()),
+            Helper::value(&params)
+        ])?;
+        let result = result.pop().unwrap_or(Value::Null);
+        let result: T = serde_json::from_value(result).with_context(|_| ErrorKind::ValueDecode)?;
         Ok(result)
     }
+
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        let timeout = timeout.as_millis() as u64;
+        self.helper.call(&self.jvm, "pollListener", vec![
+            Helper::value(&listener.0), Helper::value(&timeout)
+        ])
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        self.helper.call(&self.jvm, "queryNames", vec![
+            connection, Helper::value(&name.into()), Helper::value(&query.into())
+        ])
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        let _: bool = self.helper.call(&self.jvm, "removeListener", vec![
+            Helper::value(&listener.0)
+        ])?;
+        Ok(())
+    }
+
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        let _: bool = self.helper.call(&self.jvm, "setAttribute", vec![
+            connection, Helper::value(&mbean.into()), Helper::value(&attribute.into()),
+            Helper::value(&value)
+        ])?;
+        Ok(())
+    }
+
+    fn snapshot_mbean<S>(&self, mbean: S) -> Result<MBeanSnapshot>
+        where S: Into<String>,
+    {
+        let connection = Helper::instance(&self.jvm, &self.connection)?;
+        self.helper.call(&self.jvm, "snapshot", vec![connection, Helper::value(&mbean.into())])
+    }
+}
+
+
+/// Username and password used to authenticate with a JMX server.
+#[derive(Clone)]
+pub(crate) struct Credentials {
+    pub(crate) password: String,
+    pub(crate) username: String,
 }
 
 
 /// Additional `MBeanClient` connection options.
 pub struct MBeanClientOptions<'a> {
+    credentials: Option<Credentials>,
     jvm: JvmBuilder<'a>,
 }
 
 impl<'a> MBeanClientOptions<'a> {
+    /// Build the JVM described by these options.
+    pub(crate) fn build_jvm(&self) -> Result<Jvm> {
+        let jvm = self.jvm.build().with_context(|_| ErrorKind::JvmInit)?;
+        Ok(jvm)
+    }
+
+    /// Authenticate with the given username and password when connecting.
+    pub fn credentials<S1, S2>(mut self, username: S1, password: S2) -> Self
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.credentials = Some(Credentials {
+            password: password.into(),
+            username: username.into(),
+        });
+        self
+    }
+
     /// Use the given JvmBuilder instance instead of the default one.
     pub fn builder(mut self, builder: JvmBuilder<'a>) -> Self {
         self.jvm = builder;
@@ -166,6 +263,7 @@ impl<'a> MBeanClientOptions<'a> {
 impl<'a> Default for MBeanClientOptions<'a> {
     fn default() -> Self {
         MBeanClientOptions {
+            credentials: None,
             jvm: JvmBuilder::new(),
         }
     }
@@ -1,53 +1,127 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
 use std::thread::Builder;
 use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
 
 use crossbeam_channel as channel;
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
+#[cfg(feature = "async")]
+use crossbeam_channel::TrySendError;
 use failure::ResultExt;
+#[cfg(feature = "async")]
+use futures_channel::oneshot;
 
+use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json;
 use serde_json::Value;
 
 use super::ErrorKind;
+use super::JmxRuntime;
+use super::ListenerId;
 use super::MBeanAddress;
 use super::MBeanClient;
 use super::MBeanClientOptions;
 use super::MBeanClientTrait;
 use super::MBeanInfo;
+use super::MBeanSnapshot;
+use super::Notification;
 use super::Result;
 
+use super::mbean_client::Credentials;
+
+
+/// Time between checks for notifications while `poll_notifications` waits.
+const NOTIFICATIONS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+
+/// Channel used by the background worker to respond to a request.
+pub(crate) enum MBeanReply<T> {
+    /// Respond to a blocking `MBeanThreadedClient` request.
+    Blocking(Sender<Result<T>>),
+
+    /// Respond to an `MBeanAsyncClient` future.
+    #[cfg(feature = "async")]
+    Future(oneshot::Sender<Result<T>>),
+}
+
+impl<T> MBeanReply<T> {
+    /// Send the response to the requester.
+    ///
+    /// Returns `false` if the worker should stop because the requester is gone.
+    /// Dropped futures are not an error: they are how async requests are cancelled.
+    fn send(self, response: Result<T>) -> bool {
+        match self {
+            MBeanReply::Blocking(sender) => sender.send(response).is_ok(),
+            #[cfg(feature = "async")]
+            MBeanReply::Future(sender) => {
+                let _ = sender.send(response);
+                true
+            },
+        }
+    }
+}
+
 
 /// Encode requests sent to the background `MBeanClient`.
-enum MBeanRequest {
+pub(crate) enum MBeanRequest {
+    /// Ask the worker to perform an `add_notification_listener` call.
+    AddNotificationListener(String, Vec<String>, MBeanReply<ListenerId>),
+
     /// Ask the worker to perform a `get_attribute` call.
-    GetAttribute(String, String, Sender<Result<Value>>),
+    GetAttribute(String, String, MBeanReply<Value>),
 
     /// Ask the worker to perform a `get_mbean_info` call.
-    GetMBeanInfo(String, Sender<Result<MBeanInfo>>),
+    GetMBeanInfo(String, MBeanReply<MBeanInfo>),
+
+    /// Ask the worker to perform an `invoke` call.
+    Invoke(String, String, Vec<Value>, MBeanReply<Value>),
+
+    /// Ask the worker to perform a `poll_notifications` call that does not wait.
+    PollNotifications(ListenerId, MBeanReply<Vec<Notification>>),
 
     /// Ask the worker to perform a `query_names` call.
-    QueryNames(String, String, Sender<Result<Vec<String>>>),
+    QueryNames(String, String, MBeanReply<Vec<String>>),
 
     /// Request termination of the background thread.
     Quit,
 
     /// Request the MBean client to re-connect to the given address with the given options.
-    Reconnect(MBeanAddress, MBeanThreadedClientOptions, Sender<Result<()>>),
+    Reconnect(MBeanAddress, Arc<MBeanThreadedClientOptions>, MBeanReply<()>),
+
+    /// Ask the worker to perform a `remove_notification_listener` call.
+    RemoveNotificationListener(ListenerId, MBeanReply<()>),
+
+    /// Ask the worker to perform a `set_attribute` call.
+    SetAttribute(String, String, Value, MBeanReply<()>),
+
+    /// Ask the worker to perform a `snapshot_mbean` call.
+    SnapshotMBean(String, MBeanReply<MBeanSnapshot>),
 }
 
 
 /// Encapsulate the logic and state of the async worker thread.
 struct MBeanThreadWorker {
     client: Option<MBeanClient>,
+    pending: Arc<AtomicUsize>,
     receiver: Receiver<MBeanRequest>,
 }
 
 impl MBeanThreadWorker {
-    fn new(receiver: Receiver<MBeanRequest>) -> MBeanThreadWorker {
+    fn new(receiver: Receiver<MBeanRequest>, pending: Arc<AtomicUsize>) -> MBeanThreadWorker {
         MBeanThreadWorker {
             client: None,
+            pending,
             receiver,
         }
     }
@@ -59,62 +133,266 @@ impl MBeanThreadWorker {
 
     /// Wait for requests from other threads and process them.
     fn work(&mut self) {
-        for request in &self.receiver {
-            match request {
-                MBeanRequest::GetAttribute(mbean, attribute, sender) => {
-                    let response: Result<Value> = self.client()
-                        .and_then(|c| c.get_attribute(mbean, attribute));
-                    if let Err(_) = sender.send(response) {
-                        return;
-                    }
-                },
-                MBeanRequest::GetMBeanInfo(mbean, sender) => {
-                    let response = self.client().and_then(|c| c.get_mbean_info(mbean));
-                    if let Err(_) = sender.send(response) {
-                        return;
-                    }
-                },
-                MBeanRequest::QueryNames(name, query, sender) => {
-                    let response = self.client().and_then(|c| c.query_names(name, query));
-                    if let Err(_) = sender.send(response) {
-                        return;
-                    }
-                },
-                MBeanRequest::Quit => break,
-                MBeanRequest::Reconnect(address, options, sender) => {
-                    if options.skip_connect {
-                        self.client = None;
-                        if let Err(_) = sender.send(Ok(())) {
-                            return;
-                        }
-                    } else {
-                        let client = MBeanClient::connect_with_options(address, options.into());
-                        let sent = match client {
-                            Err(error) => sender.send(Err(error)),
-                            Ok(new_client) => {
-                                self.client = Some(new_client);
-                                sender.send(Ok(()))
-                            }
-                        };
-                        if let Err(_) = sent {
-                            return;
+        while let Ok(request) = self.receiver.recv() {
+            if !self.process(request) {
+                return;
+            }
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Process a request, returning `false` if the worker should stop.
+    fn process(&mut self, request: MBeanRequest) -> bool {
+        match request {
+            MBeanRequest::AddNotificationListener(mbean, types, reply) => {
+                let response = self.client()
+                    .and_then(|c| c.add_notification_listener(mbean, types));
+                reply.send(response)
+            },
+            MBeanRequest::GetAttribute(mbean, attribute, reply) => {
+                let response: Result<Value> = self.client()
+                    .and_then(|c| c.get_attribute(mbean, attribute));
+                reply.send(response)
+            },
+            MBeanRequest::GetMBeanInfo(mbean, reply) => {
+                let response = self.client().and_then(|c| c.get_mbean_info(mbean));
+                reply.send(response)
+            },
+            MBeanRequest::Invoke(mbean, operation, params, reply) => {
+                let response: Result<Value> = self.client()
+                    .and_then(|c| c.invoke(mbean, operation, params));
+                reply.send(response)
+            },
+            MBeanRequest::PollNotifications(listener, reply) => {
+                let response = self.client()
+                    .and_then(|c| c.poll_notifications(listener, Duration::from_millis(0)));
+                reply.send(response)
+            },
+            MBeanRequest::QueryNames(name, query, reply) => {
+                let response = self.client().and_then(|c| c.query_names(name, query));
+                reply.send(response)
+            },
+            MBeanRequest::Quit => false,
+            MBeanRequest::Reconnect(address, options, reply) => {
+                if options.skip_connect {
+                    self.client = None;
+                    reply.send(Ok(()))
+                } else {
+                    let client = match options.runtime {
+                        None => MBeanClient::connect_with_options(
+                            address, options.build_client_options()
+                        ),
+                        Some(ref runtime) => runtime.connect_with(
+                            address, options.credentials.as_ref()
+                        ),
+                    };
+                    match client {
+                        Err(error) => reply.send(Err(error)),
+                        Ok(new_client) => {
+                            self.client = Some(new_client);
+                            reply.send(Ok(()))
                         }
                     }
-                },
-            };
+                }
+            },
+            MBeanRequest::RemoveNotificationListener(listener, reply) => {
+                let response = self.client()
+                    .and_then(|c| c.remove_notification_listener(listener));
+                reply.send(response)
+            },
+            MBeanRequest::SetAttribute(mbean, attribute, value, reply) => {
+                let response = self.client().and_then(|c| c.set_attribute(mbean, attribute, value));
+                reply.send(response)
+            },
+            MBeanRequest::SnapshotMBean(mbean, reply) => {
+                let response = self.client().and_then(|c| c.snapshot_mbean(mbean));
+                reply.send(response)
+            },
         }
     }
 }
 
 
-/// Implementation of a thread safe `MBeanClient`.
-pub struct MBeanThreadedClient {
+/// Handle to the background thread owning an `MBeanClient`.
+///
+/// The thread is stopped when the handle is dropped.
+struct MBeanWorkerHandle {
+    // Number of requests sent to the worker and not yet processed.
+    pending: Arc<AtomicUsize>,
     // Sender end of the channel to the background thread.
     send_to_worker: Sender<MBeanRequest>,
-    // Background worker is `None` only after `Drop::drop` is called.
+    // Background worker is `None` after `Drop::drop` is called or if the worker is detached.
     worker: Option<JoinHandle<()>>,
 }
 
+impl MBeanWorkerHandle {
+    /// Start a background worker thread, not yet connected to any server.
+    fn spawn(options: &MBeanThreadedClientOptions) -> Result<MBeanWorkerHandle> {
+        let (send_to_worker, worker_receiver) = match options.reqs_buffer {
+            None => channel::unbounded(),
+            Some(size) => channel::bounded(size),
+        };
+        let pending = Arc::new(AtomicUsize::new(0));
+        let worker_pending = Arc::clone(&pending);
+        let worker = Builder::new().name("MBeanThreadedClient::worker".into()).spawn(|| {
+            let mut worker = MBeanThreadWorker::new(worker_receiver, worker_pending);
+            worker.work();
+        }).with_context(|_| ErrorKind::WorkerSpawn)?;
+        Ok(MBeanWorkerHandle {
+            pending,
+            send_to_worker,
+            worker: Some(worker),
+        })
+    }
+
+    /// Send a request to the background worker.
+    fn send(&self, request: MBeanRequest) -> Result<()> {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.send_to_worker.send(request).map_err(|_| {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            ErrorKind::WorkerNoSend.into()
+        })
+    }
+
+    /// Send a request to the background worker without waiting for room in the buffer.
+    #[cfg(feature = "async")]
+    fn try_send(&self, request: MBeanRequest) -> Result<()> {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.send_to_worker.try_send(request).map_err(|error| {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            match error {
+                TrySendError::Disconnected(_) => ErrorKind::WorkerNoSend.into(),
+                TrySendError::Full(_) => ErrorKind::WorkerQueueFull.into(),
+            }
+        })
+    }
+}
+
+impl Drop for MBeanWorkerHandle {
+    fn drop(&mut self) {
+        // Detached workers exit once the channel is closed and drained.
+        if let Some(worker) = self.worker.take() {
+            let _err = self.send_to_worker.send(MBeanRequest::Quit);
+            let _err = worker.join();
+        }
+    }
+}
+
+
+/// Pool of background workers, each with its own `MBeanClient`.
+pub(crate) struct MBeanWorkerPool {
+    dispatch: WorkerDispatch,
+    next: AtomicUsize,
+    workers: Vec<MBeanWorkerHandle>,
+}
+
+impl MBeanWorkerPool {
+    /// Start the background workers, not yet connected to any server.
+    pub(crate) fn spawn(options: &MBeanThreadedClientOptions) -> Result<MBeanWorkerPool> {
+        let mut workers = Vec::with_capacity(options.workers);
+        for _ in 0..options.workers {
+            workers.push(MBeanWorkerHandle::spawn(options)?);
+        }
+        Ok(MBeanWorkerPool {
+            dispatch: options.dispatch,
+            next: AtomicUsize::new(0),
+            workers,
+        })
+    }
+
+    /// Send a request built by `request` to every worker in the pool.
+    pub(crate) fn broadcast<F>(&self, mut request: F) -> Result<()>
+        where F: FnMut() -> MBeanRequest,
+    {
+        for worker in &self.workers {
+            worker.send(request())?;
+        }
+        Ok(())
+    }
+
+    /// Stop waiting for the workers to exit when the pool is dropped.
+    ///
+    /// Detached workers process the requests already buffered and exit in the background.
+    #[cfg(feature = "async")]
+    pub(crate) fn detach(&mut self) {
+        for worker in &mut self.workers {
+            worker.worker.take();
+        }
+    }
+
+    /// Select the worker for the next request with the dispatch strategy.
+    pub(crate) fn pick(&self) -> usize {
+        match self.dispatch {
+            WorkerDispatch::LeastBusy => self.workers.iter()
+                .enumerate()
+                .min_by_key(|(_, worker)| worker.pending.load(Ordering::SeqCst))
+                .map(|(index, _)| index)
+                .expect("worker pools have at least one worker"),
+            WorkerDispatch::RoundRobin => {
+                self.next.fetch_add(1, Ordering::SeqCst) % self.workers.len()
+            },
+        }
+    }
+
+    /// Send a request to one of the workers, as selected by the dispatch strategy.
+    pub(crate) fn send(&self, request: MBeanRequest) -> Result<()> {
+        self.send_to(self.pick(), request)
+    }
+
+    /// Send a request to the worker selected with `pick`.
+    pub(crate) fn send_to(&self, worker: usize, request: MBeanRequest) -> Result<()> {
+        self.workers[worker].send(request)
+    }
+
+    /// Send a request to every worker in the pool without waiting for room in the buffers.
+    #[cfg(feature = "async")]
+    pub(crate) fn try_broadcast<F>(&self, mut request: F) -> Result<()>
+        where F: FnMut() -> MBeanRequest,
+    {
+        for worker in &self.workers {
+            worker.try_send(request())?;
+        }
+        Ok(())
+    }
+
+    /// Send a request to one of the workers without waiting for room in its buffer.
+    #[cfg(feature = "async")]
+    pub(crate) fn try_send(&self, request: MBeanRequest) -> Result<()> {
+        self.workers[self.pick()].try_send(request)
+    }
+
+    /// Number of workers in the pool.
+    pub(crate) fn size(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl Drop for MBeanWorkerPool {
+    fn drop(&mut self) {
+        // Ask all workers to stop before waiting for any of them so they drain in parallel.
+        for worker in &self.workers {
+            if worker.worker.is_some() {
+                let _err = worker.send_to_worker.send(MBeanRequest::Quit);
+            }
+        }
+    }
+}
+
+
+/// Implementation of a thread safe `MBeanClient`.
+///
+/// Requests are processed by a pool of background workers, one by default.
+/// Each worker owns its own `MBeanClient` connected to the same server.
+pub struct MBeanThreadedClient {
+    /// Incremented by every reconnection, see `connection_id`.
+    connection: AtomicU64,
+    /// Worker that added each notification listener.
+    listeners: Mutex<HashMap<ListenerId, usize>>,
+    /// Options used by `reconnect`.
+    options: Mutex<Arc<MBeanThreadedClientOptions>>,
+    workers: MBeanWorkerPool,
+}
+
 impl MBeanThreadedClient {
     /// Create an `MBeanThreadedClient` instance connected to the given address.
     ///
@@ -129,17 +407,12 @@ impl MBeanThreadedClient {
     pub fn connect_with_options(
         address: MBeanAddress, options: MBeanThreadedClientOptions
     ) -> Result<MBeanThreadedClient> {
-        let (send_to_worker, worker_receiver) = match options.reqs_buffer {
-            None => channel::unbounded(),
-            Some(size) => channel::bounded(size),
-        };
-        let worker = Builder::new().name("MBeanThreadedClient::worker".into()).spawn(|| {
-            let mut worker = MBeanThreadWorker::new(worker_receiver);
-            worker.work();
-        }).with_context(|_| ErrorKind::WorkerSpawn)?;
+        let workers = MBeanWorkerPool::spawn(&options)?;
         let client = MBeanThreadedClient {
-            send_to_worker,
-            worker: Some(worker),
+            connection: AtomicU64::new(0),
+            listeners: Mutex::new(HashMap::new()),
+            options: Mutex::new(options.for_reconnect()),
+            workers,
         };
         if !options.skip_connect {
             client.reconnect_with_options(address, options)?;
@@ -148,19 +421,73 @@ impl MBeanThreadedClient {
     }
 
     /// Request the MBean client to re-connect to the given address.
+    ///
+    /// The options the client was built with are used again, including the client
+    /// options factory and credentials, unless replaced by `reconnect_with_options`.
     pub fn reconnect(&self, address: MBeanAddress) -> Result<()> {
-        self.reconnect_with_options(address, MBeanThreadedClientOptions::default())
+        let options = Arc::clone(&self.lock_options());
+        self.send_reconnect(address, options)
     }
 
     /// Request the MBean client to re-connect to the given address with the given options.
+    ///
+    /// All workers in the pool are re-connected and the first error, if any, is returned.
+    /// The pool size and dispatch options are ignored.
+    /// Notification listeners are removed with the old connections.
+    /// The options replace those used by later calls to `reconnect`.
     pub fn reconnect_with_options(
         &self, address: MBeanAddress, options: MBeanThreadedClientOptions
     ) -> Result<()> {
-        let (sender, receiver) = channel::bounded(1);
-        let request = MBeanRequest::Reconnect(address, options, sender);
-        if let Err(_) = self.send_to_worker.send(request) {
-            return Err(ErrorKind::WorkerNoSend.into());
+        *self.lock_options() = options.for_reconnect();
+        self.send_reconnect(address, Arc::new(options))
+    }
+}
+
+impl MBeanThreadedClient {
+    fn lock_listeners(&self) -> MutexGuard<'_, HashMap<ListenerId, usize>> {
+        match self.listeners.lock() {
+            Err(poisoned) => poisoned.into_inner(),
+            Ok(listeners) => listeners,
+        }
+    }
+
+    fn lock_options(&self) -> MutexGuard<'_, Arc<MBeanThreadedClientOptions>> {
+        match self.options.lock() {
+            Err(poisoned) => poisoned.into_inner(),
+            Ok(options) => options,
         }
+    }
+
+    /// Re-connect all workers and wait for them.
+    fn send_reconnect(
+        &self, address: MBeanAddress, options: Arc<MBeanThreadedClientOptions>
+    ) -> Result<()> {
+        self.lock_listeners().clear();
+        self.connection.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = channel::bounded(self.workers.size());
+        self.workers.broadcast(|| MBeanRequest::Reconnect(
+            address.clone(), Arc::clone(&options), MBeanReply::Blocking(sender.clone())
+        ))?;
+        let mut result = Ok(());
+        for _ in 0..self.workers.size() {
+            let response = match receiver.recv() {
+                Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+                Ok(response) => response,
+            };
+            if result.is_ok() {
+                result = response;
+            }
+        }
+        result
+    }
+
+    /// Return the notifications queued for a listener without waiting.
+    fn poll_queued(&self, listener: ListenerId) -> Result<Vec<Notification>> {
+        let worker = self.lock_listeners().get(&listener).cloned();
+        let worker = worker.unwrap_or_else(|| self.workers.pick());
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::PollNotifications(listener, MBeanReply::Blocking(sender));
+        self.workers.send_to(worker, request)?;
         match receiver.recv() {
             Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
             Ok(result) => result,
@@ -168,24 +495,42 @@ impl MBeanThreadedClient {
     }
 }
 
-impl Drop for MBeanThreadedClient {
-    fn drop(&mut self) {
-        let _err = self.send_to_worker.send(MBeanRequest::Quit);
-        let _err = self.worker.take().unwrap().join();
+/// Notification listeners belong to the worker that added them, which serves
+/// all requests for them.
+/// Polls wait on the calling thread, so workers keep serving other requests.
+impl MBeanClientTrait for MBeanThreadedClient {
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        let worker = self.workers.pick();
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::AddNotificationListener(
+            mbean.into(), types, MBeanReply::Blocking(sender)
+        );
+        self.workers.send_to(worker, request)?;
+        let listener = match receiver.recv() {
+            Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+            Ok(result) => result,
+        }?;
+        self.lock_listeners().insert(listener, worker);
+        Ok(listener)
+    }
+
+    /// The identifier changes every time the workers are asked to reconnect.
+    fn connection_id(&self) -> Option<u64> {
+        Some(self.connection.load(Ordering::SeqCst))
     }
-}
 
-impl MBeanClientTrait for MBeanThreadedClient {
     fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
         where S1: Into<String>,
               S2: Into<String>,
               T: DeserializeOwned,
     {
         let (sender, receiver) = channel::bounded(1);
-        let request = MBeanRequest::GetAttribute(mbean.into(), attribute.into(), sender);
-        if let Err(_) = self.send_to_worker.send(request) {
-            return Err(ErrorKind::WorkerNoSend.into());
-        }
+        let request = MBeanRequest::GetAttribute(
+            mbean.into(), attribute.into(), MBeanReply::Blocking(sender)
+        );
+        self.workers.send(request)?;
         let value: Value = match receiver.recv() {
             Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
             Ok(result) => result,
@@ -198,25 +543,98 @@ impl MBeanClientTrait for MBeanThreadedClient {
         where S: Into<String>,
     {
         let (sender, receiver) = channel::bounded(1);
-        let request = MBeanRequest::GetMBeanInfo(mbean.into(), sender);
-        if let Err(_) = self.send_to_worker.send(request) {
-            return Err(ErrorKind::WorkerNoSend.into());
-        }
+        let request = MBeanRequest::GetMBeanInfo(mbean.into(), MBeanReply::Blocking(sender));
+        self.workers.send(request)?;
         match receiver.recv() {
             Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
             Ok(result) => result,
         }
     }
 
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::Invoke(
+            mbean.into(), operation.into(), params, MBeanReply::Blocking(sender)
+        );
+        self.workers.send(request)?;
+        let value: Value = match receiver.recv() {
+            Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+            Ok(result) => result,
+        }?;
+        let value: T = serde_json::from_value(value).with_context(|_| ErrorKind::WorkerDecode)?;
+        Ok(value)
+    }
+
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        let start = Instant::now();
+        loop {
+            let notifications = self.poll_queued(listener)?;
+            let elapsed = start.elapsed();
+            if !notifications.is_empty() || elapsed >= timeout {
+                return Ok(notifications);
+            }
+            thread::sleep(cmp::min(NOTIFICATIONS_POLL_INTERVAL, timeout - elapsed));
+        }
+    }
+
     fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
         where S1: Into<String>,
               S2: Into<String>,
     {
         let (sender, receiver) = channel::bounded(1);
-        let request = MBeanRequest::QueryNames(name.into(), query.into(), sender);
-        if let Err(_) = self.send_to_worker.send(request) {
-            return Err(ErrorKind::WorkerNoSend.into());
+        let request = MBeanRequest::QueryNames(
+            name.into(), query.into(), MBeanReply::Blocking(sender)
+        );
+        self.workers.send(request)?;
+        match receiver.recv() {
+            Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+            Ok(result) => result,
+        }
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        let worker = self.lock_listeners().remove(&listener);
+        let worker = worker.unwrap_or_else(|| self.workers.pick());
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::RemoveNotificationListener(
+            listener, MBeanReply::Blocking(sender)
+        );
+        self.workers.send_to(worker, request)?;
+        match receiver.recv() {
+            Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+            Ok(result) => result,
+        }
+    }
+
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::SetAttribute(
+            mbean.into(), attribute.into(), value, MBeanReply::Blocking(sender)
+        );
+        self.workers.send(request)?;
+        match receiver.recv() {
+            Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
+            Ok(result) => result,
         }
+    }
+
+    fn snapshot_mbean<S>(&self, mbean: S) -> Result<MBeanSnapshot>
+        where S: Into<String>,
+    {
+        let (sender, receiver) = channel::bounded(1);
+        let request = MBeanRequest::SnapshotMBean(mbean.into(), MBeanReply::Blocking(sender));
+        self.workers.send(request)?;
         match receiver.recv() {
             Err(_) => Err(ErrorKind::WorkerNoResponse.into()),
             Ok(result) => result,
@@ -225,13 +643,67 @@ impl MBeanClientTrait for MBeanThreadedClient {
 }
 
 
+/// Strategy used to pick the background worker that processes a request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum WorkerDispatch {
+    /// Send requests to the worker with the fewest pending requests.
+    LeastBusy,
+
+    /// Send requests to each worker in turn.
+    RoundRobin,
+}
+
+
+/// Factory of `MBeanClientOptions` invoked by the background workers.
+type ClientOptionsFactory = dyn Fn() -> MBeanClientOptions<'static> + Send + Sync;
+
+
 /// Additional `MBeanThreadedClient` connection options.
+#[derive(Clone)]
 pub struct MBeanThreadedClientOptions {
+    client_options: Option<Arc<ClientOptionsFactory>>,
+    credentials: Option<Credentials>,
+    dispatch: WorkerDispatch,
     reqs_buffer: Option<usize>,
+    runtime: Option<JmxRuntime>,
     skip_connect: bool,
+    workers: usize,
 }
 
 impl<'a> MBeanThreadedClientOptions {
+    /// Set a factory for the options used by each worker to create its `MBeanClient`.
+    ///
+    /// Because `JvmBuilder` can't be sent across threads the factory is called
+    /// by each worker thread every time it (re-)connects to a server.
+    /// Use this to customise the JVM, for example to extend the classpath.
+    pub fn client_options<F>(mut self, factory: F) -> Self
+        where F: Fn() -> MBeanClientOptions<'static> + Send + Sync + 'static,
+    {
+        self.client_options = Some(Arc::new(factory));
+        self
+    }
+
+    /// Authenticate with the given username and password when connecting.
+    ///
+    /// The credentials apply with or without a shared runtime and replace
+    /// any credentials set by the client options factory.
+    pub fn credentials<S1, S2>(mut self, username: S1, password: S2) -> Self
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.credentials = Some(Credentials {
+            password: password.into(),
+            username: username.into(),
+        });
+        self
+    }
+
+    /// Set the strategy used to pick the worker that processes each request.
+    pub fn dispatch(mut self, dispatch: WorkerDispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
     /// Clear the requests buffer size so unlimited requests are buffered.
     pub fn requests_buffer_unlimited(mut self) -> Self {
         self.reqs_buffer = None;
@@ -244,23 +716,70 @@ impl<'a> MBeanThreadedClientOptions {
         self
     }
 
+    /// Connect the workers using a shared `JmxRuntime`.
+    ///
+    /// When a runtime is set the client options factory is not used.
+    pub fn runtime(mut self, runtime: JmxRuntime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
     pub fn skip_connect(mut self, skip: bool) -> Self {
         self.skip_connect = skip;
         self
     }
+
+    /// Set the number of background workers, each with its own connection.
+    ///
+    /// The requests buffer size applies to each worker.
+    ///
+    /// # Panics
+    /// If `workers` is zero.
+    pub fn workers(mut self, workers: usize) -> Self {
+        assert!(workers > 0, "MBeanThreadedClient needs at least one worker");
+        self.workers = workers;
+        self
+    }
+}
+
+impl MBeanThreadedClientOptions {
+    /// Options used by the workers to create their `MBeanClient`, with the credentials.
+    fn build_client_options(&self) -> MBeanClientOptions<'static> {
+        let options = match self.client_options {
+            None => MBeanClientOptions::default(),
+            Some(ref factory) => factory(),
+        };
+        match self.credentials {
+            None => options,
+            Some(ref credentials) => options.credentials(
+                credentials.username.as_str(), credentials.password.as_str()
+            ),
+        }
+    }
+
+    /// Options kept for `reconnect`, which always connects.
+    pub(crate) fn for_reconnect(&self) -> Arc<MBeanThreadedClientOptions> {
+        Arc::new(self.clone().skip_connect(false))
+    }
 }
 
 impl<'a> From<MBeanThreadedClientOptions> for MBeanClientOptions<'a> {
-    fn from(_: MBeanThreadedClientOptions) -> MBeanClientOptions<'a> {
-        MBeanClientOptions::default()
+    /// Build the `MBeanClient` options on the calling thread, credentials included.
+    fn from(options: MBeanThreadedClientOptions) -> MBeanClientOptions<'a> {
+        options.build_client_options()
     }
 }
 
 impl Default for MBeanThreadedClientOptions {
     fn default() -> Self {
         MBeanThreadedClientOptions {
+            client_options: None,
+            credentials: None,
+            dispatch: WorkerDispatch::RoundRobin,
             reqs_buffer: None,
+            runtime: None,
             skip_connect: false,
+            workers: 1,
         }
     }
 }
@@ -0,0 +1,528 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use failure::ResultExt;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::ListenerId;
+use super::MBeanClientTrait;
+use super::MBeanInfo;
+use super::Notification;
+use super::ObjectName;
+use super::Result;
+
+use super::registry::MBEAN_SERVER_DELEGATE_NAME;
+use super::registry::REGISTRATION_NOTIFICATION;
+use super::registry::UNREGISTRATION_NOTIFICATION;
+
+
+/// Closure used to compute attribute values on demand.
+type AttributeFn = Arc<dyn Fn() -> Result<Value> + Send + Sync>;
+
+/// Closure used to respond to operation invocations.
+type OperationFn = Arc<dyn Fn(Vec<Value>) -> Result<Value> + Send + Sync>;
+
+
+/// Value of a mock MBean attribute.
+#[derive(Clone)]
+enum MockAttribute {
+    /// Return an error when the attribute is requested.
+    Error(String),
+
+    /// Compute the value of the attribute when it is requested.
+    Function(AttributeFn),
+
+    /// Return a fixed value when the attribute is requested.
+    Value(Value),
+}
+
+
+/// An MBean registered with the mock server.
+struct MockMBean {
+    attributes: HashMap<String, MockAttribute>,
+    info: MBeanInfo,
+    info_error: Option<String>,
+    name: ObjectName,
+    operations: HashMap<String, OperationFn>,
+}
+
+impl MockMBean {
+    fn new(name: ObjectName, info: MBeanInfo) -> MockMBean {
+        MockMBean {
+            attributes: HashMap::new(),
+            info,
+            info_error: None,
+            name,
+            operations: HashMap::new(),
+        }
+    }
+}
+
+
+/// A notification listener added to the mock server.
+struct MockListener {
+    names: Vec<ObjectName>,
+    queue: VecDeque<Notification>,
+    types: Vec<String>,
+}
+
+impl MockListener {
+    /// Check if the listener receives a notification, like `NotificationFilterSupport`.
+    fn accepts(&self, notification: &Notification) -> bool {
+        let source = match ObjectName::parse(notification.source.as_str()) {
+            Err(_) => return false,
+            Ok(source) => source,
+        };
+        let types = self.types.is_empty() || self.types.iter()
+            .any(|prefix| notification.notification_type.starts_with(prefix.as_str()));
+        types && self.names.contains(&source)
+    }
+}
+
+
+/// State of the mock server, shared by all clones of the client.
+#[derive(Default)]
+struct MockState {
+    beans: BTreeMap<ObjectName, MockMBean>,
+    latency: Option<Duration>,
+    listeners: HashMap<ListenerId, MockListener>,
+    next_listener: u64,
+    next_sequence: i64,
+    notified: Arc<Condvar>,
+    query_error: Option<String>,
+}
+
+impl MockState {
+    /// Access a registered MBean, creating it with an empty `MBeanInfo` if needed.
+    fn bean_or_default(&mut self, name: ObjectName) -> &mut MockMBean {
+        if !self.beans.contains_key(&name) {
+            let bean = MockMBean::new(name.clone(), MBeanInfo::default());
+            self.beans.insert(name.clone(), bean);
+            self.delegate_notification(REGISTRATION_NOTIFICATION, &name);
+        }
+        self.beans.get_mut(&name).expect("the mbean was registered above")
+    }
+
+    /// Emit a registration notification from the `MBeanServerDelegate`.
+    fn delegate_notification(&mut self, notification_type: &str, name: &ObjectName) {
+        self.next_sequence += 1;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as i64)
+            .unwrap_or(0);
+        self.deliver(Notification {
+            mbean_name: Some(name.to_string()),
+            notification_type: notification_type.to_string(),
+            sequence_number: self.next_sequence,
+            source: MBEAN_SERVER_DELEGATE_NAME.to_string(),
+            timestamp,
+            ..Default::default()
+        });
+    }
+
+    /// Queue a notification for the listeners that accept it and wake up polls.
+    fn deliver(&mut self, notification: Notification) -> usize {
+        let mut delivered = 0;
+        for listener in self.listeners.values_mut() {
+            if listener.accepts(&notification) {
+                listener.queue.push_back(notification.clone());
+                delivered += 1;
+            }
+        }
+        self.notified.notify_all();
+        delivered
+    }
+}
+
+
+/// In-memory MBean server implementing `MBeanClientTrait`.
+///
+/// Allows code that consumes `MBeanClientTrait` to be tested without a JVM:
+///
+///   * MBeans are registered with an `MBeanInfo`, attribute values or closures
+///     and operation handlers.
+///   * `query_names` matches registered names against `ObjectName` patterns.
+///   * Errors and latency can be injected to exercise failure paths.
+///
+///   * Notifications are emitted on demand to the listeners of their source.
+///     Once registered, the `MBeanServerDelegate` also emits a notification
+///     every time an MBean is registered or unregistered.
+///
+/// All methods take `&self` so the server can be changed while it is shared across threads.
+#[derive(Clone, Default)]
+pub struct MockMBeanClient {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockMBeanClient {
+    /// Create an empty mock server.
+    pub fn new() -> MockMBeanClient {
+        MockMBeanClient::default()
+    }
+
+    /// Remove all injected failures.
+    pub fn clear_failures(&self) {
+        let mut state = self.state();
+        state.query_error = None;
+        for bean in state.beans.values_mut() {
+            bean.info_error = None;
+            bean.attributes.retain(|_, attribute| !matches!(*attribute, MockAttribute::Error(_)));
+        }
+    }
+
+    /// Queue a notification for the listeners of its source and type.
+    ///
+    /// Returns the number of listeners the notification was delivered to.
+    pub fn emit_notification(&self, notification: Notification) -> usize {
+        self.state().deliver(notification)
+    }
+
+    /// Make requests for the given attribute fail with the given message.
+    ///
+    /// The failure replaces any value registered for the attribute.
+    pub fn fail_attribute<S1, S2, S3>(&self, mbean: S1, attribute: S2, message: S3) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+              S3: Into<String>,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        state.bean_or_default(name).attributes
+            .insert(attribute.into(), MockAttribute::Error(message.into()));
+        Ok(())
+    }
+
+    /// Make `get_mbean_info` requests for the given MBean fail with the given message.
+    pub fn fail_mbean_info<S1, S2>(&self, mbean: S1, message: S2) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        state.bean_or_default(name).info_error = Some(message.into());
+        Ok(())
+    }
+
+    /// Make all `query_names` requests fail with the given message.
+    pub fn fail_query_names<S>(&self, message: S)
+        where S: Into<String>,
+    {
+        self.state().query_error = Some(message.into());
+    }
+
+    /// Delay every request by the given amount of time.
+    ///
+    /// Pass `None` to remove the delay.
+    pub fn latency(&self, latency: Option<Duration>) {
+        self.state().latency = latency;
+    }
+
+    /// Register a fixed attribute value.
+    ///
+    /// The MBean is registered with an empty `MBeanInfo` if it does not exist.
+    pub fn register_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        let name = registered_name(mbean)?;
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let mut state = self.state();
+        state.bean_or_default(name).attributes
+            .insert(attribute.into(), MockAttribute::Value(value));
+        Ok(())
+    }
+
+    /// Register a closure to compute the attribute value every time it is requested.
+    ///
+    /// The MBean is registered with an empty `MBeanInfo` if it does not exist.
+    pub fn register_attribute_fn<S1, S2, F>(&self, mbean: S1, attribute: S2, value: F) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+              F: Fn() -> Result<Value> + Send + Sync + 'static,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        state.bean_or_default(name).attributes
+            .insert(attribute.into(), MockAttribute::Function(Arc::new(value)));
+        Ok(())
+    }
+
+    /// Register a closure invoked with the parameters of every call to an operation.
+    ///
+    /// The MBean is registered with an empty `MBeanInfo` if it does not exist.
+    pub fn register_operation<S1, S2, F>(&self, mbean: S1, operation: S2, handler: F) -> Result<()>
+        where S1: AsRef<str>,
+              S2: Into<String>,
+              F: Fn(Vec<Value>) -> Result<Value> + Send + Sync + 'static,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        state.bean_or_default(name).operations.insert(operation.into(), Arc::new(handler));
+        Ok(())
+    }
+
+    /// Register an MBean with the given name and information.
+    ///
+    /// If the MBean is already registered its information is replaced
+    /// but attribute values and failures are kept.
+    pub fn register_mbean<S>(&self, mbean: S, info: MBeanInfo) -> Result<()>
+        where S: AsRef<str>,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        state.bean_or_default(name).info = info;
+        Ok(())
+    }
+
+    /// Remove an MBean and all its attributes from the server.
+    pub fn unregister_mbean<S>(&self, mbean: S) -> Result<()>
+        where S: AsRef<str>,
+    {
+        let name = registered_name(mbean)?;
+        let mut state = self.state();
+        if state.beans.remove(&name).is_some() {
+            state.delegate_notification(UNREGISTRATION_NOTIFICATION, &name);
+        }
+        Ok(())
+    }
+}
+
+impl MockMBeanClient {
+    /// Lock the server state, ignoring poisoning caused by panicking closures.
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Sleep for the configured latency, if any.
+    fn wait(&self) {
+        let latency = self.state().latency;
+        if let Some(latency) = latency {
+            thread::sleep(latency);
+        }
+    }
+}
+
+impl MBeanClientTrait for MockMBeanClient {
+    /// Listen to notifications emitted with `emit_notification`.
+    ///
+    /// All registered MBeans can emit notifications.
+    fn add_notification_listener<S>(&self, mbean: S, types: Vec<String>) -> Result<ListenerId>
+        where S: Into<String>,
+    {
+        self.wait();
+        let mbean = mbean.into();
+        let pattern = ObjectName::parse(&mbean)?;
+        let mut state = self.state();
+        let names: Vec<ObjectName> = state.beans.keys()
+            .filter(|name| pattern.matches(name))
+            .cloned()
+            .collect();
+        if names.is_empty() {
+            return Err(ErrorKind::MockFailure(format!("mbean '{}' not found", mbean)).into());
+        }
+        state.next_listener += 1;
+        let id = ListenerId(state.next_listener);
+        state.listeners.insert(id, MockListener {
+            names,
+            queue: VecDeque::new(),
+            types,
+        });
+        Ok(id)
+    }
+
+    /// The mock never reconnects, the identifier never changes.
+    fn connection_id(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.wait();
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let name = ObjectName::parse(&mbean)?;
+        // Release the lock before calling closures in case they use the client.
+        let value = {
+            let state = self.state();
+            let bean = state.beans.get(&name).ok_or_else(
+                || ErrorKind::MockFailure(format!("mbean '{}' not found", mbean))
+            )?;
+            bean.attributes.get(&attribute).cloned().ok_or_else(|| ErrorKind::MockFailure(
+                format!("attribute '{}' not found in mbean '{}'", attribute, mbean)
+            ))?
+        };
+        let value = match value {
+            MockAttribute::Error(message) => Err(ErrorKind::MockFailure(message).into()),
+            MockAttribute::Function(value) => value(),
+            MockAttribute::Value(value) => Ok(value),
+        }?;
+        let value: T = serde_json::from_value(value).with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(value)
+    }
+
+    fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        self.wait();
+        let mbean = mbean.into();
+        let name = ObjectName::parse(&mbean)?;
+        let state = self.state();
+        let bean = state.beans.get(&name).ok_or_else(
+            || ErrorKind::MockFailure(format!("mbean '{}' not found", mbean))
+        )?;
+        match bean.info_error {
+            Some(ref message) => Err(ErrorKind::MockFailure(message.clone()).into()),
+            None => Ok(bean.info.clone()),
+        }
+    }
+
+    fn invoke<S1, S2, T>(&self, mbean: S1, operation: S2, params: Vec<Value>) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.wait();
+        let mbean = mbean.into();
+        let operation = operation.into();
+        let name = ObjectName::parse(&mbean)?;
+        // Release the lock before calling the handler in case it uses the client.
+        let handler = {
+            let state = self.state();
+            let bean = state.beans.get(&name).ok_or_else(
+                || ErrorKind::MockFailure(format!("mbean '{}' not found", mbean))
+            )?;
+            bean.operations.get(&operation).cloned().ok_or_else(|| ErrorKind::MockFailure(
+                format!("operation '{}' not found in mbean '{}'", operation, mbean)
+            ))?
+        };
+        let value = handler(params)?;
+        let value: T = serde_json::from_value(value).with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(value)
+    }
+
+    fn poll_notifications(
+        &self, listener: ListenerId, timeout: Duration
+    ) -> Result<Vec<Notification>> {
+        self.wait();
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state();
+        loop {
+            let queue = match state.listeners.get_mut(&listener) {
+                None => {
+                    let message = format!("notification listener {} not found", listener.0);
+                    return Err(ErrorKind::MockFailure(message).into());
+                },
+                Some(listener) => &mut listener.queue,
+            };
+            let now = Instant::now();
+            if !queue.is_empty() || now >= deadline {
+                return Ok(queue.drain(..).collect());
+            }
+            let notified = Arc::clone(&state.notified);
+            state = match notified.wait_timeout(state, deadline - now) {
+                Ok((state, _)) => state,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.wait();
+        let name = ObjectName::parse(name.into())?;
+        let query = ObjectName::parse(query.into())?;
+        let state = self.state();
+        if let Some(ref message) = state.query_error {
+            return Err(ErrorKind::MockFailure(message.clone()).into());
+        }
+        let names = state.beans.values()
+            .filter(|bean| name.matches(&bean.name) && query.matches(&bean.name))
+            .map(|bean| bean.name.to_string())
+            .collect();
+        Ok(names)
+    }
+
+    fn remove_notification_listener(&self, listener: ListenerId) -> Result<()> {
+        self.wait();
+        match self.state().listeners.remove(&listener) {
+            None => {
+                let message = format!("notification listener {} not found", listener.0);
+                Err(ErrorKind::MockFailure(message).into())
+            },
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Replace the value of an attribute.
+    ///
+    /// Fails for attributes with an injected failure, attributes computed by closures
+    /// and attributes the `MBeanInfo` describes as not writable.
+    fn set_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2, value: T) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: Serialize,
+    {
+        self.wait();
+        let mbean = mbean.into();
+        let attribute = attribute.into();
+        let name = ObjectName::parse(&mbean)?;
+        let value = serde_json::to_value(value).with_context(|_| ErrorKind::ValueEncode)?;
+        let mut state = self.state();
+        let bean = state.beans.get_mut(&name).ok_or_else(
+            || ErrorKind::MockFailure(format!("mbean '{}' not found", mbean))
+        )?;
+        let read_only = bean.info.attributes.iter()
+            .any(|info| info.name == attribute && !info.is_writable);
+        if read_only {
+            let message = format!("attribute '{}' of mbean '{}' is not writable", attribute, mbean);
+            return Err(ErrorKind::MockFailure(message).into());
+        }
+        match bean.attributes.get(&attribute) {
+            Some(MockAttribute::Error(message)) => {
+                Err(ErrorKind::MockFailure(message.clone()).into())
+            },
+            Some(MockAttribute::Function(_)) => {
+                let message = format!("attribute '{}' of mbean '{}' is computed", attribute, mbean);
+                Err(ErrorKind::MockFailure(message).into())
+            },
+            _ => {
+                bean.attributes.insert(attribute, MockAttribute::Value(value));
+                Ok(())
+            },
+        }
+    }
+}
+
+
+/// Parse the name of an MBean to register, rejecting patterns.
+fn registered_name<S>(mbean: S) -> Result<ObjectName>
+    where S: AsRef<str>,
+{
+    let name = ObjectName::parse(mbean)?;
+    if name.is_pattern() {
+        return Err(ErrorKind::InvalidObjectName(name.to_string()).into());
+    }
+    Ok(name)
+}
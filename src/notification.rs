@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use serde_json::Value;
+
+use super::MBeanClientTrait;
+use super::Result;
+
+
+/// Identifier of a notification listener added with `add_notification_listener`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct ListenerId(pub u64);
+
+
+/// Notification emitted by an MBean.
+///
+/// Rust version of `javax.management.Notification`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Name of the MBean registered or unregistered, for `MBeanServerNotification`s.
+    #[serde(default)]
+    pub mbean_name: Option<String>,
+    pub message: Option<String>,
+    pub sequence_number: i64,
+
+    /// Name of the MBean that emitted the notification.
+    pub source: String,
+
+    /// Time the notification was emitted, in milliseconds since the epoch.
+    pub timestamp: i64,
+
+    /// Type of the notification, in dot notation.
+    #[serde(rename = "type")]
+    pub notification_type: String,
+
+    /// User data converted like attribute values, `Value::Null` if there is none.
+    #[serde(default)]
+    pub user_data: Value,
+}
+
+
+/// Notification listener removed from the server when dropped.
+pub struct NotificationListener<'a, C>
+    where C: MBeanClientTrait + 'a,
+{
+    client: &'a C,
+    id: ListenerId,
+}
+
+impl<'a, C> NotificationListener<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Listen to notifications of the given types emitted by MBeans matching a name or pattern.
+    ///
+    /// See `MBeanClientTrait::add_notification_listener` for details.
+    pub fn new<S>(
+        client: &'a C, mbean: S, types: Vec<String>
+    ) -> Result<NotificationListener<'a, C>>
+        where S: Into<String>,
+    {
+        let id = client.add_notification_listener(mbean, types)?;
+        Ok(NotificationListener { client, id })
+    }
+
+    pub fn id(&self) -> ListenerId {
+        self.id
+    }
+
+    /// Wait up to `timeout` for notifications and return all those received so far.
+    pub fn poll(&self, timeout: Duration) -> Result<Vec<Notification>> {
+        self.client.poll_notifications(self.id, timeout)
+    }
+}
+
+impl<'a, C> Drop for NotificationListener<'a, C>
+    where C: MBeanClientTrait,
+{
+    fn drop(&mut self) {
+        let _ = self.client.remove_notification_listener(self.id);
+    }
+}
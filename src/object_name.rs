@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+
+use super::Error;
+use super::ErrorKind;
+use super::Result;
+
+
+/// Rust version of a `javax.management.ObjectName`.
+///
+/// Names are parsed in the `domain:key=value[,key=value]*` format and can be patterns:
+///
+///   * The domain can contain the `*` and `?` wildcards.
+///   * The property list can end with `*` to match names with additional properties.
+///   * Unquoted property values can contain the `*` and `?` wildcards.
+///
+/// Equality, ordering and hashing are based on the canonical name of the object.
+#[derive(Clone, Debug)]
+pub struct ObjectName {
+    /// Computed once by `parse` as all comparisons use it.
+    canonical: String,
+    domain: String,
+    properties: Vec<(String, String)>,
+    property_list_pattern: bool,
+}
+
+impl ObjectName {
+    /// Parse an object name or object name pattern.
+    ///
+    /// As with the Java class, the empty string is the same as the `*:*` pattern.
+    pub fn parse<S>(name: S) -> Result<ObjectName>
+        where S: AsRef<str>,
+    {
+        let name = name.as_ref();
+        if name.is_empty() {
+            return Ok(ObjectName::new("*".into(), Vec::new(), true));
+        }
+        let invalid = || -> Error { ErrorKind::InvalidObjectName(name.to_string()).into() };
+        let colon = name.find(':').ok_or_else(invalid)?;
+        let domain = &name[..colon];
+        if domain.contains('\n') {
+            return Err(invalid());
+        }
+        let mut properties: Vec<(String, String)> = Vec::new();
+        let mut property_list_pattern = false;
+        let mut rest = &name[colon + 1..];
+        while !rest.is_empty() {
+            if rest == "*" || rest.starts_with("*,") {
+                if property_list_pattern {
+                    return Err(invalid());
+                }
+                property_list_pattern = true;
+                rest = &rest[1..];
+            } else {
+                let equals = rest.find('=').ok_or_else(invalid)?;
+                let key = &rest[..equals];
+                if key.is_empty() || key.contains(|c| ":,=*?\n\"".contains(c)) {
+                    return Err(invalid());
+                }
+                if properties.iter().any(|(k, _)| k == key) {
+                    return Err(invalid());
+                }
+                rest = &rest[equals + 1..];
+                let value_len = if rest.starts_with('"') {
+                    ObjectName::quoted_len(rest).ok_or_else(invalid)?
+                } else {
+                    let len = rest.find(',').unwrap_or(rest.len());
+                    if len == 0 || rest[..len].contains(|c| ":=\n\"".contains(c)) {
+                        return Err(invalid());
+                    }
+                    len
+                };
+                properties.push((key.to_string(), rest[..value_len].to_string()));
+                rest = &rest[value_len..];
+            }
+            if rest.starts_with(',') {
+                rest = &rest[1..];
+                if rest.is_empty() {
+                    return Err(invalid());
+                }
+            } else if !rest.is_empty() {
+                return Err(invalid());
+            }
+        }
+        if properties.is_empty() && !property_list_pattern {
+            return Err(invalid());
+        }
+        Ok(ObjectName::new(domain.to_string(), properties, property_list_pattern))
+    }
+
+    /// Canonical string representation of the name, with properties sorted by key.
+    pub fn canonical_name(&self) -> String {
+        self.canonical.clone()
+    }
+
+    /// The domain part of the name.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Check if this name is a pattern of any kind.
+    pub fn is_pattern(&self) -> bool {
+        self.is_domain_pattern() || self.property_list_pattern || self.is_property_value_pattern()
+    }
+
+    /// Check if the domain part of the name contains wildcards.
+    pub fn is_domain_pattern(&self) -> bool {
+        self.domain.contains(['*', '?'])
+    }
+
+    /// Check if the property list ends with the `*` wildcard.
+    pub fn is_property_list_pattern(&self) -> bool {
+        self.property_list_pattern
+    }
+
+    /// Check if any unquoted property value contains wildcards.
+    pub fn is_property_value_pattern(&self) -> bool {
+        self.properties.iter().any(|(_, value)| ObjectName::is_value_pattern(value))
+    }
+
+    /// Look up the value of a key property.
+    ///
+    /// Quoted values are returned with the quotes, as the Java class does.
+    pub fn key_property(&self, key: &str) -> Option<&str> {
+        self.properties.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Check if the given name matches this name or name pattern.
+    ///
+    /// Follows the semantics of `javax.management.ObjectName::apply`:
+    /// the given name must not be a pattern itself.
+    pub fn matches(&self, name: &ObjectName) -> bool {
+        if name.is_pattern() {
+            return false;
+        }
+        if !glob_match(&self.domain, &name.domain) {
+            return false;
+        }
+        if !self.property_list_pattern && self.properties.len() != name.properties.len() {
+            return false;
+        }
+        self.properties.iter().all(|(key, pattern)| {
+            match name.key_property(key) {
+                None => false,
+                Some(value) if ObjectName::is_value_pattern(pattern) => glob_match(pattern, value),
+                Some(value) => pattern == value,
+            }
+        })
+    }
+
+    /// Key properties in the order they appear in the name.
+    pub fn properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+}
+
+impl ObjectName {
+    fn new(
+        domain: String, properties: Vec<(String, String)>, property_list_pattern: bool
+    ) -> ObjectName {
+        let mut sorted: Vec<&(String, String)> = properties.iter().collect();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut list: Vec<String> = sorted.into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        if property_list_pattern {
+            list.push("*".into());
+        }
+        ObjectName {
+            canonical: format!("{}:{}", domain, list.join(",")),
+            domain,
+            properties,
+            property_list_pattern,
+        }
+    }
+
+    /// Length of the quoted value at the start of `value`, quotes included.
+    fn quoted_len(value: &str) -> Option<usize> {
+        let mut escaped = false;
+        for (idx, c) in value.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => return Some(idx + 1),
+                '\n' => return None,
+                _ => (),
+            }
+        }
+        None
+    }
+
+    fn is_value_pattern(value: &str) -> bool {
+        !value.starts_with('"') && value.contains(['*', '?'])
+    }
+}
+
+impl fmt::Display for ObjectName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list: Vec<String> = self.properties.iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        if self.property_list_pattern {
+            list.push("*".into());
+        }
+        write!(f, "{}:{}", self.domain, list.join(","))
+    }
+}
+
+impl FromStr for ObjectName {
+    type Err = Error;
+    fn from_str(name: &str) -> Result<ObjectName> {
+        ObjectName::parse(name)
+    }
+}
+
+impl PartialEq for ObjectName {
+    fn eq(&self, other: &ObjectName) -> bool {
+        self.canonical == other.canonical
+    }
+}
+
+impl Eq for ObjectName {}
+
+impl Hash for ObjectName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical.hash(state);
+    }
+}
+
+impl Ord for ObjectName {
+    fn cmp(&self, other: &ObjectName) -> Ordering {
+        self.canonical.cmp(&other.canonical)
+    }
+}
+
+impl PartialOrd for ObjectName {
+    fn partial_cmp(&self, other: &ObjectName) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+
+/// Match a string against a pattern with `*` and `?` wildcards.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            v = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
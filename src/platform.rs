@@ -0,0 +1,1114 @@
+//! Typed proxies for the platform MXBeans of a remote JVM.
+//!
+//! Each proxy reads attributes and invokes operations of an MXBean registered by
+//! `java.lang.management.ManagementFactory` through any `MBeanClientTrait` client
+//! and decodes the results into Rust types.
+//!
+//! Methods marked as extensions use the `com.sun.management` interfaces implemented by
+//! HotSpot based JVMs: they fail with other JVMs that do not expose the attributes.
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use failure::ResultExt;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+
+use super::ErrorKind;
+use super::MBeanClientTrait;
+use super::Notification;
+use super::NotificationListener;
+use super::Result;
+use super::thread_dump::ThreadInfo;
+
+
+/// Name of the `BufferPoolMXBean`s, as a pattern.
+pub const BUFFER_POOL_MXBEAN_PATTERN: &str = "java.nio:type=BufferPool,name=*";
+
+/// Name of the `ClassLoadingMXBean`.
+pub const CLASS_LOADING_MXBEAN_NAME: &str = "java.lang:type=ClassLoading";
+
+/// Name of the `CompilationMXBean`.
+pub const COMPILATION_MXBEAN_NAME: &str = "java.lang:type=Compilation";
+
+/// Type of the notifications emitted by garbage collectors after each collection (extension).
+pub const GC_NOTIFICATION: &str = "com.sun.management.gc.notification";
+
+/// Name of the `GarbageCollectorMXBean`s, as a pattern.
+pub const GARBAGE_COLLECTOR_MXBEAN_PATTERN: &str = "java.lang:type=GarbageCollector,name=*";
+
+/// Name of the `HotSpotDiagnosticMXBean`.
+pub const HOTSPOT_DIAGNOSTIC_MXBEAN_NAME: &str = "com.sun.management:type=HotSpotDiagnostic";
+
+/// Name of the `MemoryMXBean`.
+pub const MEMORY_MXBEAN_NAME: &str = "java.lang:type=Memory";
+
+/// Type of the notifications emitted when a pool usage crosses its usage threshold.
+pub const MEMORY_THRESHOLD_EXCEEDED: &str = "java.management.memory.threshold.exceeded";
+
+/// Type of the notifications emitted when a pool usage after a collection
+/// crosses its collection usage threshold.
+pub const MEMORY_COLLECTION_THRESHOLD_EXCEEDED: &str =
+    "java.management.memory.collection.threshold.exceeded";
+
+/// Name of the `MemoryPoolMXBean`s, as a pattern.
+pub const MEMORY_POOL_MXBEAN_PATTERN: &str = "java.lang:type=MemoryPool,name=*";
+
+/// Name of the `OperatingSystemMXBean`.
+pub const OPERATING_SYSTEM_MXBEAN_NAME: &str = "java.lang:type=OperatingSystem";
+
+/// Name of the `RuntimeMXBean`.
+pub const RUNTIME_MXBEAN_NAME: &str = "java.lang:type=Runtime";
+
+/// Name of the `ThreadMXBean`.
+pub const THREAD_MXBEAN_NAME: &str = "java.lang:type=Threading";
+
+
+/// Garbage collection, from `com.sun.management.GarbageCollectionNotificationInfo`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcEvent {
+    /// Action performed by the collector, such as `end of minor GC`.
+    #[serde(rename = "gcAction")]
+    pub action: String,
+
+    /// Cause of the collection, such as `Allocation Failure` or `System.gc()`.
+    #[serde(rename = "gcCause")]
+    pub cause: String,
+
+    /// Name of the garbage collector that did the collection.
+    pub gc_name: String,
+
+    /// Duration, times and memory usage of each pool before and after the collection.
+    #[serde(rename = "gcInfo")]
+    pub info: GcInfo,
+}
+
+impl GcEvent {
+    /// Decode a garbage collection notification.
+    ///
+    /// Returns `None` for notifications of other types.
+    pub fn from_notification(notification: &Notification) -> Result<Option<GcEvent>> {
+        if notification.notification_type != GC_NOTIFICATION {
+            return Ok(None);
+        }
+        let event = serde_json::from_value(notification.user_data.clone())
+            .with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(Some(event))
+    }
+}
+
+
+/// Information about a garbage collection, from `com.sun.management.GcInfo`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcInfo {
+    /// Elapsed time of the collection, in milliseconds.
+    pub duration: i64,
+
+    /// End time of the collection, in milliseconds since the JVM started.
+    pub end_time: i64,
+
+    /// Number of collections done by the collector, including this one.
+    pub id: i64,
+
+    /// Usage of each memory pool after the collection.
+    #[serde(deserialize_with = "table")]
+    pub memory_usage_after_gc: BTreeMap<String, MemoryUsage>,
+
+    /// Usage of each memory pool before the collection.
+    #[serde(deserialize_with = "table")]
+    pub memory_usage_before_gc: BTreeMap<String, MemoryUsage>,
+
+    /// Start time of the collection, in milliseconds since the JVM started.
+    pub start_time: i64,
+}
+
+
+/// Memory pool threshold crossed by a `MemoryThresholdEvent`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MemoryThresholdKind {
+    /// The usage after a collection crossed the collection usage threshold.
+    CollectionUsage,
+
+    /// The usage crossed the usage threshold.
+    Usage,
+}
+
+
+/// Memory pool threshold crossed, from a `java.lang.management.MemoryNotificationInfo`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MemoryThresholdEvent {
+    /// Number of times the threshold was crossed, including this one.
+    pub count: i64,
+    pub kind: MemoryThresholdKind,
+    pub pool_name: String,
+
+    /// Time the notification was emitted, in milliseconds since the epoch.
+    pub timestamp: i64,
+
+    /// Usage of the pool when the threshold was crossed.
+    pub usage: MemoryUsage,
+}
+
+impl MemoryThresholdEvent {
+    /// Decode a memory threshold notification.
+    ///
+    /// Returns `None` for notifications of other types.
+    pub fn from_notification(notification: &Notification) -> Result<Option<MemoryThresholdEvent>> {
+        let kind = match notification.notification_type.as_str() {
+            MEMORY_THRESHOLD_EXCEEDED => MemoryThresholdKind::Usage,
+            MEMORY_COLLECTION_THRESHOLD_EXCEEDED => MemoryThresholdKind::CollectionUsage,
+            _ => return Ok(None),
+        };
+        let info: MemoryNotificationInfo = serde_json::from_value(notification.user_data.clone())
+            .with_context(|_| ErrorKind::ValueDecode)?;
+        Ok(Some(MemoryThresholdEvent {
+            count: info.count,
+            kind,
+            pool_name: info.pool_name,
+            timestamp: notification.timestamp,
+            usage: info.usage,
+        }))
+    }
+}
+
+
+/// User data of memory threshold notifications.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryNotificationInfo {
+    count: i64,
+    pool_name: String,
+    usage: MemoryUsage,
+}
+
+
+/// Kind of memory managed by a memory pool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MemoryType {
+    Heap,
+    NonHeap,
+}
+
+
+/// Snapshot of memory usage, from `java.lang.management.MemoryUsage`.
+///
+/// All values are in bytes, `init` and `max` are `-1` if undefined.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub committed: i64,
+    pub init: i64,
+    pub max: i64,
+    pub used: i64,
+}
+
+
+/// Value of a HotSpot VM option, from `com.sun.management.VMOption`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VMOption {
+    pub name: String,
+    pub origin: VMOptionOrigin,
+
+    /// Value of the option, formatted as a string.
+    pub value: String,
+
+    /// The option can be set with `HotSpotDiagnosticMXBean::set_vm_option`.
+    pub writeable: bool,
+}
+
+
+/// Source of the value of a VM option, from `com.sun.management.VMOption.Origin`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VMOptionOrigin {
+    AttachOnDemand,
+    ConfigFile,
+    Default,
+    EnvironVar,
+    Ergonomic,
+    Management,
+    Other,
+    VmCreation,
+}
+
+
+/// Entry point to the platform MXBeans of a JVM.
+pub struct Platform<'a, C: 'a> {
+    client: &'a C,
+}
+
+impl<'a, C> Platform<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Access the platform MXBeans through the given client.
+    pub fn new(client: &'a C) -> Platform<'a, C> {
+        Platform { client }
+    }
+
+    /// All `BufferPoolMXBean`s registered with the server, sorted by name.
+    pub fn buffer_pools(&self) -> Result<Vec<BufferPoolMXBean<'a, C>>> {
+        let names = self.names(BUFFER_POOL_MXBEAN_PATTERN)?;
+        Ok(names.into_iter().map(|name| BufferPoolMXBean::new(self.client, name)).collect())
+    }
+
+    pub fn class_loading(&self) -> ClassLoadingMXBean<'a, C> {
+        ClassLoadingMXBean::new(self.client)
+    }
+
+    pub fn compilation(&self) -> CompilationMXBean<'a, C> {
+        CompilationMXBean::new(self.client)
+    }
+
+    /// All `GarbageCollectorMXBean`s registered with the server, sorted by name.
+    pub fn garbage_collectors(&self) -> Result<Vec<GarbageCollectorMXBean<'a, C>>> {
+        let names = self.names(GARBAGE_COLLECTOR_MXBEAN_PATTERN)?;
+        Ok(names.into_iter().map(|name| GarbageCollectorMXBean::new(self.client, name)).collect())
+    }
+
+    /// Listen to the collections of all garbage collectors (extension).
+    pub fn gc_listener(&self) -> Result<GcEventListener<'a, C>> {
+        let types = vec![GC_NOTIFICATION.to_string()];
+        let listener = NotificationListener::new(
+            self.client, GARBAGE_COLLECTOR_MXBEAN_PATTERN, types
+        )?;
+        Ok(GcEventListener { listener })
+    }
+
+    pub fn hotspot_diagnostic(&self) -> HotSpotDiagnosticMXBean<'a, C> {
+        HotSpotDiagnosticMXBean::new(self.client)
+    }
+
+    pub fn memory(&self) -> MemoryMXBean<'a, C> {
+        MemoryMXBean::new(self.client)
+    }
+
+    /// All `MemoryPoolMXBean`s registered with the server, sorted by name.
+    pub fn memory_pools(&self) -> Result<Vec<MemoryPoolMXBean<'a, C>>> {
+        let names = self.names(MEMORY_POOL_MXBEAN_PATTERN)?;
+        Ok(names.into_iter().map(|name| MemoryPoolMXBean::new(self.client, name)).collect())
+    }
+
+    pub fn operating_system(&self) -> OperatingSystemMXBean<'a, C> {
+        OperatingSystemMXBean::new(self.client)
+    }
+
+    pub fn runtime(&self) -> RuntimeMXBean<'a, C> {
+        RuntimeMXBean::new(self.client)
+    }
+
+    pub fn threading(&self) -> ThreadMXBean<'a, C> {
+        ThreadMXBean::new(self.client)
+    }
+}
+
+impl<'a, C> Platform<'a, C>
+    where C: MBeanClientTrait,
+{
+    fn names(&self, pattern: &str) -> Result<Vec<String>> {
+        let mut names = self.client.query_names(pattern, "")?;
+        names.sort();
+        Ok(names)
+    }
+}
+
+
+/// Attribute and operation access shared by all proxies.
+struct Proxy<'a, C: 'a> {
+    client: &'a C,
+    name: String,
+}
+
+impl<'a, C> Proxy<'a, C>
+    where C: MBeanClientTrait,
+{
+    fn new<S: Into<String>>(client: &'a C, name: S) -> Proxy<'a, C> {
+        Proxy {
+            client,
+            name: name.into(),
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, attribute: &str) -> Result<T> {
+        self.client.get_attribute(self.name.as_str(), attribute)
+    }
+
+    fn invoke<T: DeserializeOwned>(&self, operation: &str, params: Vec<Value>) -> Result<T> {
+        self.client.invoke(self.name.as_str(), operation, params)
+    }
+
+    fn run(&self, operation: &str) -> Result<()> {
+        let _: Value = self.invoke(operation, Vec::new())?;
+        Ok(())
+    }
+
+    fn set(&self, attribute: &str, value: Value) -> Result<()> {
+        self.client.set_attribute(self.name.as_str(), attribute, value)
+    }
+}
+
+
+/// Proxy for a `java.lang.management.BufferPoolMXBean`.
+pub struct BufferPoolMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> BufferPoolMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Proxy the buffer pool MXBean with the given ObjectName.
+    pub fn new<S: Into<String>>(client: &'a C, name: S) -> BufferPoolMXBean<'a, C> {
+        BufferPoolMXBean { proxy: Proxy::new(client, name) }
+    }
+
+    /// Number of buffers in the pool.
+    pub fn count(&self) -> Result<i64> {
+        self.proxy.get("Count")
+    }
+
+    /// Memory used by the pool, in bytes.
+    pub fn memory_used(&self) -> Result<i64> {
+        self.proxy.get("MemoryUsed")
+    }
+
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Total capacity of the buffers in the pool, in bytes.
+    pub fn total_capacity(&self) -> Result<i64> {
+        self.proxy.get("TotalCapacity")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.ClassLoadingMXBean`.
+pub struct ClassLoadingMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> ClassLoadingMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> ClassLoadingMXBean<'a, C> {
+        ClassLoadingMXBean { proxy: Proxy::new(client, CLASS_LOADING_MXBEAN_NAME) }
+    }
+
+    pub fn is_verbose(&self) -> Result<bool> {
+        self.proxy.get("Verbose")
+    }
+
+    /// Number of classes currently loaded.
+    pub fn loaded_class_count(&self) -> Result<i32> {
+        self.proxy.get("LoadedClassCount")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    pub fn set_verbose(&self, verbose: bool) -> Result<()> {
+        self.proxy.set("Verbose", Value::from(verbose))
+    }
+
+    /// Number of classes loaded since the JVM started.
+    pub fn total_loaded_class_count(&self) -> Result<i64> {
+        self.proxy.get("TotalLoadedClassCount")
+    }
+
+    /// Number of classes unloaded since the JVM started.
+    pub fn unloaded_class_count(&self) -> Result<i64> {
+        self.proxy.get("UnloadedClassCount")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.CompilationMXBean`.
+pub struct CompilationMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> CompilationMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> CompilationMXBean<'a, C> {
+        CompilationMXBean { proxy: Proxy::new(client, COMPILATION_MXBEAN_NAME) }
+    }
+
+    pub fn is_compilation_time_monitoring_supported(&self) -> Result<bool> {
+        self.proxy.get("CompilationTimeMonitoringSupported")
+    }
+
+    /// Name of the just-in-time compiler.
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Time spent compiling, in milliseconds.
+    pub fn total_compilation_time(&self) -> Result<i64> {
+        self.proxy.get("TotalCompilationTime")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.GarbageCollectorMXBean`.
+pub struct GarbageCollectorMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> GarbageCollectorMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Proxy the garbage collector MXBean with the given ObjectName.
+    pub fn new<S: Into<String>>(client: &'a C, name: S) -> GarbageCollectorMXBean<'a, C> {
+        GarbageCollectorMXBean { proxy: Proxy::new(client, name) }
+    }
+
+    /// Number of collections since the JVM started.
+    pub fn collection_count(&self) -> Result<i64> {
+        self.proxy.get("CollectionCount")
+    }
+
+    /// Time spent collecting since the JVM started, in milliseconds.
+    pub fn collection_time(&self) -> Result<i64> {
+        self.proxy.get("CollectionTime")
+    }
+
+    pub fn is_valid(&self) -> Result<bool> {
+        self.proxy.get("Valid")
+    }
+
+    /// Information about the last collection, `None` if there was none (extension).
+    pub fn last_gc_info(&self) -> Result<Option<GcInfo>> {
+        self.proxy.get("LastGcInfo")
+    }
+
+    /// Names of the memory pools managed by the collector.
+    pub fn memory_pool_names(&self) -> Result<Vec<String>> {
+        self.proxy.get("MemoryPoolNames")
+    }
+
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+}
+
+
+/// Garbage collection notifications listener, removed from the server when dropped.
+pub struct GcEventListener<'a, C>
+    where C: MBeanClientTrait + 'a,
+{
+    listener: NotificationListener<'a, C>,
+}
+
+impl<'a, C> GcEventListener<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Iterate over collections as they happen, waiting for them as needed.
+    ///
+    /// The server is polled every `interval` and the iterator stops after the first error.
+    pub fn events(&self, interval: Duration) -> GcEvents<'_, 'a, C> {
+        GcEvents {
+            failed: false,
+            interval,
+            listener: self,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Wait up to `timeout` for collections and return all those received so far.
+    pub fn poll(&self, timeout: Duration) -> Result<Vec<GcEvent>> {
+        let mut events = Vec::new();
+        for notification in self.listener.poll(timeout)? {
+            if let Some(event) = GcEvent::from_notification(&notification)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+
+/// Blocking iterator over the collections received by a `GcEventListener`.
+pub struct GcEvents<'l, 'a, C>
+    where C: MBeanClientTrait + 'a,
+          'a: 'l,
+{
+    failed: bool,
+    interval: Duration,
+    listener: &'l GcEventListener<'a, C>,
+    queue: VecDeque<GcEvent>,
+}
+
+impl<'l, 'a, C> Iterator for GcEvents<'l, 'a, C>
+    where C: MBeanClientTrait,
+{
+    type Item = Result<GcEvent>;
+
+    fn next(&mut self) -> Option<Result<GcEvent>> {
+        if self.failed {
+            return None;
+        }
+        while self.queue.is_empty() {
+            match self.listener.poll(self.interval) {
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error));
+                },
+                Ok(events) => self.queue.extend(events),
+            }
+        }
+        self.queue.pop_front().map(Ok)
+    }
+}
+
+
+/// Proxy for a `com.sun.management.HotSpotDiagnosticMXBean`.
+///
+/// Only available with HotSpot based JVMs.
+pub struct HotSpotDiagnosticMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> HotSpotDiagnosticMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> HotSpotDiagnosticMXBean<'a, C> {
+        HotSpotDiagnosticMXBean { proxy: Proxy::new(client, HOTSPOT_DIAGNOSTIC_MXBEAN_NAME) }
+    }
+
+    /// VM options that can be used to diagnose the JVM, sorted by name.
+    pub fn diagnostic_options(&self) -> Result<Vec<VMOption>> {
+        let mut options: Vec<VMOption> = self.proxy.get("DiagnosticOptions")?;
+        options.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(options)
+    }
+
+    /// Write a heap dump in HPROF format to a file on the host of the JVM.
+    ///
+    /// The path is resolved by the JVM and the file must not exist.
+    /// Only objects reachable from the GC roots are dumped when `live` is set.
+    pub fn dump_heap<S: Into<String>>(&self, path: S, live: bool) -> Result<()> {
+        let params = vec![Value::from(path.into()), Value::from(live)];
+        let _: Value = self.proxy.invoke("dumpHeap", params)?;
+        Ok(())
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Set a writeable VM option, the value is parsed by the JVM.
+    pub fn set_vm_option<S1, S2>(&self, name: S1, value: S2) -> Result<()>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let params = vec![Value::from(name.into()), Value::from(value.into())];
+        let _: Value = self.proxy.invoke("setVMOption", params)?;
+        Ok(())
+    }
+
+    pub fn vm_option<S: Into<String>>(&self, name: S) -> Result<VMOption> {
+        self.proxy.invoke("getVMOption", vec![Value::from(name.into())])
+    }
+}
+
+
+/// Proxy for a `java.lang.management.MemoryMXBean`.
+pub struct MemoryMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> MemoryMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> MemoryMXBean<'a, C> {
+        MemoryMXBean { proxy: Proxy::new(client, MEMORY_MXBEAN_NAME) }
+    }
+
+    /// Run the garbage collector.
+    pub fn gc(&self) -> Result<()> {
+        self.proxy.run("gc")
+    }
+
+    pub fn heap_memory_usage(&self) -> Result<MemoryUsage> {
+        self.proxy.get("HeapMemoryUsage")
+    }
+
+    pub fn is_verbose(&self) -> Result<bool> {
+        self.proxy.get("Verbose")
+    }
+
+    pub fn non_heap_memory_usage(&self) -> Result<MemoryUsage> {
+        self.proxy.get("NonHeapMemoryUsage")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Approximate number of objects waiting for finalization.
+    pub fn object_pending_finalization_count(&self) -> Result<i32> {
+        self.proxy.get("ObjectPendingFinalizationCount")
+    }
+
+    pub fn set_verbose(&self, verbose: bool) -> Result<()> {
+        self.proxy.set("Verbose", Value::from(verbose))
+    }
+
+    /// Listen to the threshold notifications of all memory pools.
+    ///
+    /// Thresholds are set on each pool with `MemoryPoolMXBean::set_usage_threshold`
+    /// and `MemoryPoolMXBean::set_collection_usage_threshold`.
+    pub fn threshold_listener(&self) -> Result<MemoryThresholdListener<'a, C>> {
+        let types = vec![
+            MEMORY_THRESHOLD_EXCEEDED.to_string(),
+            MEMORY_COLLECTION_THRESHOLD_EXCEEDED.to_string(),
+        ];
+        let listener = NotificationListener::new(self.proxy.client, MEMORY_MXBEAN_NAME, types)?;
+        Ok(MemoryThresholdListener { listener })
+    }
+}
+
+
+/// Memory threshold notifications listener, removed from the server when dropped.
+pub struct MemoryThresholdListener<'a, C>
+    where C: MBeanClientTrait + 'a,
+{
+    listener: NotificationListener<'a, C>,
+}
+
+impl<'a, C> MemoryThresholdListener<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Wait up to `timeout` for threshold events and return all those received so far.
+    pub fn poll(&self, timeout: Duration) -> Result<Vec<MemoryThresholdEvent>> {
+        let mut events = Vec::new();
+        for notification in self.listener.poll(timeout)? {
+            if let Some(event) = MemoryThresholdEvent::from_notification(&notification)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+
+/// Proxy for a `java.lang.management.MemoryPoolMXBean`.
+pub struct MemoryPoolMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> MemoryPoolMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Proxy the memory pool MXBean with the given ObjectName.
+    pub fn new<S: Into<String>>(client: &'a C, name: S) -> MemoryPoolMXBean<'a, C> {
+        MemoryPoolMXBean { proxy: Proxy::new(client, name) }
+    }
+
+    /// Memory usage after the last collection, `None` if the pool is not collected.
+    pub fn collection_usage(&self) -> Result<Option<MemoryUsage>> {
+        self.proxy.get("CollectionUsage")
+    }
+
+    /// Collection usage threshold, in bytes, fails if thresholds are not supported.
+    pub fn collection_usage_threshold(&self) -> Result<i64> {
+        self.proxy.get("CollectionUsageThreshold")
+    }
+
+    /// Number of times the usage after a collection crossed the collection usage threshold.
+    pub fn collection_usage_threshold_count(&self) -> Result<i64> {
+        self.proxy.get("CollectionUsageThresholdCount")
+    }
+
+    /// Check if the usage after the last collection is above the collection usage threshold.
+    pub fn is_collection_usage_threshold_exceeded(&self) -> Result<bool> {
+        self.proxy.get("CollectionUsageThresholdExceeded")
+    }
+
+    pub fn is_collection_usage_threshold_supported(&self) -> Result<bool> {
+        self.proxy.get("CollectionUsageThresholdSupported")
+    }
+
+    /// Check if the usage is above the usage threshold.
+    pub fn is_usage_threshold_exceeded(&self) -> Result<bool> {
+        self.proxy.get("UsageThresholdExceeded")
+    }
+
+    pub fn is_usage_threshold_supported(&self) -> Result<bool> {
+        self.proxy.get("UsageThresholdSupported")
+    }
+
+    pub fn is_valid(&self) -> Result<bool> {
+        self.proxy.get("Valid")
+    }
+
+    /// Names of the memory managers, including garbage collectors, of the pool.
+    pub fn memory_manager_names(&self) -> Result<Vec<String>> {
+        self.proxy.get("MemoryManagerNames")
+    }
+
+    pub fn memory_type(&self) -> Result<MemoryType> {
+        self.proxy.get("Type")
+    }
+
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Peak memory usage since the JVM started or the peak was reset.
+    pub fn peak_usage(&self) -> Result<MemoryUsage> {
+        self.proxy.get("PeakUsage")
+    }
+
+    pub fn reset_peak_usage(&self) -> Result<()> {
+        self.proxy.run("resetPeakUsage")
+    }
+
+    /// Set the collection usage threshold, in bytes, fails if thresholds are not supported.
+    ///
+    /// A threshold of `0` disables the threshold.
+    pub fn set_collection_usage_threshold(&self, threshold: i64) -> Result<()> {
+        self.proxy.set("CollectionUsageThreshold", Value::from(threshold))
+    }
+
+    /// Set the usage threshold, in bytes, fails if thresholds are not supported.
+    ///
+    /// A threshold of `0` disables the threshold.
+    pub fn set_usage_threshold(&self, threshold: i64) -> Result<()> {
+        self.proxy.set("UsageThreshold", Value::from(threshold))
+    }
+
+    pub fn usage(&self) -> Result<MemoryUsage> {
+        self.proxy.get("Usage")
+    }
+
+    /// Usage threshold, in bytes, fails if thresholds are not supported.
+    pub fn usage_threshold(&self) -> Result<i64> {
+        self.proxy.get("UsageThreshold")
+    }
+
+    /// Number of times the usage crossed the usage threshold.
+    pub fn usage_threshold_count(&self) -> Result<i64> {
+        self.proxy.get("UsageThresholdCount")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.OperatingSystemMXBean`.
+pub struct OperatingSystemMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> OperatingSystemMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> OperatingSystemMXBean<'a, C> {
+        OperatingSystemMXBean { proxy: Proxy::new(client, OPERATING_SYSTEM_MXBEAN_NAME) }
+    }
+
+    pub fn arch(&self) -> Result<String> {
+        self.proxy.get("Arch")
+    }
+
+    pub fn available_processors(&self) -> Result<i32> {
+        self.proxy.get("AvailableProcessors")
+    }
+
+    /// Virtual memory guaranteed to be available to the JVM process, in bytes (extension).
+    pub fn committed_virtual_memory_size(&self) -> Result<i64> {
+        self.proxy.get("CommittedVirtualMemorySize")
+    }
+
+    /// Free physical memory, in bytes (extension).
+    pub fn free_physical_memory_size(&self) -> Result<i64> {
+        self.proxy.get("FreePhysicalMemorySize")
+    }
+
+    /// Free swap space, in bytes (extension).
+    pub fn free_swap_space_size(&self) -> Result<i64> {
+        self.proxy.get("FreeSwapSpaceSize")
+    }
+
+    /// Maximum number of file descriptors the process can open (Unix extension).
+    pub fn max_file_descriptor_count(&self) -> Result<i64> {
+        self.proxy.get("MaxFileDescriptorCount")
+    }
+
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Number of file descriptors open by the process (Unix extension).
+    pub fn open_file_descriptor_count(&self) -> Result<i64> {
+        self.proxy.get("OpenFileDescriptorCount")
+    }
+
+    /// Recent CPU usage of the JVM process, between 0 and 1 or negative if unknown (extension).
+    pub fn process_cpu_load(&self) -> Result<f64> {
+        self.proxy.get("ProcessCpuLoad")
+    }
+
+    /// CPU time used by the JVM process, in nanoseconds (extension).
+    pub fn process_cpu_time(&self) -> Result<i64> {
+        self.proxy.get("ProcessCpuTime")
+    }
+
+    /// Recent CPU usage of the whole system, between 0 and 1 or negative if unknown (extension).
+    pub fn system_cpu_load(&self) -> Result<f64> {
+        self.proxy.get("SystemCpuLoad")
+    }
+
+    /// System load average for the last minute, negative if unavailable.
+    pub fn system_load_average(&self) -> Result<f64> {
+        self.proxy.get("SystemLoadAverage")
+    }
+
+    /// Total physical memory, in bytes (extension).
+    pub fn total_physical_memory_size(&self) -> Result<i64> {
+        self.proxy.get("TotalPhysicalMemorySize")
+    }
+
+    /// Total swap space, in bytes (extension).
+    pub fn total_swap_space_size(&self) -> Result<i64> {
+        self.proxy.get("TotalSwapSpaceSize")
+    }
+
+    pub fn version(&self) -> Result<String> {
+        self.proxy.get("Version")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.RuntimeMXBean`.
+pub struct RuntimeMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> RuntimeMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> RuntimeMXBean<'a, C> {
+        RuntimeMXBean { proxy: Proxy::new(client, RUNTIME_MXBEAN_NAME) }
+    }
+
+    pub fn class_path(&self) -> Result<String> {
+        self.proxy.get("ClassPath")
+    }
+
+    /// Arguments passed to the JVM, excluding the arguments of the main method.
+    pub fn input_arguments(&self) -> Result<Vec<String>> {
+        self.proxy.get("InputArguments")
+    }
+
+    pub fn library_path(&self) -> Result<String> {
+        self.proxy.get("LibraryPath")
+    }
+
+    /// Name of the running JVM, usually `pid@hostname`.
+    pub fn name(&self) -> Result<String> {
+        self.proxy.get("Name")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Process ID of the JVM, requires Java 10 or later.
+    pub fn pid(&self) -> Result<i64> {
+        self.proxy.get("Pid")
+    }
+
+    pub fn spec_name(&self) -> Result<String> {
+        self.proxy.get("SpecName")
+    }
+
+    pub fn spec_vendor(&self) -> Result<String> {
+        self.proxy.get("SpecVendor")
+    }
+
+    pub fn spec_version(&self) -> Result<String> {
+        self.proxy.get("SpecVersion")
+    }
+
+    /// Start time of the JVM, in milliseconds since the epoch.
+    pub fn start_time(&self) -> Result<i64> {
+        self.proxy.get("StartTime")
+    }
+
+    pub fn system_properties(&self) -> Result<BTreeMap<String, String>> {
+        let rows: Vec<TableRow<String>> = self.proxy.get("SystemProperties")?;
+        Ok(rows.into_iter().map(|row| (row.key, row.value)).collect())
+    }
+
+    /// Uptime of the JVM, in milliseconds.
+    pub fn uptime(&self) -> Result<i64> {
+        self.proxy.get("Uptime")
+    }
+
+    pub fn vm_name(&self) -> Result<String> {
+        self.proxy.get("VmName")
+    }
+
+    pub fn vm_vendor(&self) -> Result<String> {
+        self.proxy.get("VmVendor")
+    }
+
+    pub fn vm_version(&self) -> Result<String> {
+        self.proxy.get("VmVersion")
+    }
+}
+
+
+/// Proxy for a `java.lang.management.ThreadMXBean`.
+pub struct ThreadMXBean<'a, C: 'a> {
+    proxy: Proxy<'a, C>,
+}
+
+impl<'a, C> ThreadMXBean<'a, C>
+    where C: MBeanClientTrait,
+{
+    pub fn new(client: &'a C) -> ThreadMXBean<'a, C> {
+        ThreadMXBean { proxy: Proxy::new(client, THREAD_MXBEAN_NAME) }
+    }
+
+    /// IDs of all live threads.
+    pub fn all_thread_ids(&self) -> Result<Vec<i64>> {
+        self.proxy.get("AllThreadIds")
+    }
+
+    pub fn daemon_thread_count(&self) -> Result<i32> {
+        self.proxy.get("DaemonThreadCount")
+    }
+
+    /// Information about all live threads, with their stack traces.
+    ///
+    /// Optionally include the object monitors and ownable synchronizers locked by each thread.
+    pub fn dump_all_threads(
+        &self, locked_monitors: bool, locked_synchronizers: bool
+    ) -> Result<Vec<ThreadInfo>> {
+        let params = vec![Value::from(locked_monitors), Value::from(locked_synchronizers)];
+        self.proxy.invoke("dumpAllThreads", params)
+    }
+
+    /// IDs of the threads deadlocked waiting for monitors or ownable synchronizers.
+    pub fn find_deadlocked_threads(&self) -> Result<Vec<i64>> {
+        let ids: Option<Vec<i64>> = self.proxy.invoke("findDeadlockedThreads", Vec::new())?;
+        Ok(ids.unwrap_or_default())
+    }
+
+    pub fn is_thread_contention_monitoring_enabled(&self) -> Result<bool> {
+        self.proxy.get("ThreadContentionMonitoringEnabled")
+    }
+
+    pub fn is_thread_contention_monitoring_supported(&self) -> Result<bool> {
+        self.proxy.get("ThreadContentionMonitoringSupported")
+    }
+
+    pub fn is_thread_cpu_time_enabled(&self) -> Result<bool> {
+        self.proxy.get("ThreadCpuTimeEnabled")
+    }
+
+    pub fn is_thread_cpu_time_supported(&self) -> Result<bool> {
+        self.proxy.get("ThreadCpuTimeSupported")
+    }
+
+    pub fn object_name(&self) -> &str {
+        &self.proxy.name
+    }
+
+    /// Peak number of live threads since the JVM started or the peak was reset.
+    pub fn peak_thread_count(&self) -> Result<i32> {
+        self.proxy.get("PeakThreadCount")
+    }
+
+    pub fn reset_peak_thread_count(&self) -> Result<()> {
+        self.proxy.run("resetPeakThreadCount")
+    }
+
+    pub fn set_thread_contention_monitoring_enabled(&self, enabled: bool) -> Result<()> {
+        self.proxy.set("ThreadContentionMonitoringEnabled", Value::from(enabled))
+    }
+
+    pub fn set_thread_cpu_time_enabled(&self, enabled: bool) -> Result<()> {
+        self.proxy.set("ThreadCpuTimeEnabled", Value::from(enabled))
+    }
+
+    /// Number of live threads, including daemon threads.
+    pub fn thread_count(&self) -> Result<i32> {
+        self.proxy.get("ThreadCount")
+    }
+
+    /// CPU time used by a thread, in nanoseconds, or `-1` if not available.
+    pub fn thread_cpu_time(&self, id: i64) -> Result<i64> {
+        self.proxy.invoke("getThreadCpuTime", vec![Value::from(id)])
+    }
+
+    /// CPU time used by a thread in user mode, in nanoseconds, or `-1` if not available.
+    pub fn thread_user_time(&self, id: i64) -> Result<i64> {
+        self.proxy.invoke("getThreadUserTime", vec![Value::from(id)])
+    }
+
+    /// Information about the threads with the given IDs, with their stack traces.
+    ///
+    /// Threads that are no longer alive are skipped.
+    pub fn thread_info(
+        &self, ids: &[i64], locked_monitors: bool, locked_synchronizers: bool
+    ) -> Result<Vec<ThreadInfo>> {
+        let ids = ids.iter().map(|id| Value::from(*id)).collect();
+        let params = vec![
+            Value::Array(ids), Value::from(locked_monitors), Value::from(locked_synchronizers)
+        ];
+        let threads: Vec<Option<ThreadInfo>> = self.proxy.invoke("getThreadInfo", params)?;
+        Ok(threads.into_iter().flatten().collect())
+    }
+
+    /// Number of threads started since the JVM started.
+    pub fn total_started_thread_count(&self) -> Result<i64> {
+        self.proxy.get("TotalStartedThreadCount")
+    }
+}
+
+
+/// Row of a `TabularData` indexed by a single key.
+#[derive(Deserialize)]
+pub(crate) struct TableRow<V> {
+    pub(crate) key: String,
+    pub(crate) value: V,
+}
+
+/// Deserialize a `TabularData` with `key` and `value` columns into a map.
+pub(crate) fn table<'de, D, V>(
+    deserializer: D
+) -> ::std::result::Result<BTreeMap<String, V>, D::Error>
+    where D: Deserializer<'de>,
+          V: Deserialize<'de>,
+{
+    let rows: Vec<TableRow<V>> = Vec::deserialize(deserializer)?;
+    Ok(rows.into_iter().map(|row| (row.key, row.value)).collect())
+}
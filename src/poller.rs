@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crossbeam_channel as channel;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Select;
+use crossbeam_channel::Sender;
+use serde_json::Value;
+
+use super::MBeanClientTrait;
+use super::dump::AttributeState;
+
+
+/// Value of an attribute read by a `Poller`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Name of the attribute.
+    pub attribute: String,
+
+    /// Name of the MBean the attribute was read from.
+    pub name: String,
+
+    /// Time the attribute was read.
+    pub timestamp: SystemTime,
+
+    /// Value of the attribute, or the reason it could not be read.
+    pub value: AttributeState,
+}
+
+
+/// Attributes of the MBeans matching a pattern to read periodically.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Subscription {
+    attributes: Vec<String>,
+    interval: Duration,
+    pattern: String,
+}
+
+impl Subscription {
+    /// Read the given attributes of all MBeans matching the pattern every interval.
+    ///
+    /// If no attributes are given all readable attributes of the MBeans are read.
+    ///
+    /// # Panics
+    /// If `interval` is zero.
+    pub fn new<S, I, A>(pattern: S, attributes: I, interval: Duration) -> Subscription
+        where S: Into<String>,
+              I: IntoIterator<Item = A>,
+              A: Into<String>,
+    {
+        assert!(interval > Duration::from_secs(0), "Subscription interval must not be zero");
+        Subscription {
+            attributes: attributes.into_iter().map(Into::into).collect(),
+            interval,
+            pattern: pattern.into(),
+        }
+    }
+}
+
+
+/// Read MBean attributes on a schedule and deliver them over a channel.
+///
+/// Each subscription is polled on a background thread at its own interval.
+/// Patterns are resolved again on every poll so MBeans registered after the poller
+/// started are picked up and MBeans that were unregistered are no longer read.
+/// If the names can't be resolved the subscription is retried at its next interval.
+///
+/// The poller stops when it is dropped.
+pub struct Poller {
+    samples: Receiver<Sample>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Poller {
+    /// Start polling the subscriptions with the given client and default options.
+    pub fn start<C>(client: Arc<C>, subscriptions: Vec<Subscription>) -> Poller
+        where C: MBeanClientTrait + Send + Sync + 'static,
+    {
+        Poller::start_with_options(client, subscriptions, PollerOptions::default())
+    }
+
+    /// Start polling the subscriptions with the given client and options.
+    pub fn start_with_options<C>(
+        client: Arc<C>, subscriptions: Vec<Subscription>, options: PollerOptions
+    ) -> Poller
+        where C: MBeanClientTrait + Send + Sync + 'static,
+    {
+        let (samples_sender, samples) = match options.samples_buffer {
+            None => channel::unbounded(),
+            Some(size) => channel::bounded(size),
+        };
+        let (stop, stop_receiver) = channel::bounded(0);
+        let schedule = Schedule {
+            client,
+            last: HashMap::new(),
+            only_changes: options.only_changes,
+            samples: samples_sender,
+            subscriptions: subscriptions.into_iter()
+                .map(|subscription| (Instant::now(), subscription))
+                .collect(),
+        };
+        let thread = thread::spawn(move || schedule.run(&stop_receiver));
+        Poller {
+            samples,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Receiver of the samples read by the poller.
+    ///
+    /// All receivers share the same samples: each sample is delivered to one of them.
+    pub fn samples(&self) -> Receiver<Sample> {
+        self.samples.clone()
+    }
+
+    /// Stop polling and wait for the background thread to exit.
+    ///
+    /// Samples already read can still be received after the poller is stopped.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+}
+
+impl Poller {
+    fn shutdown(&mut self) {
+        // Dropping the sender disconnects the channel and wakes the background thread.
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+
+/// Additional `Poller` options.
+#[derive(Clone, Debug, Default)]
+pub struct PollerOptions {
+    only_changes: bool,
+    samples_buffer: Option<usize>,
+}
+
+impl PollerOptions {
+    /// Only deliver samples whose value changed since the previous poll.
+    ///
+    /// The first value read for each attribute of an MBean is always delivered,
+    /// including after the MBean is unregistered and registered again.
+    pub fn only_changes(mut self, only_changes: bool) -> Self {
+        self.only_changes = only_changes;
+        self
+    }
+
+    /// Clear the samples buffer size so unlimited samples are buffered (the default).
+    pub fn samples_buffer_unlimited(mut self) -> Self {
+        self.samples_buffer = None;
+        self
+    }
+
+    /// Set the samples buffer size.
+    ///
+    /// Polling pauses while the buffer is full.
+    pub fn samples_buffer_size(mut self, size: usize) -> Self {
+        self.samples_buffer = Some(size);
+        self
+    }
+}
+
+
+/// State of the background polling thread.
+struct Schedule<C> {
+    client: Arc<C>,
+    /// Last value of each attribute, by subscription, MBean and attribute name.
+    last: HashMap<(usize, String, String), AttributeState>,
+    only_changes: bool,
+    samples: Sender<Sample>,
+    /// Subscriptions with the time they are next due.
+    subscriptions: Vec<(Instant, Subscription)>,
+}
+
+impl<C> Schedule<C>
+    where C: MBeanClientTrait,
+{
+    fn run(mut self, stop: &Receiver<()>) {
+        loop {
+            let now = Instant::now();
+            let due = self.subscriptions.iter().map(|&(due, _)| due).min();
+            let timeout = match due {
+                None => Duration::from_secs(3600),
+                Some(due) => due.saturating_duration_since(now),
+            };
+            match stop.recv_timeout(timeout) {
+                Err(RecvTimeoutError::Timeout) => (),
+                _ => return,
+            }
+            for idx in 0..self.subscriptions.len() {
+                let (due, _) = self.subscriptions[idx];
+                if due > Instant::now() {
+                    continue;
+                }
+                if !self.poll(idx, stop) {
+                    return;
+                }
+                // Skip missed polls rather than bursting to catch up.
+                let (ref mut due, ref subscription) = self.subscriptions[idx];
+                *due += subscription.interval;
+                if *due < Instant::now() {
+                    *due = Instant::now() + subscription.interval;
+                }
+            }
+        }
+    }
+
+    /// Deliver a sample unless the poller is stopped while waiting for buffer space.
+    fn deliver(&self, sample: Sample, stop: &Receiver<()>) -> bool {
+        let mut select = Select::new();
+        let send = select.send(&self.samples);
+        select.recv(stop);
+        let operation = select.select();
+        if operation.index() == send {
+            return operation.send(&self.samples, sample).is_ok();
+        }
+        let _ = operation.recv(stop);
+        false
+    }
+
+    /// Poll a subscription, returning `false` if the poller was stopped.
+    fn poll(&mut self, idx: usize, stop: &Receiver<()>) -> bool {
+        let subscription = self.subscriptions[idx].1.clone();
+        let names = match self.client.query_names(subscription.pattern.as_str(), "") {
+            Err(_) => return true,
+            Ok(names) => names,
+        };
+        let mut samples = Vec::new();
+        for name in &names {
+            let timestamp = SystemTime::now();
+            let values: Vec<(String, AttributeState)> = match subscription.attributes.is_empty() {
+                true => match self.client.snapshot_mbean(name.as_str()) {
+                    // The MBean may have been unregistered since the names were resolved.
+                    Err(_) => continue,
+                    Ok(snapshot) => {
+                        let values = snapshot.attributes.into_iter()
+                            .map(|(attribute, value)| (attribute, AttributeState::Value(value)));
+                        let errors = snapshot.errors.into_iter()
+                            .map(|(attribute, error)| (attribute, AttributeState::Error(error)));
+                        values.chain(errors).collect()
+                    },
+                },
+                false => subscription.attributes.iter().map(|attribute| {
+                    let value: ::Result<Value> = self.client.get_attribute(
+                        name.as_str(), attribute.as_str()
+                    );
+                    let value = match value {
+                        Err(error) => AttributeState::Error(error.to_string()),
+                        Ok(value) => AttributeState::Value(value),
+                    };
+                    (attribute.clone(), value)
+                }).collect(),
+            };
+            for (attribute, value) in values {
+                if self.only_changes {
+                    let key = (idx, name.clone(), attribute.clone());
+                    if self.last.get(&key) == Some(&value) {
+                        continue;
+                    }
+                    self.last.insert(key, value.clone());
+                }
+                samples.push(Sample {
+                    attribute,
+                    name: name.clone(),
+                    timestamp,
+                    value,
+                });
+            }
+        }
+
+        // Forget MBeans that are gone so their values are delivered if they come back.
+        let names: HashSet<&String> = names.iter().collect();
+        self.last.retain(|&(sub, ref name, _), _| sub != idx || names.contains(name));
+        samples.into_iter().all(|sample| self.deliver(sample, stop))
+    }
+}
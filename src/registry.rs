@@ -0,0 +1,179 @@
+//! Mirror the names of the MBeans registered with a server as they change.
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use super::MBeanClientTrait;
+use super::NotificationListener;
+use super::ObjectName;
+use super::Result;
+
+
+/// Name of the `MBeanServerDelegate`, which announces MBean registrations.
+pub const MBEAN_SERVER_DELEGATE_NAME: &str = "JMImplementation:type=MBeanServerDelegate";
+
+/// Type of the notifications emitted when an MBean is registered.
+pub const REGISTRATION_NOTIFICATION: &str = "JMX.mbean.registered";
+
+/// Type of the notifications emitted when an MBean is unregistered.
+pub const UNREGISTRATION_NOTIFICATION: &str = "JMX.mbean.unregistered";
+
+
+/// Change to the MBeans registered with a server.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RegistryChange {
+    Registered(ObjectName),
+    Unregistered(ObjectName),
+}
+
+
+/// Callback invoked with each change applied to a `Registry`.
+type ChangeHook<'a> = Box<dyn FnMut(&RegistryChange) + 'a>;
+
+
+/// Local mirror of the names of the MBeans registered with a server.
+///
+/// The registry lists all MBeans once when created and then follows the
+/// registration notifications of the `MBeanServerDelegate`.
+/// Notifications are applied when the registry is refreshed so queries
+/// never wait for the server.
+pub struct Registry<'a, C>
+    where C: MBeanClientTrait + 'a,
+{
+    client: &'a C,
+    hooks: Vec<ChangeHook<'a>>,
+    listener: NotificationListener<'a, C>,
+    names: BTreeSet<ObjectName>,
+}
+
+impl<'a, C> Registry<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Start mirroring the MBeans registered with the server of a client.
+    pub fn new(client: &'a C) -> Result<Registry<'a, C>> {
+        // Listen before listing so no change is lost in between.
+        let listener = Registry::listen(client)?;
+        let mut registry = Registry {
+            client,
+            hooks: Vec::new(),
+            listener,
+            names: BTreeSet::new(),
+        };
+        registry.names = registry.list()?;
+        Ok(registry)
+    }
+
+    /// Client the registry mirrors the server of.
+    pub fn client(&self) -> &'a C {
+        self.client
+    }
+
+    /// Check if an MBean is registered.
+    pub fn contains(&self, name: &ObjectName) -> bool {
+        self.names.contains(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Names of all the registered MBeans, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &ObjectName> {
+        self.names.iter()
+    }
+
+    /// Call `hook` with every change applied by `refresh` or `resync`.
+    pub fn on_change<F>(&mut self, hook: F)
+        where F: FnMut(&RegistryChange) + 'a,
+    {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Names of the registered MBeans matching a name or pattern, sorted.
+    pub fn query(&self, pattern: &ObjectName) -> Vec<&ObjectName> {
+        if !pattern.is_pattern() {
+            return self.names.get(pattern).into_iter().collect();
+        }
+        self.names.iter().filter(|name| pattern.matches(name)).collect()
+    }
+
+    /// Wait up to `timeout` for registration notifications and apply all those received.
+    ///
+    /// Returns the changes in the order they happened.
+    pub fn refresh(&mut self, timeout: Duration) -> Result<Vec<RegistryChange>> {
+        let mut changes = Vec::new();
+        for notification in self.listener.poll(timeout)? {
+            let name = match notification.mbean_name {
+                None => continue,
+                Some(ref name) => ObjectName::parse(name.as_str())?,
+            };
+            let change = match notification.notification_type.as_str() {
+                REGISTRATION_NOTIFICATION => RegistryChange::Registered(name),
+                UNREGISTRATION_NOTIFICATION => RegistryChange::Unregistered(name),
+                _ => continue,
+            };
+            if self.apply(&change) {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Listen to the server again, list all MBeans and apply the differences with the mirror.
+    ///
+    /// Needed when `refresh` fails: after a reconnection, which removes the listener,
+    /// or if notifications were lost.
+    pub fn resync(&mut self) -> Result<Vec<RegistryChange>> {
+        // Replace the listener before listing so no change is lost in between.
+        self.listener = Registry::listen(self.client)?;
+        let names = self.list()?;
+        let mut changes: Vec<RegistryChange> = self.names.difference(&names)
+            .map(|name| RegistryChange::Unregistered(name.clone()))
+            .collect();
+        changes.extend(
+            names.difference(&self.names).map(|name| RegistryChange::Registered(name.clone()))
+        );
+        for change in &changes {
+            self.apply(change);
+        }
+        Ok(changes)
+    }
+}
+
+impl<'a, C> Registry<'a, C>
+    where C: MBeanClientTrait,
+{
+    /// Apply a change and call the hooks, returning `false` if it changed nothing.
+    fn apply(&mut self, change: &RegistryChange) -> bool {
+        let changed = match *change {
+            RegistryChange::Registered(ref name) => self.names.insert(name.clone()),
+            RegistryChange::Unregistered(ref name) => self.names.remove(name),
+        };
+        if changed {
+            for hook in &mut self.hooks {
+                hook(change);
+            }
+        }
+        changed
+    }
+
+    /// Listen to the registration notifications of the server.
+    fn listen(client: &'a C) -> Result<NotificationListener<'a, C>> {
+        let types = vec![
+            REGISTRATION_NOTIFICATION.to_string(),
+            UNREGISTRATION_NOTIFICATION.to_string(),
+        ];
+        NotificationListener::new(client, MBEAN_SERVER_DELEGATE_NAME, types)
+    }
+
+    fn list(&self) -> Result<BTreeSet<ObjectName>> {
+        let mut names = BTreeSet::new();
+        for name in self.client.query_names("*:*", "")? {
+            names.insert(ObjectName::parse(name)?);
+        }
+        Ok(names)
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use failure::ResultExt;
+use j4rs::Jvm;
+use j4rs::JvmBuilder;
+
+use super::ErrorKind;
+use super::MBeanAddress;
+use super::MBeanClient;
+use super::Result;
+
+use super::mbean_client::Credentials;
+
+
+/// Shared JVM used to create many `MBeanClient` connections.
+///
+/// The runtime builds the JVM once, on a thread of its own that owns the `Jvm`
+/// until the last clone of the runtime is dropped.
+/// Clients created by the runtime attach the calling thread to the JVM instead of
+/// building a new `Jvm`: threads are attached when their first client is created
+/// and detached when their last client is dropped, so runtimes can be cloned
+/// and used from any thread.
+///
+/// Only one JVM can exist in a process: if one was already initialised the
+/// builder given to the runtime is ignored.
+#[derive(Clone, Debug)]
+pub struct JmxRuntime {
+    // The thread owning the JVM stops once all clones of this sender are dropped.
+    _jvm: Arc<Mutex<Sender<()>>>,
+}
+
+impl JmxRuntime {
+    /// Initialise the JVM with a default `JvmBuilder`.
+    pub fn new() -> Result<JmxRuntime> {
+        JmxRuntime::with_builder(JvmBuilder::new)
+    }
+
+    /// Initialise the JVM with the `JvmBuilder` returned by `builder`.
+    ///
+    /// Because `JvmBuilder` can't be sent across threads the builder is created
+    /// by the thread that owns the JVM.
+    pub fn with_builder<F>(builder: F) -> Result<JmxRuntime>
+        where F: FnOnce() -> JvmBuilder<'static> + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let (sender, built) = mpsc::channel::<Result<()>>();
+        thread::Builder::new()
+            .name("jmx-runtime".into())
+            .spawn(move || {
+                let jvm = match builder().build().with_context(|_| ErrorKind::JvmInit) {
+                    Err(error) => {
+                        let _ = sender.send(Err(error.into()));
+                        return;
+                    },
+                    Ok(jvm) => jvm,
+                };
+                let _ = sender.send(Ok(()));
+                // Keep the JVM until all clones of the runtime are dropped.
+                let _ = stopped.recv();
+                drop(jvm);
+            })
+            .with_context(|_| ErrorKind::JvmInit)?;
+        built.recv().with_context(|_| ErrorKind::JvmInit)??;
+        Ok(JmxRuntime {
+            _jvm: Arc::new(Mutex::new(stop)),
+        })
+    }
+
+    /// Create an `MBeanClient` instance connected to the given address.
+    ///
+    /// The client is bound to the calling thread, as any other `MBeanClient` is.
+    pub fn connect(&self, address: MBeanAddress) -> Result<MBeanClient> {
+        self.connect_with(address, None)
+    }
+
+    /// Create an `MBeanClient` instance connected to the given address with credentials.
+    pub fn connect_with_credentials<S1, S2>(
+        &self, address: MBeanAddress, username: S1, password: S2
+    ) -> Result<MBeanClient>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let credentials = Credentials {
+            password: password.into(),
+            username: username.into(),
+        };
+        self.connect_with(address, Some(&credentials))
+    }
+}
+
+impl JmxRuntime {
+    pub(crate) fn connect_with(
+        &self, address: MBeanAddress, credentials: Option<&Credentials>
+    ) -> Result<MBeanClient> {
+        let jvm = Jvm::attach_thread().with_context(|_| ErrorKind::JvmAttach)?;
+        MBeanClient::connect_with_jvm(address, jvm, credentials)
+    }
+}
@@ -0,0 +1,26 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::MBeanInfo;
+
+
+/// Description of an MBean together with the values of all its readable attributes.
+///
+/// Attribute values are stored in their JSON form: open types are converted so that
+/// `CompositeData` become objects and `TabularData` become arrays of rows.
+/// Attributes that could not be read are listed in `errors` with the reason.
+///
+/// The index names of each `TabularData` are listed in `indexes`, keyed by the attribute
+/// name followed by the composite items and table columns leading to the table,
+/// separated by dots (for example `LastGcInfo.memoryUsageAfterGc`).
+/// Clients that only see converted values, like the default `snapshot_mbean`, leave it empty.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MBeanSnapshot {
+    pub attributes: BTreeMap<String, Value>,
+    pub errors: BTreeMap<String, String>,
+    #[serde(default)]
+    pub indexes: BTreeMap<String, Vec<String>>,
+    pub info: MBeanInfo,
+    pub name: String,
+}
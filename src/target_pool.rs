@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+
+use super::MBeanAddress;
+use super::MBeanClientTrait;
+use super::MBeanInfo;
+use super::MBeanThreadedClient;
+use super::MBeanThreadedClientOptions;
+use super::Result;
+
+
+/// MBean and attribute always registered with an MBean server, used to probe connections.
+const PROBE_ATTRIBUTE: &str = "MBeanServerId";
+const PROBE_MBEAN: &str = "JMImplementation:type=MBeanServerDelegate";
+
+
+/// Connection health of a target in a `JmxTargetPool`.
+///
+/// Only connection failures affect the health of a target: requests that fail while
+/// the server is still reachable (for example because an MBean does not exist) do not.
+#[derive(Clone, Debug, Default)]
+pub struct TargetHealth {
+    /// The pool holds a live connection to the target.
+    pub connected: bool,
+
+    /// Number of connection failures since the last successful request.
+    pub consecutive_failures: u64,
+
+    /// Message of the most recent connection failure.
+    pub last_error: Option<String>,
+
+    /// Time of the most recent connection failure.
+    pub last_failure: Option<Instant>,
+
+    /// Time of the most recent successful request.
+    pub last_success: Option<Instant>,
+}
+
+impl TargetHealth {
+    /// Check if the last attempt to reach the target succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+}
+
+
+/// State of a single target in the pool.
+#[derive(Default)]
+struct Target {
+    client: Option<Arc<MBeanThreadedClient>>,
+    health: TargetHealth,
+    last_used: Option<Instant>,
+}
+
+
+/// Counting semaphore capping the number of in-flight requests.
+struct Permits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Permits {
+    fn acquire(&self) -> Permit<'_> {
+        let mut available = lock(&self.available);
+        while *available == 0 {
+            available = match self.released.wait(available) {
+                Ok(available) => available,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        *available -= 1;
+        Permit { permits: self }
+    }
+}
+
+struct Permit<'a> {
+    permits: &'a Permits,
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        *lock(&self.permits.available) += 1;
+        self.permits.released.notify_one();
+    }
+}
+
+
+/// Manage connections to many MBean servers, one `MBeanThreadedClient` per `MBeanAddress`.
+///
+/// Targets are connected lazily, on their first request, and connections that were not
+/// used for longer than the idle timeout are dropped.
+/// Connections that fail are dropped and re-established on the next request.
+///
+/// The number of requests in-flight across all targets is capped so that operations
+/// fanning out to all targets do not overwhelm the process.
+pub struct JmxTargetPool {
+    options: JmxTargetPoolOptions,
+    permits: Permits,
+    targets: Mutex<HashMap<MBeanAddress, Target>>,
+}
+
+impl JmxTargetPool {
+    /// Create an empty pool with default options.
+    pub fn new() -> JmxTargetPool {
+        JmxTargetPool::with_options(JmxTargetPoolOptions::default())
+    }
+
+    /// Create an empty pool with the given options.
+    pub fn with_options(options: JmxTargetPoolOptions) -> JmxTargetPool {
+        let permits = Permits {
+            available: Mutex::new(options.max_concurrency),
+            released: Condvar::new(),
+        };
+        JmxTargetPool {
+            options,
+            permits,
+            targets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a target to the pool without connecting to it.
+    ///
+    /// Returns `false` if the target was already in the pool.
+    pub fn add(&self, address: MBeanAddress) -> bool {
+        let mut targets = self.lock();
+        if targets.contains_key(&address) {
+            return false;
+        }
+        targets.insert(address, Target::default());
+        true
+    }
+
+    /// Drop the connections that were not used for longer than the idle timeout.
+    ///
+    /// Targets remain in the pool and are re-connected on their next request.
+    /// Returns the number of connections dropped.
+    pub fn evict_idle(&self) -> usize {
+        let timeout = match self.options.idle_timeout {
+            None => return 0,
+            Some(timeout) => timeout,
+        };
+        let now = Instant::now();
+        let mut evicted = Vec::new();
+        {
+            let mut targets = self.lock();
+            for target in targets.values_mut() {
+                let idle = target.last_used
+                    .map(|last_used| now.duration_since(last_used) > timeout)
+                    .unwrap_or(true);
+                if idle && target.client.is_some() {
+                    evicted.extend(target.client.take());
+                    target.health.connected = false;
+                }
+            }
+        }
+        // Wait for the clients to stop after releasing the lock.
+        let count = evicted.len();
+        drop(evicted);
+        count
+    }
+
+    /// Health of the given target, if it is in the pool.
+    pub fn health(&self, address: &MBeanAddress) -> Option<TargetHealth> {
+        self.lock().get(address).map(|target| target.health.clone())
+    }
+
+    /// Health of all the targets in the pool.
+    pub fn health_all(&self) -> HashMap<MBeanAddress, TargetHealth> {
+        self.lock().iter()
+            .map(|(address, target)| (address.clone(), target.health.clone()))
+            .collect()
+    }
+
+    /// Remove a target from the pool, dropping its connection.
+    ///
+    /// Returns `false` if the target was not in the pool.
+    pub fn remove(&self, address: &MBeanAddress) -> bool {
+        let target = self.lock().remove(address);
+        target.is_some()
+    }
+
+    /// Addresses of all the targets in the pool.
+    pub fn targets(&self) -> Vec<MBeanAddress> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Run a request against the client for a target, adding the target if needed.
+    ///
+    /// The target is connected if needed and its health is updated with the outcome.
+    pub fn with_target<F, T>(&self, address: &MBeanAddress, request: F) -> Result<T>
+        where F: FnOnce(&MBeanThreadedClient) -> Result<T>,
+    {
+        let _permit = self.permits.acquire();
+        let client = match self.client(address) {
+            Err(error) => {
+                self.record_failure(address, &error);
+                return Err(error);
+            },
+            Ok(client) => client,
+        };
+        let result = request(&client);
+        match result {
+            Ok(_) => self.record_success(address),
+            Err(ref error) => {
+                let probe: Result<String> = client.get_attribute(PROBE_MBEAN, PROBE_ATTRIBUTE);
+                match probe {
+                    Ok(_) => self.record_success(address),
+                    Err(_) => self.record_failure(address, error),
+                }
+            },
+        }
+        result
+    }
+
+    /// Run a request against all targets in the pool, in parallel.
+    ///
+    /// Idle connections are evicted before the requests are issued.
+    pub fn with_all_targets<F, T>(&self, request: F) -> HashMap<MBeanAddress, Result<T>>
+        where F: Fn(&MBeanThreadedClient) -> Result<T> + Sync,
+              T: Send,
+    {
+        self.evict_idle();
+        let addresses = self.targets();
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(HashMap::with_capacity(addresses.len()));
+        let threads = self.options.max_concurrency.min(addresses.len());
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let address = match addresses.get(next.fetch_add(1, Ordering::SeqCst)) {
+                        None => return,
+                        Some(address) => address,
+                    };
+                    let result = self.with_target(address, &request);
+                    lock(&results).insert(address.clone(), result);
+                });
+            }
+        });
+        match results.into_inner() {
+            Ok(results) => results,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Get the value of a specific MBean attribute from a target.
+    pub fn get_attribute<S1, S2, T>(
+        &self, address: &MBeanAddress, mbean: S1, attribute: S2
+    ) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.with_target(address, |client| client.get_attribute(mbean, attribute))
+    }
+
+    /// Get the value of a specific MBean attribute from all targets.
+    pub fn get_attribute_all<S1, S2, T>(
+        &self, mbean: S1, attribute: S2
+    ) -> HashMap<MBeanAddress, Result<T>>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned + Send,
+    {
+        let (mbean, attribute) = (mbean.into(), attribute.into());
+        self.with_all_targets(|client| client.get_attribute(mbean.as_str(), attribute.as_str()))
+    }
+
+    /// Get information about an MBean from a target.
+    pub fn get_mbean_info<S>(&self, address: &MBeanAddress, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        self.with_target(address, |client| client.get_mbean_info(mbean))
+    }
+
+    /// Query for the names of MBeans on a target.
+    pub fn query_names<S1, S2>(
+        &self, address: &MBeanAddress, name: S1, query: S2
+    ) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.with_target(address, |client| client.query_names(name, query))
+    }
+
+    /// Query for the names of MBeans on all targets.
+    pub fn query_names_all<S1, S2>(
+        &self, name: S1, query: S2
+    ) -> HashMap<MBeanAddress, Result<Vec<String>>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        let (name, query) = (name.into(), query.into());
+        self.with_all_targets(|client| client.query_names(name.as_str(), query.as_str()))
+    }
+}
+
+impl JmxTargetPool {
+    /// Get the client for a target, connecting it if needed.
+    fn client(&self, address: &MBeanAddress) -> Result<Arc<MBeanThreadedClient>> {
+        {
+            let mut targets = self.lock();
+            let target = targets.entry(address.clone()).or_default();
+            target.last_used = Some(Instant::now());
+            if let Some(ref client) = target.client {
+                return Ok(Arc::clone(client));
+            }
+        }
+
+        // Connect without holding the lock so other targets are not blocked.
+        let options = self.options.client.clone().skip_connect(false);
+        let client = MBeanThreadedClient::connect_with_options(address.clone(), options)?;
+        let client = Arc::new(client);
+        let mut targets = self.lock();
+        let target = targets.entry(address.clone()).or_default();
+        let client = target.client.get_or_insert(client);
+        target.health.connected = true;
+        Ok(Arc::clone(client))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<MBeanAddress, Target>> {
+        lock(&self.targets)
+    }
+
+    fn record_failure(&self, address: &MBeanAddress, error: &::Error) {
+        let mut targets = self.lock();
+        if let Some(target) = targets.get_mut(address) {
+            let dropped = target.client.take();
+            target.health.connected = false;
+            target.health.consecutive_failures += 1;
+            target.health.last_error = Some(error.to_string());
+            target.health.last_failure = Some(Instant::now());
+            drop(targets);
+            drop(dropped);
+        }
+    }
+
+    fn record_success(&self, address: &MBeanAddress) {
+        let mut targets = self.lock();
+        if let Some(target) = targets.get_mut(address) {
+            target.health.consecutive_failures = 0;
+            target.health.last_success = Some(Instant::now());
+        }
+    }
+}
+
+impl Default for JmxTargetPool {
+    fn default() -> Self {
+        JmxTargetPool::new()
+    }
+}
+
+
+/// Additional `JmxTargetPool` options.
+#[derive(Clone)]
+pub struct JmxTargetPoolOptions {
+    client: MBeanThreadedClientOptions,
+    idle_timeout: Option<Duration>,
+    max_concurrency: usize,
+}
+
+impl JmxTargetPoolOptions {
+    /// Set the options used to connect to each target.
+    ///
+    /// The `skip_connect` option is ignored as targets are connected when first used.
+    pub fn client(mut self, options: MBeanThreadedClientOptions) -> Self {
+        self.client = options;
+        self
+    }
+
+    /// Set how long a connection can go unused before it is evicted, `None` to never evict.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of requests in-flight across all targets.
+    ///
+    /// # Panics
+    /// If `max` is zero.
+    pub fn max_concurrency(mut self, max: usize) -> Self {
+        assert!(max > 0, "JmxTargetPool needs to allow at least one request at a time");
+        self.max_concurrency = max;
+        self
+    }
+}
+
+impl Default for JmxTargetPoolOptions {
+    fn default() -> Self {
+        JmxTargetPoolOptions {
+            client: MBeanThreadedClientOptions::default(),
+            idle_timeout: Some(Duration::from_secs(300)),
+            max_concurrency: 16,
+        }
+    }
+}
+
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
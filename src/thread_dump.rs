@@ -0,0 +1,248 @@
+//! Capture the threads of a remote JVM and render them like `jstack`.
+use std::fmt::Write;
+
+use super::MBeanClientTrait;
+use super::Result;
+use super::platform::ThreadMXBean;
+
+
+/// Classes and methods that mean a thread is parked rather than waiting on a monitor.
+static PARK_METHODS: &[(&str, &str)] = &[
+    ("jdk.internal.misc.Unsafe", "park"),
+    ("sun.misc.Unsafe", "park"),
+];
+
+
+/// Identity of a lock, from `java.lang.management.LockInfo`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockInfo {
+    pub class_name: String,
+    pub identity_hash_code: i32,
+}
+
+
+/// Object monitor locked by a thread, from `java.lang.management.MonitorInfo`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub class_name: String,
+    pub identity_hash_code: i32,
+
+    /// Depth in the stack trace of the frame that locked the monitor, `-1` if unknown.
+    pub locked_stack_depth: i32,
+}
+
+
+/// Frame of a thread stack trace, from `java.lang.StackTraceElement`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StackFrame {
+    pub class_loader_name: Option<String>,
+    pub class_name: String,
+    pub file_name: Option<String>,
+
+    /// Line number in the source file, negative if unknown or for native methods.
+    pub line_number: i32,
+    pub method_name: String,
+    pub module_name: Option<String>,
+    pub module_version: Option<String>,
+    pub native_method: bool,
+}
+
+impl StackFrame {
+    /// Format the frame like `StackTraceElement::toString`, without the class loader.
+    fn render(&self) -> String {
+        let mut text = format!("{}.{}(", self.class_name, self.method_name);
+        if let Some(ref module) = self.module_name {
+            text.push_str(module);
+            if let Some(ref version) = self.module_version {
+                let _ = write!(text, "@{}", version);
+            }
+            text.push('/');
+        }
+        match (self.native_method, &self.file_name) {
+            (true, _) => text.push_str("Native Method"),
+            (false, Some(file)) if self.line_number >= 0 => {
+                let _ = write!(text, "{}:{}", file, self.line_number);
+            },
+            (false, Some(file)) => text.push_str(file),
+            (false, None) => text.push_str("Unknown Source"),
+        }
+        text.push(')');
+        text
+    }
+}
+
+
+/// State of a thread, from `java.lang.Thread.State`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ThreadState {
+    Blocked,
+    New,
+    Runnable,
+    Terminated,
+    TimedWaiting,
+    Waiting,
+}
+
+impl ThreadState {
+    /// Name of the state as used by Java.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ThreadState::Blocked => "BLOCKED",
+            ThreadState::New => "NEW",
+            ThreadState::Runnable => "RUNNABLE",
+            ThreadState::Terminated => "TERMINATED",
+            ThreadState::TimedWaiting => "TIMED_WAITING",
+            ThreadState::Waiting => "WAITING",
+        }
+    }
+}
+
+
+/// Information about a thread, from `java.lang.management.ThreadInfo`.
+///
+/// Blocked and waited times are in milliseconds and are `-1` unless thread
+/// contention monitoring is enabled on the JVM.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadInfo {
+    pub blocked_count: i64,
+    pub blocked_time: i64,
+
+    /// Requires Java 9 or later, always `false` otherwise.
+    #[serde(default)]
+    pub daemon: bool,
+    pub in_native: bool,
+
+    /// Lock the thread is blocked on or waiting for.
+    pub lock_info: Option<LockInfo>,
+    pub lock_name: Option<String>,
+
+    /// ID of the thread owning the lock the thread is blocked on, `-1` if none.
+    pub lock_owner_id: i64,
+    pub lock_owner_name: Option<String>,
+
+    /// Monitors locked by the thread, empty unless requested.
+    #[serde(default)]
+    pub locked_monitors: Vec<MonitorInfo>,
+
+    /// Ownable synchronizers locked by the thread, empty unless requested.
+    #[serde(default)]
+    pub locked_synchronizers: Vec<LockInfo>,
+
+    /// Requires Java 9 or later, always `0` otherwise.
+    #[serde(default)]
+    pub priority: i32,
+    pub stack_trace: Vec<StackFrame>,
+    pub suspended: bool,
+    pub thread_id: i64,
+    pub thread_name: String,
+    pub thread_state: ThreadState,
+    pub waited_count: i64,
+    pub waited_time: i64,
+}
+
+impl ThreadInfo {
+    /// Format the thread like `jstack` does.
+    pub fn render(&self) -> String {
+        let mut text = format!("\"{}\" #{}", self.thread_name, self.thread_id);
+        if self.daemon {
+            text.push_str(" daemon");
+        }
+        if self.priority > 0 {
+            let _ = write!(text, " prio={}", self.priority);
+        }
+        if self.suspended {
+            text.push_str(" suspended");
+        }
+        if self.in_native {
+            text.push_str(" in native");
+        }
+        let _ = writeln!(text);
+        let _ = writeln!(text, "   java.lang.Thread.State: {}", self.thread_state.as_str());
+
+        for (depth, frame) in self.stack_trace.iter().enumerate() {
+            let _ = writeln!(text, "\tat {}", frame.render());
+            if depth == 0 {
+                if let Some(ref lock) = self.lock_info {
+                    let _ = writeln!(text, "\t- {} {}", self.lock_action(frame), render_lock(lock));
+                }
+            }
+            let monitors = self.locked_monitors.iter()
+                .filter(|monitor| monitor.locked_stack_depth == depth as i32);
+            for monitor in monitors {
+                let _ = writeln!(
+                    text, "\t- locked <0x{:08x}> (a {})",
+                    monitor.identity_hash_code, monitor.class_name
+                );
+            }
+        }
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "   Locked ownable synchronizers:");
+        if self.locked_synchronizers.is_empty() {
+            let _ = writeln!(text, "\t- None");
+        }
+        for lock in &self.locked_synchronizers {
+            let _ = writeln!(text, "\t- {}", render_lock(lock));
+        }
+        text
+    }
+
+    /// Describe how the thread waits for its lock, based on its top frame.
+    fn lock_action(&self, frame: &StackFrame) -> &'static str {
+        let parked = PARK_METHODS.iter().any(|&(class, method)| {
+            frame.class_name == class && frame.method_name == method
+        });
+        match self.thread_state {
+            ThreadState::Blocked => "waiting to lock",
+            // jstack aligns this action with two spaces before the lock.
+            _ if parked => "parking to wait for ",
+            _ => "waiting on",
+        }
+    }
+}
+
+fn render_lock(lock: &LockInfo) -> String {
+    format!("<0x{:08x}> (a {})", lock.identity_hash_code, lock.class_name)
+}
+
+
+/// Information about the threads that are deadlocked, with their stack traces.
+///
+/// Deadlocks on both object monitors and ownable synchronizers are found.
+pub fn find_deadlocked_threads<C>(client: &C) -> Result<Vec<ThreadInfo>>
+    where C: MBeanClientTrait,
+{
+    let threading = ThreadMXBean::new(client);
+    let ids = threading.find_deadlocked_threads()?;
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    threading.thread_info(&ids, true, true)
+}
+
+/// Render threads like `jstack` does, for tools that parse thread dumps.
+pub fn render_jstack(threads: &[ThreadInfo]) -> String {
+    let mut text = String::from("Full thread dump:\n\n");
+    for thread in threads {
+        text.push_str(&thread.render());
+        text.push('\n');
+    }
+    text
+}
+
+/// Information about all live threads, with their stack traces.
+///
+/// Optionally include the object monitors and ownable synchronizers locked by each thread.
+pub fn thread_dump<C>(
+    client: &C, locked_monitors: bool, locked_synchronizers: bool
+) -> Result<Vec<ThreadInfo>>
+    where C: MBeanClientTrait,
+{
+    ThreadMXBean::new(client).dump_all_threads(locked_monitors, locked_synchronizers)
+}
+
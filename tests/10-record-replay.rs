@@ -0,0 +1,76 @@
+//!
+//! This test is also an example of the basic use the library.
+//! The client side is limited to the body of the `run_test` function.
+//!
+//! This test:
+//!
+//!   1. Records requests made to a client into a cassette file.
+//!   2. Replays the cassette with a client that does not need a server.
+//!   3. Checks responses, including errors, match the recording.
+//!
+extern crate jmx;
+
+use std::env;
+use std::fs;
+
+use jmx::MBeanClientTrait;
+use jmx::MBeanInfo;
+use jmx::Result;
+use jmx::cassette::Cassette;
+use jmx::cassette::RecordingClient;
+use jmx::cassette::ReplayClient;
+use jmx::mock::MockMBeanClient;
+
+
+#[test]
+fn record_replay() {
+    let path = env::temp_dir().join(format!("jmx-rust-cassette-{}.json", std::process::id()));
+    run_test(&path);
+    let _ = fs::remove_file(path);
+}
+
+fn run_test(path: &std::path::Path) {
+    // Record a session with a server.
+    let server = MockMBeanClient::new();
+    let info = MBeanInfo {
+        class_name: "JmxServer".into(),
+        ..Default::default()
+    };
+    server.register_mbean("FOO:name=ServerBean", info).unwrap();
+    server.register_attribute("FOO:name=ServerBean", "ThreadCount", 16).unwrap();
+    server.fail_attribute("FOO:name=ServerBean", "SchemaName", "boom").unwrap();
+    let client = RecordingClient::new(server.clone());
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+    server.register_attribute("FOO:name=ServerBean", "ThreadCount", 32).unwrap();
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 32);
+    let schema: Result<String> = client.get_attribute("FOO:name=ServerBean", "SchemaName");
+    assert!(schema.is_err());
+    let info = client.get_mbean_info("FOO:name=ServerBean").unwrap();
+    let names = client.query_names("FOO:*", "").unwrap();
+    client.save(path).unwrap();
+    assert_eq!(client.cassette().interactions.len(), 5);
+
+    // Replay the session without a server.
+    let cassette = Cassette::load(path).unwrap();
+    assert_eq!(cassette, client.cassette());
+    let replay = ReplayClient::new(cassette);
+    let threads: i64 = replay.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+    let threads: i64 = replay.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 32);
+    let threads: i64 = replay.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 32);
+    let schema: Result<String> = replay.get_attribute("FOO:name=ServerBean", "SchemaName");
+    assert_eq!(
+        format!("{}", schema.unwrap_err()),
+        "replayed error: mock client failure: boom"
+    );
+    assert_eq!(replay.get_mbean_info("FOO:name=ServerBean").unwrap(), info);
+    assert_eq!(replay.query_names("FOO:*", "").unwrap(), names);
+
+    // Requests that were not recorded fail.
+    assert!(replay.query_names("*:*", "").is_err());
+    assert!(replay.get_mbean_info("FOO:name=Missing").is_err());
+}
@@ -0,0 +1,79 @@
+//!
+//! This test is also an example of the basic use the library.
+//! The client side is limited to the body of the `run_test` function.
+//!
+//! This test:
+//!
+//!   1. Creates an async client that is not connected.
+//!   2. Connects the client to a JMX server.
+//!   3. Issues several requests before waiting for their results.
+//!
+extern crate futures_executor;
+extern crate jmx;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use futures_executor::block_on;
+
+use jmx::MBeanAddress;
+use jmx::MBeanAsyncClient;
+use jmx::MBeanThreadedClientOptions;
+use jmx::Result;
+
+
+static JMX_PORT: u16 = 1625;
+
+
+#[test]
+fn async_client() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    run_test();
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_test() {
+    // Create a client and expect requests to fail until it is connected.
+    let client = MBeanAsyncClient::new(MBeanThreadedClientOptions::default())
+        .expect("Failed to create JMX client");
+    let result: Result<i32> = block_on(
+        client.get_attribute("FOO:name=ServerBean", "ThreadCount")
+    );
+    assert!(result.is_err());
+
+    // Connect to the server.
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    block_on(client.reconnect(address)).expect("Failed to connect to the JMX test server");
+
+    // Issue requests before waiting for any of them.
+    let threads = client.get_attribute("FOO:name=ServerBean", "ThreadCount");
+    let schema = client.get_attribute("FOO:name=ServerBean", "SchemaName");
+    let info = client.get_mbean_info("FOO:name=ServerBean");
+    let names = client.query_names("FOO:*", "");
+    let threads: i32 = block_on(threads).unwrap();
+    let schema: String = block_on(schema).unwrap();
+    assert_eq!(threads, 16);
+    assert_eq!(schema, "test");
+    assert_eq!(block_on(info).unwrap().class_name, "JmxServer");
+    assert_eq!(block_on(names).unwrap(), vec!["FOO:name=ServerBean"]);
+
+    // Dropped futures do not stop the worker.
+    drop(client.get_mbean_info("FOO:name=ServerBean"));
+    let threads = client.get_attribute("FOO:name=ServerBean", "ThreadCount");
+    let threads: i32 = block_on(threads).unwrap();
+    assert_eq!(threads, 16);
+}
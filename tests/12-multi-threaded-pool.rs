@@ -0,0 +1,90 @@
+//!
+//! This test is also an example of the use of a pool of workers in a multi-threaded process.
+//!
+//! This test:
+//!
+//!   1. Connects a pool of workers to a JMX server.
+//!   2. Fetch attributes from many threads, with each dispatch strategy.
+//!   3. Disconnects all workers in the pool.
+//!   4. Attempts to fetch an attribute from every worker (expect to fail).
+//!   5. Reconnect all workers to the server.
+//!
+extern crate jmx;
+
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClientTrait;
+use jmx::MBeanThreadedClient;
+use jmx::MBeanThreadedClientOptions;
+use jmx::Result;
+use jmx::WorkerDispatch;
+
+
+static JMX_PORT: u16 = 1626;
+static WORKERS: usize = 3;
+
+
+#[test]
+fn multi_threaded_pool() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(5));
+
+    run_test(WorkerDispatch::RoundRobin);
+    run_test(WorkerDispatch::LeastBusy);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_test(dispatch: WorkerDispatch) {
+    // Create a pool of connections to the remote JMX server.
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let options = MBeanThreadedClientOptions::default()
+        .dispatch(dispatch)
+        .workers(WORKERS);
+    let client = MBeanThreadedClient::connect_with_options(address.clone(), options)
+        .expect("Failed to connect to the JMX test server");
+    let client = Arc::new(client);
+
+    // Fetch attributes from several threads at once.
+    let threads: Vec<_> = (0..WORKERS * 2).map(|_| {
+        let client = Arc::clone(&client);
+        thread::spawn(move || {
+            let info = client.get_mbean_info("FOO:name=ServerBean").unwrap();
+            assert_eq!(info.class_name, "JmxServer");
+            let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+            assert_eq!(threads, 16);
+        })
+    }).collect();
+    for thread in threads {
+        thread.join().expect("Client thread failed");
+    }
+
+    // Disconnect all workers and expect all requests to fail.
+    let options = MBeanThreadedClientOptions::default().skip_connect(true);
+    client.reconnect_with_options(address.clone(), options).unwrap();
+    for _ in 0..WORKERS * 2 {
+        let result: Result<i32> = client.get_attribute("FOO:name=ServerBean", "ThreadCount");
+        assert!(result.is_err());
+    }
+
+    // Reconnect all workers and expect all requests to succeed.
+    client.reconnect(address).expect("Failed to connect to the JMX test server");
+    for _ in 0..WORKERS * 2 {
+        let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+        assert_eq!(threads, 16);
+    }
+}
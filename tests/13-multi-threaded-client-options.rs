@@ -0,0 +1,92 @@
+//!
+//! This test is also an example of customising the JVM used by a multi-threaded client.
+//!
+//! This test:
+//!
+//!   1. Connects a pool of workers to a JMX server with a custom JVM builder.
+//!   2. Checks the builder factory was called once by each worker.
+//!   3. Fetch a specific JMX attribute.
+//!   4. Reconnect all workers with the same options.
+//!   5. Reconnect all workers reusing the options the client was built with.
+//!
+extern crate j4rs;
+extern crate jmx;
+
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use j4rs::JvmBuilder;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClientOptions;
+use jmx::MBeanClientTrait;
+use jmx::MBeanThreadedClient;
+use jmx::MBeanThreadedClientOptions;
+
+
+static JMX_PORT: u16 = 1627;
+static WORKERS: usize = 2;
+
+
+#[test]
+fn multi_threaded_client_options() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(5));
+
+    run_test();
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_test() {
+    // Build the JVM options on the worker threads and count how many times that happens.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let options = || {
+        let calls = Arc::clone(&calls);
+        MBeanThreadedClientOptions::default()
+            .workers(WORKERS)
+            .client_options(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                // Customise the instance as desired, for example to extend the classpath.
+                let jvm = JvmBuilder::new();
+                MBeanClientOptions::default().builder(jvm)
+            })
+    };
+
+    // Create a connection to the remote JMX server.
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanThreadedClient::connect_with_options(address.clone(), options())
+        .expect("Failed to connect to the JMX test server");
+    assert_eq!(calls.load(Ordering::SeqCst), WORKERS);
+
+    // Fetch some attribute from the server.
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+
+    // Re-connect with the same options.
+    client.reconnect_with_options(address.clone(), options())
+        .expect("Failed to connect to the JMX test server");
+    assert_eq!(calls.load(Ordering::SeqCst), WORKERS * 2);
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+
+    // Re-connect without options: the factory is still used.
+    client.reconnect(address).expect("Failed to connect to the JMX test server");
+    assert_eq!(calls.load(Ordering::SeqCst), WORKERS * 3);
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+}
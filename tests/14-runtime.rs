@@ -0,0 +1,85 @@
+//!
+//! This test is also an example of sharing one JVM across many clients.
+//!
+//! This test:
+//!
+//!   1. Initialises a shared JVM runtime.
+//!   2. Connects several clients to a JMX server from the same thread.
+//!   3. Connects clients from other threads with clones of the runtime.
+//!   4. Fetch a specific JMX attribute with every client.
+//!   5. Keeps using a client once the runtime is dropped.
+//!
+extern crate jmx;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::JmxRuntime;
+use jmx::MBeanAddress;
+use jmx::MBeanClientTrait;
+
+
+static JMX_PORT: u16 = 1628;
+
+
+#[test]
+fn runtime() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(5));
+
+    run_test();
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_test() {
+    // Initialise the JVM once.
+    let runtime = JmxRuntime::new().expect("Failed to initialise the JVM");
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+
+    // Create several connections from this thread.
+    let first = runtime.connect(address.clone())
+        .expect("Failed to connect to the JMX test server");
+    let second = runtime.connect(address.clone())
+        .expect("Failed to connect to the JMX test server");
+    let threads: i32 = first.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+    drop(first);
+    let threads: i32 = second.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+
+    // Create connections from other threads.
+    let threads: Vec<_> = (0..4).map(|_| {
+        let runtime = runtime.clone();
+        let address = address.clone();
+        thread::spawn(move || {
+            let client = runtime.connect(address)
+                .expect("Failed to connect to the JMX test server");
+            let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+            assert_eq!(threads, 16);
+        })
+    }).collect();
+    for thread in threads {
+        thread.join().expect("Client thread failed");
+    }
+
+    // Clients on this thread still work after other threads detached.
+    let threads: i32 = second.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+
+    // Clients keep the thread attached after the runtime released the JVM.
+    drop(runtime);
+    let threads: i32 = second.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+}
@@ -0,0 +1,104 @@
+//!
+//! This test is also an example of managing connections to many JMX servers.
+//!
+//! This test:
+//!
+//!   1. Adds two JMX servers and an unreachable address to a pool.
+//!   2. Queries the names of MBeans on all targets at once.
+//!   3. Checks the health of each target.
+//!   4. Evicts idle connections and reconnects on the next request.
+//!
+extern crate jmx;
+
+use std::process::Child;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::JmxTargetPool;
+use jmx::JmxTargetPoolOptions;
+use jmx::MBeanAddress;
+
+
+static JMX_PORTS: [u16; 2] = [1629, 1630];
+static UNREACHABLE_PORT: u16 = 1631;
+
+
+#[test]
+fn target_pool() {
+    // Start the servers and wait for them to be up.
+    let mut servers: Vec<Child> = JMX_PORTS.iter().map(|port| {
+        Command::new("java")
+            .arg("-Dcom.sun.management.jmxremote")
+            .arg(format!("-Dcom.sun.management.jmxremote.port={}", port))
+            .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+            .arg("-Dcom.sun.management.jmxremote.ssl=false")
+            .arg("TestServer")
+            .current_dir("tests/jmxserver")
+            .spawn()
+            .expect("Could not start JMX server")
+    }).collect();
+    thread::sleep(Duration::from_secs(5));
+
+    run_test();
+
+    // Stop the servers once we are done.
+    for server in servers.iter_mut() {
+        let _ = server.kill();
+    }
+}
+
+fn run_test() {
+    // Add all targets to the pool, nothing is connected yet.
+    let options = JmxTargetPoolOptions::default()
+        .idle_timeout(Some(Duration::from_millis(0)))
+        .max_concurrency(2);
+    let pool = JmxTargetPool::with_options(options);
+    let servers: Vec<MBeanAddress> = JMX_PORTS.iter()
+        .map(|port| MBeanAddress::address(format!("localhost:{}", port)))
+        .collect();
+    let unreachable = MBeanAddress::address(format!("localhost:{}", UNREACHABLE_PORT));
+    for address in servers.iter().chain(Some(&unreachable)) {
+        assert!(pool.add(address.clone()));
+        assert!(!pool.health(address).unwrap().connected);
+    }
+    assert_eq!(pool.targets().len(), 3);
+
+    // Query all targets at once.
+    let names = pool.query_names_all("FOO:*", "");
+    assert_eq!(names.len(), 3);
+    for address in &servers {
+        assert_eq!(names[address].as_ref().unwrap(), &vec!["FOO:name=ServerBean"]);
+        let health = pool.health(address).unwrap();
+        assert!(health.connected);
+        assert!(health.is_healthy());
+    }
+    assert!(names[&unreachable].is_err());
+    let health = pool.health(&unreachable).unwrap();
+    assert!(!health.connected);
+    assert!(!health.is_healthy());
+    assert_eq!(health.consecutive_failures, 1);
+    assert!(health.last_error.is_some());
+
+    // Errors for missing attributes do not make a target unhealthy.
+    let missing: jmx::Result<i32> =
+        pool.get_attribute(&servers[0], "FOO:name=ServerBean", "Missing");
+    assert!(missing.is_err());
+    assert!(pool.health(&servers[0]).unwrap().is_healthy());
+
+    // Evict idle connections and reconnect on demand.
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(pool.evict_idle(), 2);
+    assert!(!pool.health(&servers[1]).unwrap().connected);
+    let threads: i32 = pool.get_attribute(&servers[1], "FOO:name=ServerBean", "ThreadCount")
+        .unwrap();
+    assert_eq!(threads, 16);
+    assert!(pool.health(&servers[1]).unwrap().connected);
+
+    // Remove targets from the pool.
+    assert!(pool.remove(&unreachable));
+    assert!(!pool.remove(&unreachable));
+    let threads = pool.get_attribute_all::<_, _, i32>("FOO:name=ServerBean", "ThreadCount");
+    assert_eq!(threads.len(), 2);
+    assert!(threads.values().all(|threads| *threads.as_ref().unwrap() == 16));
+}
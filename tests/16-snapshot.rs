@@ -0,0 +1,100 @@
+//!
+//! This test is also an example of fetching whole MBeans at once.
+//!
+//! This test:
+//!
+//!   1. Connects to a JMX server.
+//!   2. Snapshots an MBean with simple attributes.
+//!   3. Snapshots MBeans with open type attributes.
+//!   4. Fetches composite attributes into rust structs.
+//!
+extern crate jmx;
+#[macro_use]
+extern crate serde_derive;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanClientTrait;
+
+
+static JMX_PORT: u16 = 1633;
+
+
+#[derive(Debug, Deserialize)]
+struct MemoryUsage {
+    committed: i64,
+    init: i64,
+    max: i64,
+    used: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Property {
+    key: String,
+    value: String,
+}
+
+
+#[test]
+fn snapshot() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    run_test();
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+}
+
+fn run_test() {
+    // Create a connection to the remote JMX server.
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address)
+        .expect("Failed to connect to the JMX test server");
+
+    // Snapshot a simple MBean.
+    let snapshot = client.snapshot_mbean("FOO:name=ServerBean").unwrap();
+    assert_eq!(snapshot.name, "FOO:name=ServerBean");
+    assert_eq!(snapshot.info.class_name, "JmxServer");
+    assert_eq!(snapshot.attributes.len(), 2);
+    assert_eq!(snapshot.attributes["ThreadCount"], 16);
+    assert_eq!(snapshot.attributes["SchemaName"], "test");
+    assert!(snapshot.errors.is_empty());
+
+    // Composite and tabular attributes are converted into objects and arrays.
+    let snapshot = client.snapshot_mbean("java.lang:type=Memory").unwrap();
+    assert!(snapshot.attributes["HeapMemoryUsage"]["used"].is_number());
+    let snapshot = client.snapshot_mbean("java.lang:type=Runtime").unwrap();
+    let properties = snapshot.attributes["SystemProperties"].as_array().unwrap();
+    assert!(properties.iter().any(|row| row["key"] == "java.version"));
+    assert_eq!(snapshot.indexes["SystemProperties"], vec!["key".to_string()]);
+
+    // Open types can be decoded into rust types.
+    let usage: MemoryUsage = client.get_attribute("java.lang:type=Memory", "HeapMemoryUsage")
+        .unwrap();
+    assert!(usage.used > 0);
+    assert!(usage.committed >= usage.used);
+    assert!(usage.init >= 0 && usage.max != 0);
+    let properties: Vec<Property> = client.get_attribute(
+        "java.lang:type=Runtime", "SystemProperties"
+    ).unwrap();
+    assert!(properties.iter().any(|property| {
+        property.key == "java.vendor" && !property.value.is_empty()
+    }));
+
+    // Missing MBeans fail the snapshot.
+    assert!(client.snapshot_mbean("FOO:name=Missing").is_err());
+}
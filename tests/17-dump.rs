@@ -0,0 +1,100 @@
+//!
+//! This test is also an example of comparing the state of a server over time.
+//!
+//! This test:
+//!
+//!   1. Dumps all MBeans from a mock server.
+//!   2. Changes the server: adds, removes and updates MBeans and attributes.
+//!   3. Dumps the server again and compares the dumps.
+//!   4. Stores and reloads a dump as JSON.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use jmx::MBeanAttribute;
+use jmx::MBeanClientTrait;
+use jmx::MBeanInfo;
+use jmx::ServerDump;
+use jmx::dump::AttributeState;
+use jmx::mock::MockMBeanClient;
+
+
+#[test]
+fn dump() {
+    run_test();
+}
+
+fn info(attributes: &[&str]) -> MBeanInfo {
+    let attributes = attributes.iter().map(|name| MBeanAttribute {
+        is_readable: true,
+        name: name.to_string(),
+        type_name: "int".into(),
+        ..Default::default()
+    }).collect();
+    MBeanInfo {
+        attributes,
+        class_name: "JmxServer".into(),
+        description: "".into(),
+        operations: Vec::new(),
+        ..Default::default()
+    }
+}
+
+fn run_test() {
+    // Dump the initial state of the server.
+    let server = MockMBeanClient::new();
+    server.register_mbean("FOO:name=Same", info(&["Count"])).unwrap();
+    server.register_mbean("FOO:name=Changed", info(&["Count", "Size"])).unwrap();
+    server.register_mbean("FOO:name=Removed", info(&["Count"])).unwrap();
+    server.register_attribute("FOO:name=Same", "Count", 1).unwrap();
+    server.register_attribute("FOO:name=Changed", "Count", 1).unwrap();
+    server.register_attribute("FOO:name=Changed", "Size", 10).unwrap();
+    server.register_attribute("FOO:name=Removed", "Count", 1).unwrap();
+    server.register_mbean("BAR:name=Other", info(&["Count"])).unwrap();
+    let before = server.dump("FOO:*").unwrap();
+    assert_eq!(before.mbeans.len(), 3);
+    assert!(before.errors.is_empty());
+    assert_eq!(before.mbeans["FOO:name=Changed"].attributes["Size"], 10);
+    assert!(before.diff(&before).is_empty());
+
+    // Change the server and dump it again.
+    server.unregister_mbean("FOO:name=Removed").unwrap();
+    server.register_mbean("FOO:name=Added", info(&["Count"])).unwrap();
+    server.register_attribute("FOO:name=Added", "Count", 1).unwrap();
+    server.register_attribute("FOO:name=Changed", "Count", 2).unwrap();
+    server.fail_attribute("FOO:name=Changed", "Size", "boom").unwrap();
+    server.fail_mbean_info("FOO:name=Same", "no info").unwrap();
+    let after = server.dump("FOO:*").unwrap();
+    assert_eq!(after.mbeans.len(), 2);
+    assert_eq!(after.errors.len(), 1);
+    assert_eq!(after.mbeans["FOO:name=Changed"].errors["Size"], "mock client failure: boom");
+
+    // Compare the dumps.
+    let diff = before.diff(&after);
+    assert_eq!(diff.added, vec!["FOO:name=Added"]);
+    assert_eq!(diff.removed, vec!["FOO:name=Removed"]);
+    assert_eq!(diff.unavailable, vec!["FOO:name=Same"]);
+    let changed = &diff.changed["FOO:name=Changed"];
+    assert!(changed.info.is_none());
+    let count = &changed.attributes["Count"];
+    assert_eq!(count.before, Some(AttributeState::Value(1.into())));
+    assert_eq!(count.after, Some(AttributeState::Value(2.into())));
+    let size = &changed.attributes["Size"];
+    assert_eq!(size.after, Some(AttributeState::Error("mock client failure: boom".into())));
+
+    // Metadata changes are reported too.
+    server.clear_failures();
+    server.register_mbean("FOO:name=Same", info(&["Count", "Extra"])).unwrap();
+    let latest = server.dump("FOO:*").unwrap();
+    let diff = after.diff(&latest);
+    assert_eq!(diff.unavailable, vec!["FOO:name=Same"]);
+    let diff = before.diff(&latest);
+    let same = &diff.changed["FOO:name=Same"];
+    assert_eq!(same.info.as_ref().unwrap().after.attributes.len(), 2);
+    assert_eq!(same.attributes["Extra"].before, None);
+
+    // Dumps can be stored as JSON.
+    let json = serde_json::to_string(&after).unwrap();
+    let reloaded: ServerDump = serde_json::from_str(&json).unwrap();
+    assert_eq!(reloaded, after);
+}
@@ -0,0 +1,184 @@
+//!
+//! This test is also an example of exporting MBean attributes as Prometheus metrics.
+//!
+//! This test:
+//!
+//!   1. Registers MBeans with simple, composite and tabular attributes on a mock server.
+//!   2. Scrapes the server with `jmx_exporter` style rules.
+//!   3. Checks the default export format when no rule names the metric.
+//!   4. Renders the metrics in the OpenMetrics text format.
+//!   5. Labels table rows with their index, as reported in snapshots.
+//!
+extern crate jmx;
+#[macro_use]
+extern crate serde_json;
+
+use jmx::ErrorKind;
+use jmx::MBeanAttribute;
+use jmx::MBeanInfo;
+use jmx::MBeanSnapshot;
+use jmx::exporter::Exporter;
+use jmx::exporter::MetricType;
+use jmx::exporter::Metrics;
+use jmx::mock::MockMBeanClient;
+
+
+static CONFIG: &str = r#"
+lowercaseOutputName: true
+whitelistObjectNames: ["java.lang:*", "FOO:*"]
+blacklistObjectNames: ["java.lang:type=Compilation"]
+rules:
+  - pattern: 'java.lang<type=Memory><HeapMemoryUsage>(\w+)'
+    name: jvm_memory_heap_$1_bytes
+    help: Heap memory $1
+    type: GAUGE
+  - pattern: 'java.lang<type=Threading><>ThreadCount'
+    name: jvm_threads
+    valueFactor: 0.5
+  - pattern: 'java.lang<type=GarbageCollector, name=(.+)><>CollectionCount'
+    name: jvm_gc_collections_total
+    type: COUNTER
+    labels:
+      gc: $1
+  - pattern: 'FOO<name=(\w+)><>State: (\w+)'
+    name: foo_state
+    value: 1
+    labels:
+      state: $2
+  - pattern: 'FOO<name=\w+><>Ignored'
+  - pattern: 'FOO<(.*)><Pools>(.*)'
+"#;
+
+
+#[test]
+fn exporter() {
+    run_test();
+}
+
+fn info(attributes: &[&str]) -> MBeanInfo {
+    let attributes = attributes.iter().map(|name| MBeanAttribute {
+        description: format!("{} attribute", name),
+        is_readable: true,
+        name: name.to_string(),
+        type_name: "int".into(),
+        ..Default::default()
+    }).collect();
+    MBeanInfo {
+        attributes,
+        class_name: "JmxServer".into(),
+        description: "".into(),
+        operations: Vec::new(),
+        ..Default::default()
+    }
+}
+
+fn run_test() {
+    let server = MockMBeanClient::new();
+    server.register_mbean("java.lang:type=Memory", info(&["HeapMemoryUsage"])).unwrap();
+    server.register_attribute("java.lang:type=Memory", "HeapMemoryUsage", json!({
+        "committed": 2048, "init": 1024, "max": 4096, "used": 512,
+    })).unwrap();
+    server.register_mbean("java.lang:type=Threading", info(&["ThreadCount"])).unwrap();
+    server.register_attribute("java.lang:type=Threading", "ThreadCount", 16).unwrap();
+    let gc = "java.lang:type=GarbageCollector,name=G1 Young Generation";
+    server.register_mbean(gc, info(&["CollectionCount"])).unwrap();
+    server.register_attribute(gc, "CollectionCount", 7).unwrap();
+    server.register_mbean("java.lang:type=Compilation", info(&["TotalCompilationTime"])).unwrap();
+    server.register_attribute("java.lang:type=Compilation", "TotalCompilationTime", 3).unwrap();
+    server.register_mbean("FOO:name=ServerBean", info(&["Enabled", "Ignored", "Pools", "State"]))
+        .unwrap();
+    server.register_attribute("FOO:name=ServerBean", "Enabled", true).unwrap();
+    server.register_attribute("FOO:name=ServerBean", "Ignored", "text").unwrap();
+    server.register_attribute("FOO:name=ServerBean", "Pools", json!([
+        {"pool": "main", "size": 4, "active": 1},
+        {"pool": "batch", "size": 2, "active": 0},
+    ])).unwrap();
+    server.register_attribute("FOO:name=ServerBean", "State", "RUNNING").unwrap();
+    server.register_mbean("BAR:name=Skipped", info(&["Count"])).unwrap();
+    server.register_attribute("BAR:name=Skipped", "Count", 1).unwrap();
+
+    // Scrape the server with the rules.
+    let exporter = Exporter::from_yaml(CONFIG).expect("Failed to load exporter config");
+    let metrics = exporter.scrape(&server).expect("Failed to scrape mock server");
+    assert_eq!(metrics.value("jvm_memory_heap_used_bytes", &[]), Some(512.0));
+    assert_eq!(metrics.value("jvm_memory_heap_max_bytes", &[]), Some(4096.0));
+    let family = metrics.family("jvm_memory_heap_used_bytes").unwrap();
+    assert_eq!(family.help, "Heap memory used");
+    assert_eq!(family.metric_type, MetricType::Gauge);
+    assert_eq!(metrics.value("jvm_threads", &[]), Some(8.0));
+    let family = metrics.family("jvm_gc_collections").unwrap();
+    assert_eq!(family.metric_type, MetricType::Counter);
+    assert_eq!(
+        metrics.value("jvm_gc_collections", &[("gc", "G1 Young Generation")]),
+        Some(7.0)
+    );
+    assert_eq!(metrics.value("foo_state", &[("state", "RUNNING")]), Some(1.0));
+
+    // Blacklisted, not whitelisted, unmatched and string values are not exported.
+    let names: Vec<&str> = metrics.families().iter().map(|family| family.name.as_str()).collect();
+    assert!(!names.iter().any(|name| name.contains("compilation")));
+    assert!(!names.iter().any(|name| name.starts_with("bar")));
+    assert!(!names.iter().any(|name| name.contains("ignored")));
+    assert_eq!(metrics.value("foo_serverbean_enabled", &[]), None);
+
+    // Attributes matched by rules without a name use the default format.
+    assert_eq!(
+        metrics.value("foo_serverbean_pools_size", &[("pool", "main")]),
+        Some(4.0)
+    );
+    assert_eq!(
+        metrics.value("foo_serverbean_pools_active", &[("pool", "batch")]),
+        Some(0.0)
+    );
+
+    // Without rules, all numeric and boolean attributes use the default format.
+    let exporter = Exporter::from_yaml("whitelistObjectNames: ['FOO:*']").unwrap();
+    let metrics = exporter.scrape(&server).unwrap();
+    assert_eq!(metrics.value("FOO_ServerBean_Enabled", &[]), Some(1.0));
+    let family = metrics.family("FOO_ServerBean_Enabled").unwrap();
+    assert_eq!(family.help, "Enabled attribute (FOO<name=ServerBean><>Enabled)");
+    assert_eq!(family.metric_type, MetricType::Untyped);
+
+    // Render the metrics.
+    let exporter = Exporter::from_yaml(CONFIG).unwrap();
+    let text = exporter.scrape(&server).unwrap().render();
+    assert!(text.contains("# TYPE jvm_gc_collections counter\n"));
+    assert!(text.contains("jvm_gc_collections_total{gc=\"G1 Young Generation\"} 7\n"));
+    assert!(text.contains("# HELP jvm_memory_heap_used_bytes Heap memory used\n"));
+    assert!(text.contains("jvm_memory_heap_used_bytes 512\n"));
+    assert!(text.contains("# TYPE jvm_threads unknown\n"));
+    assert!(text.ends_with("# EOF\n"));
+
+    // Tables with a known index are labelled with the index columns, whatever their type.
+    let mut snapshot = MBeanSnapshot {
+        name: "FOO:name=Tables".into(),
+        info: info(&["Workers"]),
+        ..Default::default()
+    };
+    snapshot.attributes.insert("Workers".into(), json!([
+        {"id": 1, "name": "first", "busy": 3},
+        {"id": 2, "name": "first", "busy": 5},
+    ]));
+    snapshot.indexes.insert("Workers".into(), vec!["id".into()]);
+    let exporter = Exporter::from_yaml("{}").unwrap();
+    let mut metrics = Metrics::new();
+    exporter.collect(&snapshot, &mut metrics).unwrap();
+    assert_eq!(metrics.value("FOO_Tables_Workers_busy", &[("id", "1")]), Some(3.0));
+    assert_eq!(metrics.value("FOO_Tables_Workers_busy", &[("id", "2")]), Some(5.0));
+
+    // Merged labels don't clash with the labels of the samples.
+    let mut merged = Metrics::new();
+    merged.merge(metrics, &[("id".to_string(), "app".to_string())]);
+    assert_eq!(
+        merged.value("FOO_Tables_Workers_busy", &[("id", "app"), ("exported_id", "1")]),
+        Some(3.0)
+    );
+
+    // Invalid rules are rejected.
+    let error = Exporter::from_yaml("rules: [{name: foo}]").err().unwrap();
+    match *error.kind() {
+        ErrorKind::ExporterConfig(_) => (),
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+    assert!(Exporter::from_yaml("rules: [{pattern: '(', name: foo}]").is_err());
+}
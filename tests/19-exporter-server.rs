@@ -0,0 +1,114 @@
+//!
+//! This test runs the `jmx-exporter` binary against the test JMX server.
+//!
+//! This test:
+//!
+//!   1. Starts the exporter with a reachable and an unreachable target.
+//!   2. Checks the health endpoint.
+//!   3. Checks the metrics scraped from the server and the scrape errors of each target.
+//!   4. Sends concurrent requests for the metrics.
+//!
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpStream;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+
+static JMX_PORT: u16 = 1634;
+static HTTP_PORT: u16 = 1635;
+static UNREACHABLE_PORT: u16 = 1636;
+
+
+#[test]
+fn exporter_server() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+
+    // Start the exporter.
+    let config = env::temp_dir().join(format!("jmx-exporter-test-{}.yaml", HTTP_PORT));
+    fs::write(&config, format!(r#"
+whitelistObjectNames: ["FOO:*"]
+targets:
+  - name: test
+    hostPort: localhost:{}
+  - name: unreachable
+    hostPort: localhost:{}
+rules:
+  - pattern: 'FOO<name=ServerBean><>ThreadCount'
+    name: foo_threads
+    type: GAUGE
+"#, JMX_PORT, UNREACHABLE_PORT)).expect("Could not write exporter config");
+    let mut exporter = Command::new(env!("CARGO_BIN_EXE_jmx-exporter"))
+        .arg(format!("127.0.0.1:{}", HTTP_PORT))
+        .arg(&config)
+        .spawn()
+        .expect("Could not start the exporter");
+    wait_for_exporter();
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the processes once we are done.
+    let _ = exporter.kill();
+    let _ = exporter.wait();
+    let _ = server.kill();
+    let _ = server.wait();
+    let _ = fs::remove_file(&config);
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+/// Wait for the exporter to start its JVM and listen for requests.
+fn wait_for_exporter() {
+    for _ in 0..50 {
+        if TcpStream::connect(("127.0.0.1", HTTP_PORT)).is_ok() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn get(path: &str) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", HTTP_PORT))
+        .expect("Could not connect to the exporter");
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+fn run_test() {
+    let health = get("/healthz");
+    assert!(health.starts_with("HTTP/1.1 200"), "{}", health);
+    assert!(health.ends_with("ok\n"));
+    assert!(get("/missing").starts_with("HTTP/1.1 404"));
+
+    let metrics = get("/metrics");
+    assert!(metrics.starts_with("HTTP/1.1 200"), "{}", metrics);
+    assert!(metrics.contains("application/openmetrics-text"));
+    assert!(metrics.contains("foo_threads{target=\"test\"} 16\n"), "{}", metrics);
+    assert!(metrics.contains("jmx_scrape_error{target=\"test\"} 0\n"));
+    assert!(metrics.contains("jmx_scrape_error{target=\"unreachable\"} 1\n"));
+    assert!(metrics.contains("# TYPE jmx_scrape_duration_seconds gauge\n"));
+    assert!(metrics.ends_with("# EOF\n"));
+
+    // Concurrent requests share on-demand scrapes and all get the metrics.
+    let requests: Vec<_> = (0..6).map(|_| thread::spawn(|| get("/metrics"))).collect();
+    for request in requests {
+        let metrics = request.join().unwrap();
+        assert!(metrics.contains("foo_threads{target=\"test\"} 16\n"), "{}", metrics);
+    }
+}
@@ -0,0 +1,133 @@
+//!
+//! This test runs the `jmx` command line tool against the test JMX server.
+//!
+//! This test:
+//!
+//!   1. Lists domains and MBeans and describes an MBean.
+//!   2. Gets and sets attributes, in table and JSON format.
+//!   3. Invokes operations with parameters.
+//!   4. Watches and dumps attributes.
+//!   5. Runs commands in the interactive shell.
+//!
+extern crate serde_json;
+
+use std::io::Write;
+use std::process::Command;
+use std::process::Output;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+
+static JMX_PORT: u16 = 1637;
+
+
+#[test]
+fn cli() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_jmx"));
+    command.arg("--address").arg(format!("localhost:{}", JMX_PORT));
+    command
+}
+
+/// Run the tool with the given arguments and return its standard output.
+fn jmx(args: &[&str]) -> String {
+    let output = command().args(args).output().expect("Could not run jmx");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn jmx_json(args: &[&str]) -> Value {
+    let mut all_args = vec!["--output", "json"];
+    all_args.extend_from_slice(args);
+    serde_json::from_str(&jmx(&all_args)).expect("Output is not valid JSON")
+}
+
+fn run_test() {
+    // List and describe MBeans.
+    let domains = jmx(&["domains"]);
+    assert!(domains.lines().any(|domain| domain == "FOO"));
+    assert!(domains.lines().any(|domain| domain == "java.lang"));
+    assert_eq!(jmx(&["beans", "FOO:*"]), "FOO:name=ServerBean\n");
+    let info = jmx(&["info", "FOO:name=ServerBean"]);
+    assert!(info.contains("ThreadCount  rw  int"), "{}", info);
+    assert!(info.contains("SchemaName   r   java.lang.String"), "{}", info);
+    let info = jmx_json(&["info", "java.util.logging:type=Logging"]);
+    let operations = info["operations"].as_array().unwrap();
+    assert!(operations.iter().any(|operation| operation["name"] == "getLoggerLevel"));
+
+    // Get and set attributes.
+    let values = jmx(&["get", "FOO:name=ServerBean", "ThreadCount", "SchemaName"]);
+    assert_eq!(values, "ThreadCount  16\nSchemaName   test\n");
+    jmx(&["set", "FOO:name=ServerBean", "ThreadCount", "20"]);
+    let values = jmx_json(&["get", "FOO:name=ServerBean", "ThreadCount"]);
+    assert_eq!(values["ThreadCount"], 20);
+
+    // Invoke operations.
+    let logging = "java.util.logging:type=Logging";
+    assert_eq!(jmx(&["invoke", logging, "setLoggerLevel", "global", "FINE"]), "");
+    assert_eq!(jmx(&["invoke", logging, "getLoggerLevel", "global"]), "FINE\n");
+    assert_eq!(jmx_json(&["invoke", logging, "getLoggerLevel", "global"]), "FINE");
+
+    // Watch and dump attributes.
+    let watch = jmx(&[
+        "watch", "FOO:name=ServerBean", "ThreadCount", "--interval", "0.1", "--count", "2"
+    ]);
+    assert_eq!(watch, "20\n20\n");
+    let dump = jmx_json(&["dump", "FOO:*"]);
+    assert_eq!(dump["mbeans"]["FOO:name=ServerBean"]["attributes"]["SchemaName"], "test");
+
+    // Errors are reported with a failed exit status.
+    let output: Output = command()
+        .args(["get", "FOO:name=ServerBean", "Missing"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("jmx: "));
+    for interval in &["0", "-1", "NaN", "inf"] {
+        let output = command()
+            .args(["watch", "FOO:name=ServerBean", "ThreadCount", "--interval", interval])
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "interval {} was accepted", interval);
+    }
+
+    // Run commands in the interactive shell.
+    let mut shell = command()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Could not run jmx");
+    shell.stdin.take().unwrap()
+        .write_all(b"get 'FOO:name=ServerBean' SchemaName\nbogus\nquit\n")
+        .unwrap();
+    let output = shell.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("SchemaName  test\n"), "{}", stdout);
+    assert!(stdout.contains("bogus"), "{}", stdout);
+}
@@ -0,0 +1,97 @@
+//!
+//! This test is also an example of polling MBean attributes on a schedule.
+//!
+//! This test:
+//!
+//!   1. Polls attributes of MBeans on a mock server matching a pattern.
+//!   2. Checks only changed values are delivered when requested.
+//!   3. Checks MBeans registered and unregistered after the poller started.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAttribute;
+use jmx::MBeanInfo;
+use jmx::Poller;
+use jmx::PollerOptions;
+use jmx::Sample;
+use jmx::Subscription;
+use jmx::dump::AttributeState;
+use jmx::mock::MockMBeanClient;
+
+
+#[test]
+fn poller() {
+    run_test();
+}
+
+fn info(attributes: &[&str]) -> MBeanInfo {
+    let attributes = attributes.iter().map(|name| MBeanAttribute {
+        is_readable: true,
+        name: name.to_string(),
+        ..Default::default()
+    }).collect();
+    MBeanInfo {
+        attributes,
+        ..Default::default()
+    }
+}
+
+fn recv(poller: &Poller) -> Sample {
+    poller.samples().recv_timeout(Duration::from_secs(5)).expect("No sample received")
+}
+
+fn run_test() {
+    let server = Arc::new(MockMBeanClient::new());
+    server.register_mbean("FOO:name=ServerBean", info(&["SchemaName", "ThreadCount"])).unwrap();
+    server.register_attribute("FOO:name=ServerBean", "ThreadCount", 16).unwrap();
+    server.register_attribute("FOO:name=ServerBean", "SchemaName", "test").unwrap();
+    let interval = Duration::from_millis(20);
+
+    // Every poll delivers all values.
+    let poller = Poller::start(Arc::clone(&server), vec![
+        Subscription::new("FOO:*", vec!["ThreadCount", "Missing"], interval),
+    ]);
+    let sample = recv(&poller);
+    assert_eq!(sample.name, "FOO:name=ServerBean");
+    assert_eq!(sample.attribute, "ThreadCount");
+    assert_eq!(sample.value, AttributeState::Value(serde_json::Value::from(16)));
+    let sample = recv(&poller);
+    assert_eq!(sample.attribute, "Missing");
+    match sample.value {
+        AttributeState::Error(_) => (),
+        value => panic!("unexpected value: {:?}", value),
+    }
+    assert_eq!(recv(&poller).attribute, "ThreadCount");
+    poller.stop();
+
+    // Only changes are delivered, for all attributes if none are given.
+    let options = PollerOptions::default().only_changes(true);
+    let poller = Poller::start_with_options(Arc::clone(&server), vec![
+        Subscription::new("FOO:*", Vec::<String>::new(), interval),
+    ], options);
+    let mut attributes = vec![recv(&poller).attribute, recv(&poller).attribute];
+    attributes.sort();
+    assert_eq!(attributes, vec!["SchemaName", "ThreadCount"]);
+    server.register_attribute("FOO:name=ServerBean", "ThreadCount", 17).unwrap();
+    let sample = recv(&poller);
+    assert_eq!(sample.attribute, "ThreadCount");
+    assert_eq!(sample.value, AttributeState::Value(serde_json::Value::from(17)));
+
+    // MBeans that come and go are picked up.
+    server.register_attribute("FOO:name=OtherBean", "SchemaName", "other").unwrap();
+    server.register_mbean("FOO:name=OtherBean", info(&["SchemaName"])).unwrap();
+    let sample = recv(&poller);
+    assert_eq!(sample.name, "FOO:name=OtherBean");
+    assert_eq!(sample.value, AttributeState::Value(serde_json::Value::from("other")));
+    server.unregister_mbean("FOO:name=OtherBean").unwrap();
+    thread::sleep(interval * 5);
+    assert!(poller.samples().try_recv().is_err());
+    server.register_attribute("FOO:name=OtherBean", "SchemaName", "other").unwrap();
+    server.register_mbean("FOO:name=OtherBean", info(&["SchemaName"])).unwrap();
+    assert_eq!(recv(&poller).name, "FOO:name=OtherBean");
+}
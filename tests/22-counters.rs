@@ -0,0 +1,92 @@
+//!
+//! This test is also an example of computing rates of counter attributes.
+//!
+//! This test:
+//!
+//!   1. Marks counters explicitly and from MBean descriptors on a mock server.
+//!   2. Computes deltas and per-second rates from samples.
+//!   3. Detects counter resets.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use jmx::Counters;
+use jmx::MBeanAttribute;
+use jmx::MBeanInfo;
+use jmx::Sample;
+use jmx::dump::AttributeState;
+use jmx::mock::MockMBeanClient;
+
+
+#[test]
+fn counters() {
+    run_test();
+}
+
+fn sample(name: &str, attribute: &str, value: u64, seconds: u64) -> Sample {
+    Sample {
+        attribute: attribute.into(),
+        name: name.into(),
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        value: AttributeState::Value(serde_json::Value::from(value)),
+    }
+}
+
+fn run_test() {
+    let gc = "java.lang:type=GarbageCollector,name=G1 Young Generation";
+    let mut counters = Counters::new();
+    counters.mark("java.lang:type=GarbageCollector,*", "CollectionTime").unwrap();
+    assert!(counters.is_counter(gc, "CollectionTime"));
+    assert!(!counters.is_counter(gc, "CollectionCount"));
+    assert!(!counters.is_counter("java.lang:type=Threading", "CollectionTime"));
+
+    // Rates need two samples.
+    assert_eq!(counters.update(&sample(gc, "CollectionTime", 100, 10)), None);
+    let rate = counters.update(&sample(gc, "CollectionTime", 150, 20)).unwrap();
+    assert_eq!(rate.delta, 50.0);
+    assert_eq!(rate.rate, 5.0);
+    assert!(!rate.reset);
+    assert_eq!(counters.update(&sample(gc, "CollectionCount", 5, 20)), None);
+
+    // Errors are skipped and resets detected.
+    let mut error = sample(gc, "CollectionTime", 0, 25);
+    error.value = AttributeState::Error("boom".into());
+    assert_eq!(counters.update(&error), None);
+    let rate = counters.update(&sample(gc, "CollectionTime", 30, 30)).unwrap();
+    assert!(rate.reset);
+    assert_eq!(rate.delta, 30.0);
+    assert_eq!(rate.rate, 3.0);
+
+    // Learn counters from descriptors.
+    let server = MockMBeanClient::new();
+    let mut descriptor = BTreeMap::new();
+    descriptor.insert("metricType".to_string(), "counter".to_string());
+    let info = MBeanInfo {
+        attributes: vec![
+            MBeanAttribute {
+                descriptor,
+                is_readable: true,
+                name: "TotalStartedThreadCount".into(),
+                ..Default::default()
+            },
+            MBeanAttribute {
+                is_readable: true,
+                name: "ThreadCount".into(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    server.register_mbean("java.lang:type=Threading", info).unwrap();
+    counters.learn(&server, "java.lang:*").unwrap();
+    let threading = "java.lang:type=Threading";
+    assert!(counters.is_counter(threading, "TotalStartedThreadCount"));
+    assert!(!counters.is_counter(threading, "ThreadCount"));
+    assert_eq!(counters.update(&sample(threading, "TotalStartedThreadCount", 10, 0)), None);
+    let rate = counters.update(&sample(threading, "TotalStartedThreadCount", 12, 4)).unwrap();
+    assert_eq!(rate.rate, 0.5);
+}
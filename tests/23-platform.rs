@@ -0,0 +1,148 @@
+//!
+//! This test is also an example of reading the platform MXBeans of a JVM.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Reads memory, threading, runtime, OS, class loading and compilation MXBeans.
+//!   3. Enumerates garbage collectors, memory pools and buffer pools.
+//!   4. Invokes platform MXBean operations.
+//!   5. Reads and sets VM options and dumps the heap.
+//!
+extern crate jmx;
+
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::platform::MemoryType;
+use jmx::platform::Platform;
+use jmx::platform::VMOptionOrigin;
+
+
+static JMX_PORT: u16 = 1638;
+
+
+#[test]
+fn platform() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let platform = Platform::new(&client);
+
+    // Memory.
+    let memory = platform.memory();
+    let heap = memory.heap_memory_usage().unwrap();
+    assert!(heap.used > 0);
+    assert!(heap.committed >= heap.used);
+    assert!(memory.non_heap_memory_usage().unwrap().used > 0);
+    assert!(!memory.is_verbose().unwrap());
+    memory.gc().unwrap();
+
+    // Threading.
+    let threading = platform.threading();
+    let count = threading.thread_count().unwrap();
+    assert!(count > 0);
+    assert!(threading.peak_thread_count().unwrap() >= count);
+    assert!(threading.total_started_thread_count().unwrap() >= i64::from(count));
+    let ids = threading.all_thread_ids().unwrap();
+    assert!(!ids.is_empty());
+    assert!(threading.thread_cpu_time(ids[0]).unwrap() >= -1);
+    assert!(threading.find_deadlocked_threads().unwrap().is_empty());
+    threading.reset_peak_thread_count().unwrap();
+
+    // Runtime.
+    let runtime = platform.runtime();
+    assert!(runtime.name().unwrap().contains('@'));
+    assert!(runtime.uptime().unwrap() > 0);
+    let port = JMX_PORT.to_string();
+    assert!(runtime.input_arguments().unwrap().iter().any(|arg| arg.contains(&port)));
+    let properties = runtime.system_properties().unwrap();
+    assert!(properties.contains_key("java.version"));
+
+    // Operating system.
+    let os = platform.operating_system();
+    assert!(os.available_processors().unwrap() > 0);
+    assert!(!os.arch().unwrap().is_empty());
+    assert!(os.total_physical_memory_size().unwrap() > 0);
+    assert!(os.process_cpu_time().unwrap() > 0);
+
+    // Class loading and compilation.
+    let class_loading = platform.class_loading();
+    assert!(class_loading.loaded_class_count().unwrap() > 0);
+    assert!(class_loading.total_loaded_class_count().unwrap() > 0);
+    assert!(!platform.compilation().name().unwrap().is_empty());
+
+    // Garbage collectors, after the collection requested above.
+    let collectors = platform.garbage_collectors().unwrap();
+    assert!(!collectors.is_empty());
+    let total: i64 = collectors.iter().map(|gc| gc.collection_count().unwrap()).sum();
+    assert!(total > 0);
+    let collected = collectors.iter()
+        .find(|gc| gc.collection_count().unwrap() > 0)
+        .unwrap();
+    let info = collected.last_gc_info().unwrap().expect("GC info missing after a collection");
+    assert!(info.id > 0);
+    assert!(!info.memory_usage_after_gc.is_empty());
+    assert!(!collected.memory_pool_names().unwrap().is_empty());
+
+    // Memory and buffer pools.
+    let pools = platform.memory_pools().unwrap();
+    assert!(pools.iter().any(|pool| pool.memory_type().unwrap() == MemoryType::Heap));
+    assert!(pools.iter().any(|pool| pool.memory_type().unwrap() == MemoryType::NonHeap));
+    let non_heap = pools.iter()
+        .find(|pool| pool.memory_type().unwrap() == MemoryType::NonHeap)
+        .unwrap();
+    assert_eq!(non_heap.collection_usage().unwrap(), None);
+    let buffers = platform.buffer_pools().unwrap();
+    assert!(buffers.iter().any(|pool| pool.name().unwrap() == "direct"));
+    assert!(buffers[0].count().unwrap() >= 0);
+
+    // HotSpot diagnostics.
+    let hotspot = platform.hotspot_diagnostic();
+    let option = hotspot.vm_option("HeapDumpOnOutOfMemoryError").unwrap();
+    assert_eq!(option.value, "false");
+    assert_eq!(option.origin, VMOptionOrigin::Default);
+    assert!(option.writeable);
+    hotspot.set_vm_option("HeapDumpOnOutOfMemoryError", "true").unwrap();
+    let option = hotspot.vm_option("HeapDumpOnOutOfMemoryError").unwrap();
+    assert_eq!(option.value, "true");
+    assert_eq!(option.origin, VMOptionOrigin::Management);
+    assert!(hotspot.set_vm_option("UseCompressedOops", "false").is_err());
+    assert!(hotspot.vm_option("NoSuchOption").is_err());
+    let options = hotspot.diagnostic_options().unwrap();
+    assert!(options.iter().any(|option| option.name == "HeapDumpOnOutOfMemoryError"));
+    assert!(options.iter().all(|option| option.writeable));
+
+    let path = ::std::env::temp_dir().join(format!("jmx-test-{}.hprof", JMX_PORT));
+    let _ = fs::remove_file(&path);
+    hotspot.dump_heap(path.to_str().unwrap(), true).unwrap();
+    assert!(fs::metadata(&path).unwrap().len() > 0);
+    assert!(hotspot.dump_heap(path.to_str().unwrap(), true).is_err());
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,124 @@
+//!
+//! This test is also an example of capturing a thread dump of a JVM.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Dumps all threads, with their locked monitors and synchronizers.
+//!   3. Renders the dump like `jstack`.
+//!   4. Looks for deadlocked threads.
+//!   5. Renders a thread waiting on and holding locks.
+//!
+extern crate jmx;
+#[macro_use]
+extern crate serde_json;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::thread_dump::ThreadInfo;
+use jmx::thread_dump::ThreadState;
+use jmx::thread_dump::find_deadlocked_threads;
+use jmx::thread_dump::render_jstack;
+use jmx::thread_dump::thread_dump;
+
+
+static JMX_PORT: u16 = 1639;
+
+
+#[test]
+fn thread_dumps() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+
+    // The main thread of the server is sleeping.
+    let threads = thread_dump(&client, true, true).unwrap();
+    let main = threads.iter()
+        .find(|thread| thread.thread_name == "main")
+        .expect("Main thread not found");
+    assert_eq!(main.thread_state, ThreadState::TimedWaiting);
+    assert!(main.stack_trace.iter().any(|frame| {
+        frame.class_name == "java.lang.Thread" && frame.method_name.starts_with("sleep")
+    }));
+    assert!(main.stack_trace.iter().any(|frame| frame.class_name == "TestServer"));
+
+    let dump = render_jstack(&threads);
+    assert!(dump.starts_with("Full thread dump:"));
+    assert!(dump.contains(&format!("\"main\" #{}", main.thread_id)));
+    assert!(dump.contains("   java.lang.Thread.State: TIMED_WAITING"));
+    assert!(dump.contains("\tat TestServer.main(TestServer.java:"));
+    assert!(find_deadlocked_threads(&client).unwrap().is_empty());
+
+    // Render locks from a known thread.
+    let thread: ThreadInfo = serde_json::from_value(json!({
+        "blockedCount": 1,
+        "blockedTime": -1,
+        "daemon": true,
+        "inNative": false,
+        "lockInfo": {"className": "java.lang.Object", "identityHashCode": 0x1234},
+        "lockName": "java.lang.Object@1234",
+        "lockOwnerId": 12,
+        "lockOwnerName": "owner",
+        "lockedMonitors": [{
+            "className": "java.lang.String",
+            "identityHashCode": 0xabcd,
+            "lockedStackDepth": 1,
+        }],
+        "lockedSynchronizers": [],
+        "priority": 5,
+        "stackTrace": [
+            {
+                "className": "Worker", "fileName": "Worker.java",
+                "lineNumber": 10, "methodName": "work"
+            },
+            {
+                "className": "Worker", "fileName": "Worker.java",
+                "lineNumber": 5, "methodName": "run"
+            },
+        ],
+        "suspended": false,
+        "threadId": 13,
+        "threadName": "worker",
+        "threadState": "BLOCKED",
+        "waitedCount": 0,
+        "waitedTime": -1,
+    })).unwrap();
+    let expected = concat!(
+        "\"worker\" #13 daemon prio=5\n",
+        "   java.lang.Thread.State: BLOCKED\n",
+        "\tat Worker.work(Worker.java:10)\n",
+        "\t- waiting to lock <0x00001234> (a java.lang.Object)\n",
+        "\tat Worker.run(Worker.java:5)\n",
+        "\t- locked <0x0000abcd> (a java.lang.String)\n",
+        "\n",
+        "   Locked ownable synchronizers:\n",
+        "\t- None\n",
+    );
+    assert_eq!(thread.render(), expected);
+}
@@ -0,0 +1,164 @@
+//!
+//! This test is also an example of running `jcmd` commands over JMX.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Lists the diagnostic commands and their arguments.
+//!   3. Runs diagnostic commands and parses a class histogram.
+//!   4. Passes arguments to a mock server according to the operation signatures.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use jmx::ErrorKind;
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanInfo;
+use jmx::MBeanOperation;
+use jmx::MBeanParameter;
+use jmx::diagnostic_command::DIAGNOSTIC_COMMAND_MBEAN_NAME;
+use jmx::diagnostic_command::DiagnosticCommandMBean;
+use jmx::diagnostic_command::operation_name;
+use jmx::diagnostic_command::parse_class_histogram;
+use jmx::mock::MockMBeanClient;
+
+
+static JMX_PORT: u16 = 1640;
+
+
+#[test]
+fn diagnostic_commands() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    assert_eq!(operation_name("GC.class_histogram"), "gcClassHistogram");
+    assert_eq!(operation_name("VM.system_properties"), "vmSystemProperties");
+    assert_eq!(operation_name("Thread.print"), "threadPrint");
+
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let dcmd = DiagnosticCommandMBean::new(&client);
+
+    // List commands.
+    let commands = dcmd.commands().unwrap();
+    let histogram = commands.iter()
+        .find(|command| command.name == "GC.class_histogram")
+        .expect("GC.class_histogram command not found");
+    assert_eq!(histogram.operation, "gcClassHistogram");
+    assert!(histogram.enabled);
+    assert!(histogram.help.contains("Syntax"));
+    let all = histogram.arguments.iter()
+        .find(|argument| argument.name == "-all")
+        .expect("-all argument not found");
+    assert!(all.is_option);
+    assert!(!all.is_mandatory);
+    assert_eq!(all.type_name, "BOOLEAN");
+    assert_eq!(all.position, -1);
+    assert!(commands.iter().any(|command| command.name == "Thread.print"));
+
+    // Run commands.
+    let rows = dcmd.class_histogram(false).unwrap();
+    assert_eq!(rows[0].rank, 1);
+    let string = rows.iter()
+        .find(|row| row.class_name == "java.lang.String")
+        .expect("java.lang.String not in the histogram");
+    assert!(string.instances > 0);
+    assert!(string.bytes > 0);
+    assert!(!dcmd.class_histogram(true).unwrap().is_empty());
+    assert!(dcmd.thread_print(true).unwrap().contains("\"main\""));
+    assert!(dcmd.vm_flags(false).unwrap().contains("-XX:"));
+    assert!(dcmd.vm_system_properties().unwrap().contains("java.version="));
+    assert!(!dcmd.gc_heap_info().unwrap().is_empty());
+    assert!(dcmd.execute("VM.version", &[] as &[&str]).unwrap().contains("JDK"));
+    assert!(dcmd.execute("No.such_command", &[] as &[&str]).is_err());
+
+    // Parse histograms of old and new JVMs.
+    let text = concat!(
+        " num     #instances         #bytes  class name (module)\n",
+        "-------------------------------------------------------\n",
+        "   1:          5398         601432  [B (java.base@17.0.8)\n",
+        "   2:           812          98344  MyClass\n",
+        "Total          6210         699776\n",
+    );
+    let rows = parse_class_histogram(text).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].class_name, "[B");
+    assert_eq!(rows[0].module, Some("java.base@17.0.8".to_string()));
+    assert_eq!(rows[1].instances, 812);
+    assert_eq!(rows[1].bytes, 98344);
+    assert_eq!(rows[1].module, None);
+    assert!(parse_class_histogram("   1:  many  98344  MyClass\n").is_err());
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+    let operation = |name: &str, signature: Vec<MBeanParameter>| MBeanOperation {
+        name: name.into(),
+        return_type: "java.lang.String".into(),
+        signature,
+        ..Default::default()
+    };
+    let arguments = MBeanParameter {
+        name: "arguments".into(),
+        type_name: "[Ljava.lang.String;".into(),
+        ..Default::default()
+    };
+    let info = MBeanInfo {
+        operations: vec![
+            operation("gcHeapInfo", Vec::new()),
+            operation("threadPrint", vec![arguments]),
+        ],
+        ..Default::default()
+    };
+    server.register_mbean(DIAGNOSTIC_COMMAND_MBEAN_NAME, info).unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    for name in &["gcHeapInfo", "threadPrint"] {
+        let calls = Arc::clone(&calls);
+        server.register_operation(DIAGNOSTIC_COMMAND_MBEAN_NAME, *name, move |params| {
+            calls.lock().unwrap().push(params);
+            Err(ErrorKind::MockFailure("command failed".into()).into())
+        }).unwrap();
+    }
+    let dcmd = DiagnosticCommandMBean::new(&server);
+
+    // Failures of commands without arguments are reported as they are.
+    let error = dcmd.gc_heap_info().unwrap_err();
+    assert_eq!(error.to_string(), "mock client failure: command failed");
+    assert!(dcmd.thread_print(false).is_err());
+    assert!(dcmd.thread_print(true).is_err());
+    assert_eq!(*calls.lock().unwrap(), vec![
+        Vec::new(),
+        vec![Value::Array(Vec::new())],
+        vec![Value::Array(vec![Value::from("-l")])],
+    ]);
+}
@@ -0,0 +1,109 @@
+//!
+//! This test is also an example of capturing a flight recording over JMX.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Lists the predefined recording configurations.
+//!   3. Starts a recording with options and custom settings.
+//!   4. Stops the recording and downloads it into a local file.
+//!   5. Closes the recording.
+//!
+extern crate jmx;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::jfr::FlightRecorderMXBean;
+use jmx::jfr::RecordingOptions;
+use jmx::jfr::RecordingState;
+
+
+static JMX_PORT: u16 = 1641;
+
+
+#[test]
+fn flight_recorder() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let jfr = FlightRecorderMXBean::new(&client);
+
+    // Configurations.
+    let configurations = jfr.configurations().unwrap();
+    let profile = configurations.iter()
+        .find(|configuration| configuration.name == "profile")
+        .expect("Profile configuration not found");
+    assert!(profile.contents.contains("<configuration"));
+    assert!(!profile.settings.is_empty());
+
+    // Start a recording, durations are rounded up to whole seconds.
+    let options = RecordingOptions::default()
+        .name("test")
+        .max_age(Duration::from_millis(3_599_500))
+        .max_size(10 * 1024 * 1024);
+    let id = jfr.start_new_recording("default", &options).unwrap();
+    let recording_options = jfr.recording_options(id).unwrap();
+    assert_eq!(recording_options["name"], "test");
+    assert_eq!(recording_options["maxSize"], "10485760");
+    assert!(!recording_options.contains_key("destination"));
+    let mut settings = BTreeMap::new();
+    settings.insert("jdk.CPULoad#enabled".to_string(), "true".to_string());
+    settings.insert("jdk.CPULoad#period".to_string(), "100 ms".to_string());
+    jfr.set_recording_settings(id, &settings).unwrap();
+    assert_eq!(jfr.recording_settings(id).unwrap(), settings);
+
+    let recording = jfr.recordings().unwrap().into_iter()
+        .find(|recording| recording.id == id)
+        .expect("Recording not found");
+    assert_eq!(recording.name, "test");
+    assert_eq!(recording.state, RecordingState::Running);
+    assert_eq!(recording.max_age, 3600);
+    assert!(recording.start_time > 0);
+
+    // Stop and download the recording.
+    thread::sleep(Duration::from_millis(500));
+    assert!(jfr.stop_recording(id).unwrap());
+    assert!(jfr.stop_recording(id).is_err());
+    let path = ::std::env::temp_dir().join(format!("jmx-test-{}.jfr", JMX_PORT));
+    let size = jfr.download(id, &path).unwrap();
+    assert!(size > 0);
+    assert_eq!(fs::metadata(&path).unwrap().len(), size);
+    let mut magic = [0; 4];
+    fs::File::open(&path).unwrap().read_exact(&mut magic).unwrap();
+    assert_eq!(&magic, b"FLR\0");
+    fs::remove_file(&path).unwrap();
+
+    // Close the recording.
+    jfr.close_recording(id).unwrap();
+    assert!(jfr.recordings().unwrap().iter().all(|recording| recording.id != id));
+    assert!(jfr.download(id, &path).is_err());
+}
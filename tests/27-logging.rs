@@ -0,0 +1,186 @@
+//!
+//! This test is also an example of changing logger levels of a live JVM.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server, which only has `java.util.logging`.
+//!   2. Sets and resets the level of a logger.
+//!   3. Detects Log4j 2 and Logback on a mock server.
+//!   4. Sets the level of a logger with all the frameworks at once.
+//!   5. Configures a new Log4j 2 logger.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use jmx::MBeanAddress;
+use jmx::MBeanAttribute;
+use jmx::MBeanClient;
+use jmx::MBeanInfo;
+use jmx::logging::Level;
+use jmx::logging::LoggerControl;
+use jmx::logging::LoggingFramework;
+use jmx::mock::MockMBeanClient;
+
+
+static JMX_PORT: u16 = 1642;
+
+
+#[test]
+fn logging() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let control = LoggerControl::detect(&client).unwrap();
+    assert_eq!(control.frameworks(), &[LoggingFramework::JavaUtilLogging]);
+
+    let loggers = control.loggers().unwrap();
+    let root = loggers.iter().find(|logger| logger.name.is_empty()).expect("Root logger missing");
+    assert_eq!(root.level, Some("INFO".to_string()));
+    assert!(loggers.iter().any(|logger| logger.name == "global"));
+
+    let changed = control.set_level("global", Some(Level::Debug)).unwrap();
+    assert_eq!(changed, vec![LoggingFramework::JavaUtilLogging]);
+    let logger = control.logger("global").unwrap();
+    assert_eq!(logger[0].level, Some("FINE".to_string()));
+    control.set_level("global", None).unwrap();
+    assert_eq!(control.logger("global").unwrap()[0].level, None);
+    assert!(control.set_level("com.example.missing", Some(Level::Debug)).is_err());
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+
+    // Log4j 2 logger configurations, including an async logger ring buffer to skip.
+    for (name, level) in &[("", "ERROR"), ("com.example", "INFO")] {
+        let mbean = format!(
+            "org.apache.logging.log4j2:type=app,component=Loggers,name={}",
+            if name.is_empty() { "root" } else { name }
+        );
+        let info = MBeanInfo {
+            attributes: vec![
+                MBeanAttribute { is_readable: true, name: "Name".into(), ..Default::default() },
+                MBeanAttribute {
+                    is_readable: true,
+                    is_writable: true,
+                    name: "Level".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        server.register_mbean(mbean.as_str(), info).unwrap();
+        server.register_attribute(mbean.as_str(), "Name", name).unwrap();
+        server.register_attribute(mbean.as_str(), "Level", level).unwrap();
+    }
+    server.register_mbean(
+        "org.apache.logging.log4j2:type=app,component=Loggers,name=root,subtype=RingBuffer",
+        MBeanInfo::default()
+    ).unwrap();
+
+    // Log4j 2 logger context with an XML configuration.
+    let config = Arc::new(Mutex::new(concat!(
+        "<Configuration><Loggers><Root level=\"error\"/>",
+        "<Logger name=\"com.example\" level=\"info\"/></Loggers></Configuration>"
+    ).to_string()));
+    let context = "org.apache.logging.log4j2:type=app";
+    let text = Arc::clone(&config);
+    server.register_attribute_fn(context, "ConfigText", move || {
+        Ok(Value::from(text.lock().unwrap().clone()))
+    }).unwrap();
+    let text = Arc::clone(&config);
+    server.register_operation(context, "setConfigText", move |params| {
+        *text.lock().unwrap() = params[0].as_str().unwrap().to_string();
+        Ok(Value::Null)
+    }).unwrap();
+
+    // Logback configurator.
+    let logback = concat!(
+        "ch.qos.logback.classic:",
+        "Name=default,Type=ch.qos.logback.classic.jmx.JMXConfigurator"
+    );
+    let levels = Arc::new(Mutex::new(BTreeMap::new()));
+    levels.lock().unwrap().insert("ROOT".to_string(), "WARN".to_string());
+    let list = Arc::clone(&levels);
+    server.register_attribute_fn(logback, "LoggerList", move || {
+        let names: Vec<String> = list.lock().unwrap().keys().cloned().collect();
+        Ok(Value::from(names))
+    }).unwrap();
+    let get = Arc::clone(&levels);
+    server.register_operation(logback, "getLoggerLevel", move |params| {
+        let name = params[0].as_str().unwrap();
+        Ok(Value::from(get.lock().unwrap().get(name).cloned().unwrap_or_default()))
+    }).unwrap();
+    let set = Arc::clone(&levels);
+    server.register_operation(logback, "setLoggerLevel", move |params| {
+        let name = params[0].as_str().unwrap().to_string();
+        let level = params[1].as_str().unwrap();
+        let level = if level == "null" { String::new() } else { level.to_string() };
+        set.lock().unwrap().insert(name, level);
+        Ok(Value::Null)
+    }).unwrap();
+
+    let control = LoggerControl::detect(&server).unwrap();
+    assert_eq!(control.frameworks(), &[LoggingFramework::Log4j2, LoggingFramework::Logback]);
+    let roots = control.logger("").unwrap();
+    assert_eq!(roots.len(), 2);
+    assert_eq!(roots[0].level, Some("ERROR".to_string()));
+    assert_eq!(roots[1].level, Some("WARN".to_string()));
+
+    // Set the level with both frameworks.
+    let changed = control.set_level("com.example", Some(Level::Debug)).unwrap();
+    assert_eq!(changed, vec![LoggingFramework::Log4j2, LoggingFramework::Logback]);
+    let loggers = control.logger("com.example").unwrap();
+    assert_eq!(loggers.len(), 2);
+    assert!(loggers.iter().all(|logger| logger.level == Some("DEBUG".to_string())));
+
+    // Log4j 2 configurations always have a level.
+    let changed = control.set_level("com.example", None).unwrap();
+    assert_eq!(changed, vec![LoggingFramework::Logback]);
+    assert_eq!(levels.lock().unwrap()["com.example"], "");
+
+    // Log4j 2 loggers without a configuration are added to the XML configuration.
+    let changed = control.set_level("com.example.new", Some(Level::Warn)).unwrap();
+    assert_eq!(changed, vec![LoggingFramework::Log4j2, LoggingFramework::Logback]);
+    assert!(config.lock().unwrap().ends_with(
+        "<Logger name=\"com.example.new\" level=\"WARN\"/>\n</Loggers></Configuration>"
+    ));
+
+    // Levels of all frameworks.
+    assert_eq!(Level::parse("fine"), Some(Level::Debug));
+    assert_eq!(Level::parse("WARNING"), Some(Level::Warn));
+    assert_eq!(Level::parse("verbose"), None);
+    assert_eq!(Level::Error.name(LoggingFramework::JavaUtilLogging), "SEVERE");
+    assert_eq!(Level::Error.name(LoggingFramework::Logback), "ERROR");
+}
@@ -0,0 +1,162 @@
+//!
+//! This test is also an example of low memory alerts driven by notifications.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Sets tiny usage thresholds on the heap memory pools.
+//!   3. Runs the garbage collector and waits for the threshold notifications.
+//!   4. Delivers notifications emitted by a mock server to listeners.
+//!
+extern crate jmx;
+#[macro_use]
+extern crate serde_json;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanClientTrait;
+use jmx::MBeanInfo;
+use jmx::Notification;
+use jmx::NotificationListener;
+use jmx::mock::MockMBeanClient;
+use jmx::platform::MEMORY_MXBEAN_NAME;
+use jmx::platform::MEMORY_THRESHOLD_EXCEEDED;
+use jmx::platform::MemoryThresholdKind;
+use jmx::platform::MemoryType;
+use jmx::platform::Platform;
+
+
+static JMX_PORT: u16 = 1643;
+
+
+#[test]
+fn memory_thresholds() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let platform = Platform::new(&client);
+    let memory = platform.memory();
+    let listener = memory.threshold_listener().unwrap();
+
+    // Any used heap crosses a threshold of one byte.
+    let mut pools = Vec::new();
+    for pool in platform.memory_pools().unwrap() {
+        if pool.memory_type().unwrap() != MemoryType::Heap {
+            continue;
+        }
+        if pool.is_collection_usage_threshold_supported().unwrap() {
+            pool.set_collection_usage_threshold(1).unwrap();
+            assert_eq!(pool.collection_usage_threshold().unwrap(), 1);
+            pools.push(pool);
+        }
+    }
+    assert!(!pools.is_empty(), "No heap pool supports collection usage thresholds");
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    while events.is_empty() && Instant::now() < deadline {
+        memory.gc().unwrap();
+        events.extend(listener.poll(Duration::from_millis(500)).unwrap());
+    }
+    assert!(!events.is_empty(), "No memory threshold notification received");
+    let event = &events[0];
+    assert_eq!(event.kind, MemoryThresholdKind::CollectionUsage);
+    assert!(event.count >= 1);
+    assert!(event.usage.used >= 1);
+    assert!(event.timestamp > 0);
+    let names: Vec<String> = pools.iter().map(|pool| pool.name().unwrap()).collect();
+    assert!(names.contains(&event.pool_name));
+
+    let pool = pools.iter().find(|pool| pool.name().unwrap() == event.pool_name).unwrap();
+    assert!(pool.collection_usage_threshold_count().unwrap() >= 1);
+    assert!(pool.is_collection_usage_threshold_exceeded().unwrap());
+    for pool in &pools {
+        pool.set_collection_usage_threshold(0).unwrap();
+    }
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+    server.register_mbean(MEMORY_MXBEAN_NAME, MBeanInfo::default()).unwrap();
+    server.register_mbean("com.example:type=Other", MBeanInfo::default()).unwrap();
+    let memory = Platform::new(&server).memory();
+    let listener = memory.threshold_listener().unwrap();
+    assert!(listener.poll(Duration::from_millis(10)).unwrap().is_empty());
+
+    // Only the listeners of the source and type receive notifications.
+    let notification = Notification {
+        notification_type: MEMORY_THRESHOLD_EXCEEDED.to_string(),
+        sequence_number: 1,
+        source: MEMORY_MXBEAN_NAME.to_string(),
+        timestamp: 1234,
+        user_data: json!({
+            "count": 2,
+            "poolName": "G1 Old Gen",
+            "usage": {"committed": 100, "init": 10, "max": 1000, "used": 90},
+        }),
+        ..Default::default()
+    };
+    assert_eq!(server.emit_notification(notification.clone()), 1);
+    let other = Notification {
+        notification_type: "com.example.other".to_string(),
+        ..notification.clone()
+    };
+    assert_eq!(server.emit_notification(other), 0);
+
+    let events = listener.poll(Duration::from_secs(1)).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].count, 2);
+    assert_eq!(events[0].kind, MemoryThresholdKind::Usage);
+    assert_eq!(events[0].pool_name, "G1 Old Gen");
+    assert_eq!(events[0].timestamp, 1234);
+    assert_eq!(events[0].usage.used, 90);
+
+    // Notifications emitted by another thread wake up polls.
+    let emitter = server.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        emitter.emit_notification(Notification {
+            source: "com.example:type=Other".to_string(),
+            ..notification
+        });
+    });
+    let other = NotificationListener::new(&server, "com.example:*", Vec::new()).unwrap();
+    let received = other.poll(Duration::from_secs(5)).unwrap();
+    handle.join().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].source, "com.example:type=Other");
+
+    // Listeners are removed when dropped.
+    let id = other.id();
+    drop(other);
+    assert!(server.poll_notifications(id, Duration::from_millis(10)).is_err());
+    assert!(NotificationListener::new(&server, "com.example:type=Missing", Vec::new()).is_err());
+}
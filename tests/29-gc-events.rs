@@ -0,0 +1,118 @@
+//!
+//! This test is also an example of measuring each garbage collection pause.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server.
+//!   2. Runs the garbage collector and waits for its notification.
+//!   3. Decodes garbage collection notifications emitted by a mock server.
+//!
+extern crate jmx;
+#[macro_use]
+extern crate serde_json;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanInfo;
+use jmx::Notification;
+use jmx::mock::MockMBeanClient;
+use jmx::platform::GC_NOTIFICATION;
+use jmx::platform::Platform;
+
+
+static JMX_PORT: u16 = 1644;
+
+
+#[test]
+fn gc_events() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let platform = Platform::new(&client);
+    let listener = platform.gc_listener().unwrap();
+    platform.memory().gc().unwrap();
+
+    let event = listener.events(Duration::from_millis(500))
+        .map(|event| event.unwrap())
+        .find(|event| event.cause == "System.gc()")
+        .unwrap();
+    let collectors: Vec<String> = platform.garbage_collectors().unwrap().iter()
+        .map(|collector| collector.name().unwrap())
+        .collect();
+    assert!(collectors.contains(&event.gc_name));
+    assert!(event.action.starts_with("end of "));
+    assert!(event.info.id >= 1);
+    assert!(event.info.end_time >= event.info.start_time);
+    assert!(event.info.duration >= 0);
+    assert!(!event.info.memory_usage_before_gc.is_empty());
+    assert!(!event.info.memory_usage_after_gc.is_empty());
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+    let young = "java.lang:type=GarbageCollector,name=G1 Young Generation";
+    server.register_mbean(young, MBeanInfo::default()).unwrap();
+    let platform = Platform::new(&server);
+    let listener = platform.gc_listener().unwrap();
+
+    let usage = |used: i64| json!({"committed": 100, "init": 0, "max": -1, "used": used});
+    server.emit_notification(Notification {
+        notification_type: GC_NOTIFICATION.to_string(),
+        source: young.to_string(),
+        timestamp: 1234,
+        user_data: json!({
+            "gcAction": "end of minor GC",
+            "gcCause": "G1 Evacuation Pause",
+            "gcName": "G1 Young Generation",
+            "gcInfo": {
+                "GcThreadCount": 4,
+                "duration": 3,
+                "endTime": 1003,
+                "id": 7,
+                "memoryUsageAfterGc": [{"key": "G1 Eden Space", "value": usage(0)}],
+                "memoryUsageBeforeGc": [{"key": "G1 Eden Space", "value": usage(80)}],
+                "startTime": 1000,
+            },
+        }),
+        ..Default::default()
+    });
+
+    let events = listener.poll(Duration::from_secs(1)).unwrap();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.action, "end of minor GC");
+    assert_eq!(event.cause, "G1 Evacuation Pause");
+    assert_eq!(event.gc_name, "G1 Young Generation");
+    assert_eq!(event.info.duration, 3);
+    assert_eq!(event.info.id, 7);
+    assert_eq!(event.info.memory_usage_before_gc["G1 Eden Space"].used, 80);
+    assert_eq!(event.info.memory_usage_after_gc["G1 Eden Space"].used, 0);
+    assert!(listener.poll(Duration::from_millis(10)).unwrap().is_empty());
+}
@@ -0,0 +1,140 @@
+//!
+//! This test is also an example of discovering MBeans incrementally.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server and mirrors its MBeans.
+//!   2. Queries the mirror with patterns.
+//!   3. Follows registrations and unregistrations on a mock server.
+//!   4. Scrapes metrics for the MBeans of the mirror.
+//!
+extern crate jmx;
+
+use std::cell::RefCell;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanAttribute;
+use jmx::MBeanClient;
+use jmx::MBeanClientTrait;
+use jmx::ListenerId;
+use jmx::MBeanInfo;
+use jmx::ObjectName;
+use jmx::Registry;
+use jmx::RegistryChange;
+use jmx::exporter::Exporter;
+use jmx::mock::MockMBeanClient;
+use jmx::registry::MBEAN_SERVER_DELEGATE_NAME;
+
+
+static JMX_PORT: u16 = 1645;
+
+
+#[test]
+fn registry() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanClient::connect(address).expect("Failed to connect to the JMX server");
+    let mut registry = Registry::new(&client).unwrap();
+    assert_eq!(registry.len(), client.query_names("*:*", "").unwrap().len());
+    assert!(registry.contains(&ObjectName::parse("java.lang:type=Memory").unwrap()));
+    assert!(registry.contains(&ObjectName::parse(MBEAN_SERVER_DELEGATE_NAME).unwrap()));
+
+    let pattern = ObjectName::parse("java.lang:type=MemoryPool,*").unwrap();
+    let mut pools: Vec<String> = registry.query(&pattern).iter()
+        .map(|name| name.to_string())
+        .collect();
+    let mut expected = client.query_names("java.lang:type=MemoryPool,*", "").unwrap();
+    pools.sort();
+    expected.sort();
+    assert_eq!(pools, expected);
+
+    assert!(registry.refresh(Duration::from_millis(100)).unwrap().is_empty());
+    assert!(registry.resync().unwrap().is_empty());
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+    server.register_mbean(MBEAN_SERVER_DELEGATE_NAME, MBeanInfo::default()).unwrap();
+    let info = MBeanInfo {
+        attributes: vec![
+            MBeanAttribute { is_readable: true, name: "Size".into(), ..Default::default() },
+        ],
+        ..Default::default()
+    };
+    server.register_mbean("com.example:type=Cache,name=users", info.clone()).unwrap();
+    server.register_attribute("com.example:type=Cache,name=users", "Size", 10).unwrap();
+
+    let seen = RefCell::new(Vec::new());
+    let mut registry = Registry::new(&server).unwrap();
+    registry.on_change(|change| seen.borrow_mut().push(change.clone()));
+    assert_eq!(registry.len(), 2);
+
+    // Changes are applied on refresh, in order.
+    let orders = "com.example:type=Cache,name=orders";
+    server.register_mbean(orders, info).unwrap();
+    server.register_attribute(orders, "Size", 20).unwrap();
+    server.unregister_mbean("com.example:type=Cache,name=users").unwrap();
+    let changes = registry.refresh(Duration::from_secs(1)).unwrap();
+    let orders = ObjectName::parse(orders).unwrap();
+    let users = ObjectName::parse("com.example:type=Cache,name=users").unwrap();
+    assert_eq!(changes, vec![
+        RegistryChange::Registered(orders.clone()),
+        RegistryChange::Unregistered(users.clone()),
+    ]);
+    assert_eq!(*seen.borrow(), changes);
+    assert!(registry.contains(&orders));
+    assert!(!registry.contains(&users));
+
+    let pattern = ObjectName::parse("com.example:type=Cache,*").unwrap();
+    assert_eq!(registry.query(&pattern), vec![&orders]);
+    assert_eq!(registry.query(&orders), vec![&orders]);
+    assert!(registry.query(&users).is_empty());
+
+    // The exporter scrapes the MBeans of the mirror.
+    let exporter = Exporter::from_yaml("whitelistObjectNames: ['com.example:*']").unwrap();
+    let metrics = exporter.scrape_registry(&registry).unwrap();
+    assert_eq!(metrics.value("com_example_Cache_Size", &[("name", "orders")]), Some(20.0));
+    assert_eq!(metrics.value("com_example_Cache_Size", &[("name", "users")]), None);
+
+    // Resync lists the server again, for example after lost notifications.
+    assert!(registry.resync().unwrap().is_empty());
+    assert!(registry.refresh(Duration::from_millis(10)).unwrap().is_empty());
+
+    // Refresh fails once the listener is gone, as after a reconnection,
+    // and resync listens again. The first resync added the second listener.
+    server.remove_notification_listener(ListenerId(2)).unwrap();
+    assert!(registry.refresh(Duration::from_millis(10)).is_err());
+    server.register_mbean(users.to_string(), MBeanInfo::default()).unwrap();
+    assert_eq!(registry.resync().unwrap(), vec![RegistryChange::Registered(users.clone())]);
+    server.unregister_mbean(users.to_string()).unwrap();
+    let changes = registry.refresh(Duration::from_secs(1)).unwrap();
+    assert_eq!(changes, vec![RegistryChange::Unregistered(users)]);
+    drop(registry);
+    assert_eq!(seen.borrow().len(), 4);
+}
@@ -0,0 +1,178 @@
+//!
+//! This test is also an example of caching the `MBeanInfo` of MBeans.
+//!
+//! This test:
+//!
+//!   1. Connects to the test JMX server through a cache.
+//!   2. Caches immutable `MBeanInfo`s until the client reconnects, even behind the cache.
+//!   3. Expires mutable `MBeanInfo`s after a TTL on a mock server.
+//!   4. Drops the `MBeanInfo` of MBeans when they are unregistered.
+//!   5. Refuses an unlimited TTL for clients that can't tell when they reconnect.
+//!
+extern crate jmx;
+extern crate serde;
+
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use jmx::CachingClient;
+use jmx::CachingClientOptions;
+use jmx::MBeanAddress;
+use jmx::MBeanClientTrait;
+use jmx::MBeanInfo;
+use jmx::MBeanThreadedClient;
+use jmx::Result;
+use jmx::mock::MockMBeanClient;
+use jmx::registry::MBEAN_SERVER_DELEGATE_NAME;
+
+
+static JMX_PORT: u16 = 1646;
+
+
+#[test]
+fn caching_client() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+    run_mock_test();
+}
+
+fn run_test() {
+    let address = || MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let client = MBeanThreadedClient::connect(address()).expect("Failed to connect");
+    let options = CachingClientOptions::default().ttl(Duration::from_millis(1));
+    let client = CachingClient::with_options(client, options).unwrap();
+
+    // Platform MXBeans declare their info immutable so the TTL does not apply.
+    let info = client.get_mbean_info("java.lang:type=Memory").unwrap();
+    assert_eq!(info.descriptor.get("immutableInfo").map(String::as_str), Some("true"));
+    thread::sleep(Duration::from_millis(10));
+    assert!(client.is_cached("java.lang:type=Memory"));
+    assert_eq!(client.get_mbean_info("java.lang:type=Memory").unwrap(), info);
+
+    client.snapshot_mbean("FOO:name=ServerBean").unwrap();
+    assert!(client.is_cached("FOO:name=ServerBean"));
+
+    client.reconnect(address()).unwrap();
+    assert!(!client.is_cached("java.lang:type=Memory"));
+    assert_eq!(client.get_mbean_info("java.lang:type=Memory").unwrap(), info);
+
+    // Reconnections of the wrapped client are detected on the next lookup.
+    client.inner().reconnect(address()).unwrap();
+    assert!(client.is_cached("java.lang:type=Memory"));
+    client.get_mbean_info("FOO:name=ServerBean").unwrap();
+    assert!(!client.is_cached("java.lang:type=Memory"));
+}
+
+fn run_mock_test() {
+    let server = MockMBeanClient::new();
+    assert!(CachingClient::new(server.clone()).is_err());
+    server.register_mbean(MBEAN_SERVER_DELEGATE_NAME, MBeanInfo::default()).unwrap();
+
+    let mut descriptor = BTreeMap::new();
+    descriptor.insert("immutableInfo".to_string(), "true".to_string());
+    let immutable = MBeanInfo {
+        class_name: "Immutable".into(),
+        descriptor,
+        ..Default::default()
+    };
+    let mutable = MBeanInfo {
+        class_name: "Mutable".into(),
+        ..Default::default()
+    };
+    server.register_mbean("FOO:name=Immutable", immutable.clone()).unwrap();
+    server.register_mbean("FOO:name=Mutable", mutable.clone()).unwrap();
+
+    let options = CachingClientOptions::default().ttl(Duration::from_millis(100));
+    let client = CachingClient::with_options(server.clone(), options).unwrap();
+    assert_eq!(client.get_mbean_info("FOO:name=Immutable").unwrap(), immutable);
+    assert_eq!(client.get_mbean_info("FOO:name=Mutable").unwrap(), mutable);
+
+    // Cached entries are served without asking the server until they expire.
+    server.fail_mbean_info("FOO:name=Immutable", "boom").unwrap();
+    server.fail_mbean_info("FOO:name=Mutable", "boom").unwrap();
+    assert_eq!(client.get_mbean_info("FOO:name=Mutable").unwrap(), mutable);
+    thread::sleep(Duration::from_millis(150));
+    assert!(!client.is_cached("FOO:name=Mutable"));
+    assert!(client.get_mbean_info("FOO:name=Mutable").is_err());
+    assert_eq!(client.get_mbean_info("FOO:name=Immutable").unwrap(), immutable);
+
+    // Unregistered MBeans are dropped from the cache.
+    server.unregister_mbean("FOO:name=Immutable").unwrap();
+    assert!(client.get_mbean_info("FOO:name=Immutable").is_err());
+    assert!(!client.is_cached("FOO:name=Immutable"));
+
+    // Entries can be dropped explicitly.
+    server.clear_failures();
+    server.register_mbean("FOO:name=Immutable", immutable.clone()).unwrap();
+    client.get_mbean_info("FOO:name=Immutable").unwrap();
+    client.invalidate("FOO:name=Immutable").unwrap();
+    assert!(!client.is_cached("FOO:name=Immutable"));
+    client.get_mbean_info("FOO:name=Immutable").unwrap();
+    client.invalidate_all();
+    assert!(!client.is_cached("FOO:name=Immutable"));
+
+    // Without the delegate the cache only relies on the TTL.
+    let server = MockMBeanClient::new();
+    let options = CachingClientOptions::default().invalidate_on_unregister(false).ttl_unlimited();
+    let client = CachingClient::with_options(server.clone(), options).unwrap();
+    server.register_mbean("FOO:name=Mutable", mutable.clone()).unwrap();
+    client.get_mbean_info("FOO:name=Mutable").unwrap();
+    server.unregister_mbean("FOO:name=Mutable").unwrap();
+    assert_eq!(client.get_mbean_info("FOO:name=Mutable").unwrap(), mutable);
+
+    // Clients without a connection identifier may reconnect unnoticed.
+    let options = CachingClientOptions::default().invalidate_on_unregister(false).ttl_unlimited();
+    assert!(CachingClient::with_options(OpaqueClient(server.clone()), options).is_err());
+    let options = CachingClientOptions::default().invalidate_on_unregister(false);
+    let client = CachingClient::with_options(OpaqueClient(server), options).unwrap();
+    assert_eq!(client.connection_id(), None);
+}
+
+
+/// Client that does not implement `connection_id`.
+struct OpaqueClient(MockMBeanClient);
+
+impl MBeanClientTrait for OpaqueClient {
+    fn get_attribute<S1, S2, T>(&self, mbean: S1, attribute: S2) -> Result<T>
+        where S1: Into<String>,
+              S2: Into<String>,
+              T: DeserializeOwned,
+    {
+        self.0.get_attribute(mbean, attribute)
+    }
+
+    fn get_mbean_info<S>(&self, mbean: S) -> Result<MBeanInfo>
+        where S: Into<String>,
+    {
+        self.0.get_mbean_info(mbean)
+    }
+
+    fn query_names<S1, S2>(&self, name: S1, query: S2) -> Result<Vec<String>>
+        where S1: Into<String>,
+              S2: Into<String>,
+    {
+        self.0.query_names(name, query)
+    }
+}
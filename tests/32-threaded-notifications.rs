@@ -0,0 +1,90 @@
+//!
+//! This test is also an example of listening to notifications from a pool of workers.
+//!
+//! This test:
+//!
+//!   1. Connects a pool of workers to the test JMX server.
+//!   2. Listens to garbage collection notifications and waits for them.
+//!   3. Serves other requests while a poll waits for notifications.
+//!   4. Reconnects the workers and checks the old listener fails.
+//!
+extern crate jmx;
+
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClientTrait;
+use jmx::MBeanThreadedClient;
+use jmx::MBeanThreadedClientOptions;
+use jmx::platform::GC_NOTIFICATION;
+
+
+static JMX_PORT: u16 = 1647;
+static WORKERS: usize = 2;
+
+
+#[test]
+fn threaded_notifications() {
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=false")
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn run_test() {
+    let address = MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    let options = MBeanThreadedClientOptions::default().workers(WORKERS);
+    let client = MBeanThreadedClient::connect_with_options(address.clone(), options)
+        .expect("Failed to connect to the JMX server");
+    let client = Arc::new(client);
+
+    // Polls reach the worker that added the listener, whichever worker is picked next.
+    let listener = client.add_notification_listener(
+        "java.lang:type=GarbageCollector,*", vec![GC_NOTIFICATION.to_string()]
+    ).unwrap();
+    let _: () = client.invoke("java.lang:type=Memory", "gc", Vec::new()).unwrap();
+    let notifications = client.poll_notifications(listener, Duration::from_secs(5)).unwrap();
+    assert!(!notifications.is_empty());
+    for _ in 0..WORKERS {
+        assert!(client.poll_notifications(listener, Duration::from_millis(0)).is_ok());
+    }
+
+    // Waiting for notifications does not hold up the workers.
+    let waiting = Arc::clone(&client);
+    let poll = thread::spawn(move || {
+        waiting.poll_notifications(listener, Duration::from_secs(3)).unwrap()
+    });
+    thread::sleep(Duration::from_millis(200));
+    for _ in 0..WORKERS {
+        let start = Instant::now();
+        let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+        assert_eq!(threads, 16);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+    poll.join().unwrap();
+
+    // Listeners are removed with the connections they were added to.
+    client.reconnect(address).unwrap();
+    assert!(client.poll_notifications(listener, Duration::from_millis(0)).is_err());
+    assert!(client.remove_notification_listener(listener).is_err());
+}
@@ -0,0 +1,92 @@
+//!
+//! This test is also an example of connecting to a server that requires authentication.
+//!
+//! This test:
+//!
+//!   1. Starts a JMX server that requires a username and password.
+//!   2. Fails to connect without credentials.
+//!   3. Connects with `MBeanClientOptions` converted from threaded options with credentials.
+//!   4. Connects a threaded client with credentials.
+//!
+extern crate jmx;
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use jmx::MBeanAddress;
+use jmx::MBeanClient;
+use jmx::MBeanClientOptions;
+use jmx::MBeanClientTrait;
+use jmx::MBeanThreadedClient;
+use jmx::MBeanThreadedClientOptions;
+
+
+static JMX_PORT: u16 = 1648;
+
+
+#[test]
+fn credentials() {
+    // The JVM refuses password files readable by other users.
+    let dir = env::temp_dir().join(format!("jmx-rust-credentials-{}", JMX_PORT));
+    fs::create_dir_all(&dir).unwrap();
+    let access = write_file(&dir.join("jmxremote.access"), "monitor readonly\n");
+    let password = write_file(&dir.join("jmxremote.password"), "monitor secret\n");
+
+    // Start the server and wait for it to be up.
+    let mut server = Command::new("java")
+        .arg("-Dcom.sun.management.jmxremote")
+        .arg(format!("-Dcom.sun.management.jmxremote.port={}", JMX_PORT))
+        .arg("-Dcom.sun.management.jmxremote.authenticate=true")
+        .arg(format!("-Dcom.sun.management.jmxremote.access.file={}", access))
+        .arg(format!("-Dcom.sun.management.jmxremote.password.file={}", password))
+        .arg("-Dcom.sun.management.jmxremote.ssl=false")
+        .arg("TestServer")
+        .current_dir("tests/jmxserver")
+        .spawn()
+        .expect("Could not start JMX server");
+    thread::sleep(Duration::from_secs(1));
+
+    let result = ::std::panic::catch_unwind(run_test);
+
+    // Stop the server once we are done.
+    let _ = server.kill();
+    let _ = server.wait();
+    let _ = fs::remove_dir_all(&dir);
+    if let Err(error) = result {
+        ::std::panic::resume_unwind(error);
+    }
+}
+
+fn run_test() {
+    let address = || MBeanAddress::address(format!("localhost:{}", JMX_PORT));
+    assert!(MBeanClient::connect(address()).is_err());
+
+    // Credentials survive the conversion to single threaded options.
+    let options = MBeanThreadedClientOptions::default().credentials("monitor", "secret");
+    let client = MBeanClient::connect_with_options(address(), MBeanClientOptions::from(options))
+        .expect("Failed to connect with credentials");
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+
+    let options = MBeanThreadedClientOptions::default().credentials("monitor", "wrong");
+    assert!(MBeanClient::connect_with_options(address(), options.into()).is_err());
+
+    // Threaded clients connect each worker with the credentials.
+    let options = MBeanThreadedClientOptions::default().workers(2).credentials("monitor", "secret");
+    let client = MBeanThreadedClient::connect_with_options(address(), options)
+        .expect("Failed to connect with credentials");
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 16);
+}
+
+/// Write a file only its owner can read and return its path.
+fn write_file(path: &Path, content: &str) -> String {
+    fs::write(path, content).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).unwrap();
+    path.display().to_string()
+}
@@ -0,0 +1,131 @@
+//!
+//! This test is also an example of the basic use the library.
+//! The client side is limited to the body of the `run_test` function.
+//!
+//! This test:
+//!
+//!   1. Creates an in-memory mock MBean server, no JVM needed.
+//!   2. Registers MBeans with fixed and computed attributes.
+//!   3. Queries MBean names, information and attributes.
+//!   4. Injects errors and checks they are returned.
+//!   5. Snapshots an MBean.
+//!   6. Sets attributes and invokes operations.
+//!
+extern crate jmx;
+extern crate serde_json;
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use jmx::MBeanAttribute;
+use jmx::MBeanClientTrait;
+use jmx::MBeanInfo;
+use jmx::Result;
+use jmx::mock::MockMBeanClient;
+
+
+#[test]
+fn mock_client() {
+    run_test();
+}
+
+fn run_test() {
+    // Create a mock server and register some MBeans.
+    let client = MockMBeanClient::new();
+    let info = MBeanInfo {
+        attributes: vec![MBeanAttribute {
+            description: "Attribute exposed for management".into(),
+            is_readable: true,
+            is_writable: true,
+            name: "ThreadCount".into(),
+            type_name: "int".into(),
+            ..Default::default()
+        }],
+        class_name: "JmxServer".into(),
+        description: "Information on the management interface of the MBean".into(),
+        operations: Vec::new(),
+        ..Default::default()
+    };
+    client.register_mbean("FOO:name=ServerBean", info).unwrap();
+    client.register_attribute("FOO:name=ServerBean", "ThreadCount", 16).unwrap();
+    client.register_attribute("FOO:name=ServerBean", "SchemaName", "test").unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    client.register_attribute_fn("java.lang:type=Threading", "ThreadCount", move || {
+        Ok(serde_json::Value::from(counter.fetch_add(1, Ordering::SeqCst) + 1))
+    }).unwrap();
+    client.register_attribute("java.lang:type=Memory", "Verbose", false).unwrap();
+
+    // Fetch attributes and information.
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    let schema: String = client.get_attribute("FOO:name=ServerBean", "SchemaName").unwrap();
+    assert_eq!(threads, 16);
+    assert_eq!(schema, "test");
+    let count: u64 = client.get_attribute("java.lang:type=Threading", "ThreadCount").unwrap();
+    assert_eq!(count, 1);
+    let count: u64 = client.get_attribute("java.lang:type=Threading", "ThreadCount").unwrap();
+    assert_eq!(count, 2);
+    let mbean = client.get_mbean_info("FOO:name=ServerBean").unwrap();
+    assert_eq!(mbean.attributes.len(), 1);
+    assert_eq!(mbean.class_name, "JmxServer");
+
+    // Query MBean names.
+    let mut names = client.query_names("java.lang:*", "").unwrap();
+    names.sort();
+    assert_eq!(names, vec!["java.lang:type=Memory", "java.lang:type=Threading"]);
+    let names = client.query_names("*:name=Server*", "").unwrap();
+    assert_eq!(names, vec!["FOO:name=ServerBean"]);
+    let names = client.query_names("*:*", "java.lang:type=Mem?ry").unwrap();
+    assert_eq!(names, vec!["java.lang:type=Memory"]);
+    assert!(client.query_names("java.lang:type=*", "").unwrap().len() == 2);
+    assert!(client.query_names("java.lang:name=*", "").unwrap().is_empty());
+
+    // Inject errors.
+    client.fail_attribute("FOO:name=ServerBean", "SchemaName", "boom").unwrap();
+    client.fail_mbean_info("FOO:name=ServerBean", "no info").unwrap();
+    client.fail_query_names("no query");
+    let schema: Result<String> = client.get_attribute("FOO:name=ServerBean", "SchemaName");
+    assert_eq!(format!("{}", schema.unwrap_err()), "mock client failure: boom");
+    assert!(client.get_mbean_info("FOO:name=ServerBean").is_err());
+    assert!(client.query_names("*:*", "").is_err());
+    let missing: Result<i32> = client.get_attribute("FOO:name=Missing", "ThreadCount");
+    assert!(missing.is_err());
+    let wrong_type: Result<i32> = client.get_attribute("java.lang:type=Memory", "Verbose");
+    assert!(wrong_type.is_err());
+    client.clear_failures();
+    assert!(client.get_mbean_info("FOO:name=ServerBean").is_ok());
+    assert_eq!(client.query_names("*:*", "").unwrap().len(), 3);
+
+    // Snapshot MBeans attribute by attribute.
+    let snapshot = client.snapshot_mbean("FOO:name=ServerBean").unwrap();
+    assert_eq!(snapshot.attributes["ThreadCount"], 16);
+    assert!(snapshot.errors.is_empty());
+    assert_eq!(snapshot.info.class_name, "JmxServer");
+
+    // Inject latency.
+    client.latency(Some(Duration::from_millis(50)));
+    let start = Instant::now();
+    let _: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    client.latency(None);
+
+    // Set attributes and invoke operations.
+    client.set_attribute("FOO:name=ServerBean", "ThreadCount", 20).unwrap();
+    let threads: i32 = client.get_attribute("FOO:name=ServerBean", "ThreadCount").unwrap();
+    assert_eq!(threads, 20);
+    assert!(client.set_attribute("java.lang:type=Threading", "ThreadCount", 1).is_err());
+    client.register_operation("FOO:name=ServerBean", "resize", |params| {
+        Ok(serde_json::Value::from(params.len()))
+    }).unwrap();
+    let params = vec![serde_json::Value::from(1), serde_json::Value::from(2)];
+    let result: usize = client.invoke("FOO:name=ServerBean", "resize", params).unwrap();
+    assert_eq!(result, 2);
+    assert!(client.invoke::<_, _, usize>("FOO:name=ServerBean", "missing", Vec::new()).is_err());
+
+    // Unregister MBeans.
+    client.unregister_mbean("FOO:name=ServerBean").unwrap();
+    assert_eq!(client.query_names("*:*", "").unwrap().len(), 2);
+}
@@ -4,7 +4,7 @@ import java.lang.management.*;
 public class TestServer {
   private MBeanServer mbs = null;
 
-  public TestServer() {
+  public TestServer(int extraBeans) {
     mbs = ManagementFactory.getPlatformMBeanServer();
 
     // Unique identification of MBeans
@@ -15,6 +15,12 @@ public class TestServer {
       // Uniquely identify the MBeans and register them with the platform MBeanServer
       serverName = new ObjectName("FOO:name=ServerBean");
       mbs.registerMBean(serverBean, serverName);
+
+      // Register additional beans to benchmark requests on large servers.
+      for (int id = 0; id < extraBeans; id++) {
+        ObjectName name = new ObjectName("BENCH:type=ServerBean,id=" + id);
+        mbs.registerMBean(new JmxServer(id, "bench"), name);
+      }
     } catch(Exception e) {
       e.printStackTrace();
     }
@@ -32,7 +38,8 @@ public class TestServer {
   }
 
   public static void main(String argv[]) {
-    TestServer server = new TestServer();
+    int extraBeans = argv.length > 0 ? Integer.parseInt(argv[0]) : 0;
+    TestServer server = new TestServer(extraBeans);
     System.out.println("TestServer is running...");
     TestServer.waitAWhile();
   }